@@ -0,0 +1,110 @@
+//! Scanner throughput and allocation benchmark.
+//!
+//! Scans a multi-megabyte generated program and reports how fast the
+//! scanner goes and how much it allocates per scan. The `owned` row copies
+//! every lexeme into a `String`, as tokens did before they borrowed from
+//! the source, to show what borrowing saves. Run it with
+//! `cargo bench --bench lexer`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use nd_lang::scanner::scanner::Scanner;
+
+/// Wraps the system allocator to count allocations and allocated bytes
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const INPUT_SIZE: usize = 4 * 1024 * 1024;
+const RUNS: usize = 5;
+
+/// A snippet using every kind of token, repeated to build the input
+const UNIT: &str = "let total: i32 = (alpha + beta_2) * 42.5 ^ 3 % gamma;
+if total >= 10 { total += \"some string\"; } else { show = !flag; }
+// a comment line
+";
+
+/// How tokens are scanned in a run
+#[derive(Clone, Copy)]
+enum Mode {
+    Borrowed,
+    /// Each lexeme is copied out of the source
+    Owned,
+    Lossless,
+}
+
+struct Run {
+    tokens: usize,
+    elapsed: Duration,
+    allocations: usize,
+    bytes: usize,
+}
+
+fn scan(source: &str, mode: Mode) -> Run {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let start = Instant::now();
+
+    let tokens = match mode {
+        Mode::Borrowed => Scanner::new(source, "bench".to_string()).count(),
+        Mode::Owned => Scanner::new(source, "bench".to_string()).fold(0, |tokens, token| {
+            let _ = black_box(token.map(|token| token.lexeme.to_owned()));
+            tokens + 1
+        }),
+        Mode::Lossless => Scanner::new_lossless(source, "bench".to_string()).count(),
+    };
+
+    Run {
+        tokens,
+        elapsed: start.elapsed(),
+        allocations: ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        bytes: ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes,
+    }
+}
+
+fn report(name: &str, source: &str, mode: Mode) {
+    // The first scan warms up the symbol table and the caches
+    scan(source, mode);
+    let runs: Vec<Run> = (0..RUNS).map(|_| scan(source, mode)).collect();
+    let best = runs.iter().min_by_key(|run| run.elapsed).unwrap();
+
+    println!(
+        "{:<10} {:>8.1} MB/s {:>9} tokens {:>9} allocations {:>12} bytes allocated",
+        name,
+        source.len() as f64 / 1e6 / best.elapsed.as_secs_f64(),
+        best.tokens,
+        best.allocations,
+        best.bytes
+    );
+}
+
+fn main() {
+    let mut source = String::with_capacity(INPUT_SIZE + UNIT.len());
+    while source.len() < INPUT_SIZE {
+        source.push_str(UNIT);
+    }
+    println!("Scanning {} bytes, best of {} runs", source.len(), RUNS);
+
+    report("scan", &source, Mode::Borrowed);
+    report("owned", &source, Mode::Owned);
+    report("lossless", &source, Mode::Lossless);
+}
@@ -0,0 +1,184 @@
+use core::fmt;
+
+use crate::tokens::token::Token;
+
+use super::{expr::Expr, stmt::Stmt};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyntaxKind {
+    Program,
+
+    // Statements
+    ExpressionStmt,
+    BlockStmt,
+    IfStmt,
+    VarDeclarationStmt,
+    ReturnStmt,
+    OperatorDeclStmt,
+    ImportStmt,
+    ExportStmt,
+    EnumDeclStmt,
+    TryStmt,
+    ThrowStmt,
+    TestStmt,
+
+    // Expressions
+    Number,
+    String,
+    Bool,
+    Null,
+    Identifier,
+    Unary,
+    BinaryOp,
+    Assignment,
+    Grouping,
+    Function,
+    Call,
+    Member,
+    List,
+    Index,
+    Propagate,
+    Match,
+}
+
+impl From<&Expr> for SyntaxKind {
+    fn from(expr: &Expr) -> Self {
+        match expr {
+            Expr::Number(_) => SyntaxKind::Number,
+            Expr::String(_) => SyntaxKind::String,
+            Expr::Bool(_) => SyntaxKind::Bool,
+            Expr::Null => SyntaxKind::Null,
+            Expr::Identifier(_) => SyntaxKind::Identifier,
+            Expr::Unary { .. } => SyntaxKind::Unary,
+            Expr::BinaryOp { .. } => SyntaxKind::BinaryOp,
+            Expr::Assignment { .. } => SyntaxKind::Assignment,
+            Expr::Grouping { .. } => SyntaxKind::Grouping,
+            Expr::Function { .. } => SyntaxKind::Function,
+            Expr::Call { .. } => SyntaxKind::Call,
+            Expr::Member { .. } => SyntaxKind::Member,
+            Expr::List(_) => SyntaxKind::List,
+            Expr::Index { .. } => SyntaxKind::Index,
+            Expr::Propagate { .. } => SyntaxKind::Propagate,
+            Expr::Match { .. } => SyntaxKind::Match,
+        }
+    }
+}
+
+impl From<&Stmt> for SyntaxKind {
+    fn from(stmt: &Stmt) -> Self {
+        match stmt {
+            Stmt::ExpressionStmt { .. } => SyntaxKind::ExpressionStmt,
+            Stmt::BlockStmt { .. } => SyntaxKind::BlockStmt,
+            Stmt::IfStmt { .. } => SyntaxKind::IfStmt,
+            Stmt::VarDeclarationStmt { .. } => SyntaxKind::VarDeclarationStmt,
+            Stmt::ReturnStmt { .. } => SyntaxKind::ReturnStmt,
+            Stmt::OperatorDeclStmt { .. } => SyntaxKind::OperatorDeclStmt,
+            Stmt::ImportStmt { .. } => SyntaxKind::ImportStmt,
+            Stmt::ExportStmt { .. } => SyntaxKind::ExportStmt,
+            Stmt::EnumDeclStmt { .. } => SyntaxKind::EnumDeclStmt,
+            Stmt::TryStmt { .. } => SyntaxKind::TryStmt,
+            Stmt::ThrowStmt { .. } => SyntaxKind::ThrowStmt,
+            Stmt::TestStmt { .. } => SyntaxKind::TestStmt,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement<'src> {
+    Node(SyntaxNode<'src>),
+    Token(Token<'src>),
+}
+
+/// A node of the concrete syntax tree. Unlike `Stmt`/`Expr`, it keeps
+/// every token the parser consumed, trivia included, so printing it
+/// reproduces the source byte-for-byte.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxNode<'src> {
+    pub kind: SyntaxKind,
+    pub children: Vec<SyntaxElement<'src>>,
+}
+
+impl<'src> SyntaxNode<'src> {
+    pub fn new(kind: SyntaxKind, children: Vec<SyntaxElement<'src>>) -> Self {
+        Self { kind, children }
+    }
+
+    ///Returns the tokens under this node, in source order
+    pub fn tokens(&self) -> Vec<&Token<'src>> {
+        let mut tokens = Vec::new();
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+
+    ///Returns the tree as an indented outline, one node or token per line
+    pub fn dump(&self) -> String {
+        let mut out = String::new();
+        self.dump_into(&mut out, 0);
+        out
+    }
+
+    fn dump_into(&self, out: &mut String, depth: usize) {
+        out.push_str(&format!("{}{:?}\n", "  ".repeat(depth), self.kind));
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => node.dump_into(out, depth + 1),
+                SyntaxElement::Token(token) => out.push_str(&format!(
+                    "{}{:?} {:?}\n",
+                    "  ".repeat(depth + 1),
+                    token.ttype,
+                    token.source_text()
+                )),
+            }
+        }
+    }
+}
+
+impl fmt::Display for SyntaxNode<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for token in self.tokens() {
+            write!(f, "{}", token.full_text())?;
+        }
+        Ok(())
+    }
+}
+
+/// Assembles a `SyntaxNode` tree while the parser runs. A node is only
+/// created once its kind is known, by grouping everything pushed since a
+/// checkpoint, which lets Pratt led handlers wrap an already parsed
+/// left-hand side.
+#[derive(Default)]
+pub struct CstBuilder<'src> {
+    children: Vec<SyntaxElement<'src>>,
+}
+
+impl<'src> CstBuilder<'src> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///Returns a position that a node can later be started at
+    pub fn checkpoint(&self) -> usize {
+        self.children.len()
+    }
+
+    ///Wraps everything pushed since `checkpoint` into a node of `kind`
+    pub fn finish_node_at(&mut self, checkpoint: usize, kind: SyntaxKind) {
+        let children = self.children.split_off(checkpoint);
+        self.children
+            .push(SyntaxElement::Node(SyntaxNode::new(kind, children)));
+    }
+
+    pub fn push_token(&mut self, token: Token<'src>) {
+        self.children.push(SyntaxElement::Token(token));
+    }
+
+    ///Returns the root node holding everything pushed so far
+    pub fn finish(self) -> SyntaxNode<'src> {
+        SyntaxNode::new(SyntaxKind::Program, self.children)
+    }
+}
@@ -1,43 +1,122 @@
 use core::fmt;
+use std::rc::Rc;
 
-use crate::tokens::token::Token;
+use crate::tokens::{symbol::Symbol, token::Token};
 
-#[derive(Debug, PartialEq)] // Add PartialEq derive
+use super::{pattern::MatchArm, stmt::Stmt};
+
+#[derive(Debug, Clone, PartialEq)] // Add PartialEq derive
 pub enum Expr {
     // --------------------
     // Literal Expressions
     // --------------------
     Number(String),
     String(String),
-    Identifier(String),
+    Bool(bool),
+    Null,
+    Identifier(Symbol),
+    /// `[a, b, c]`
+    List(Vec<Expr>),
 
     // --------------------
     // Complex Expressions
     // --------------------
     Unary {
-        op: Token,
+        op: Token<'static>,
         right: Box<Expr>,
     },
     BinaryOp {
         left: Box<Expr>,
-        op: Token,
+        op: Token<'static>,
         right: Box<Expr>,
     },
     Assignment {
         assignee: Box<Expr>,
-        op: Token,
+        op: Token<'static>,
         assigned: Box<Expr>,
     },
     Grouping {
         group: Box<Expr>,
+    },
+    /// `func(a, b) { ... }`. The body is shared with the function values
+    /// created from it.
+    Function {
+        params: Vec<Symbol>,
+        body: Rc<Vec<Stmt>>,
+        line: usize,
+        end_line: usize,
+    },
+    Call {
+        callee: Box<Expr>,
+        args: Vec<Expr>,
+        line: usize,
+        /// Where the call starts on its line, for stack traces
+        position: usize,
+    },
+    /// `items[0]`
+    Index {
+        object: Box<Expr>,
+        index: Box<Expr>,
+    },
+    /// `util.name`, reading an export of an imported module, or
+    /// `text.upper`, a method of a string
+    Member {
+        object: Box<Expr>,
+        property: Symbol,
+    },
+    /// `value?`: the value held by an `Ok`/`Some`, or else an early
+    /// return of the `Err`/`None`
+    Propagate {
+        value: Box<Expr>,
+        line: usize,
+    },
+    /// `match value { pattern => result, ... }`
+    Match {
+        value: Box<Expr>,
+        arms: Vec<MatchArm>,
+        line: usize,
+        end_line: usize,
     }, // Add more node types as needed
 }
 
+impl Expr {
+    ///Returns the last line the expression spans, if it is known. Only
+    ///function literals and matches record their lines.
+    pub fn end_line(&self) -> Option<usize> {
+        match self {
+            Expr::Number(_)
+            | Expr::String(_)
+            | Expr::Bool(_)
+            | Expr::Null
+            | Expr::Identifier(_) => None,
+            Expr::Unary { right, .. } => right.end_line(),
+            Expr::BinaryOp { left, right, .. } => right.end_line().or(left.end_line()),
+            Expr::Assignment {
+                assignee, assigned, ..
+            } => assigned.end_line().or(assignee.end_line()),
+            Expr::Grouping { group } => group.end_line(),
+            Expr::Member { object, .. } | Expr::Propagate { value: object, .. } => {
+                object.end_line()
+            }
+            Expr::List(items) => items.iter().rev().find_map(Expr::end_line),
+            Expr::Index { object, index } => index.end_line().or(object.end_line()),
+            Expr::Function { end_line, .. } | Expr::Match { end_line, .. } => Some(*end_line),
+            Expr::Call { callee, args, .. } => args
+                .iter()
+                .rev()
+                .find_map(Expr::end_line)
+                .or(callee.end_line()),
+        }
+    }
+}
+
 impl fmt::Display for Expr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Expr::Number(x) => write!(f, "{}", x),
             Expr::String(x) => write!(f, "\"{}\"", x),
+            Expr::Bool(x) => write!(f, "{}", x),
+            Expr::Null => write!(f, "null"),
             Expr::Unary { op, right } => write!(f, "({}{})", op.lexeme, right),
             Expr::Identifier(s) => write!(f, "{}", s),
             Expr::BinaryOp { left, op, right } => write!(f, "({} {} {})", left, op.lexeme, right),
@@ -48,7 +127,24 @@ impl fmt::Display for Expr {
                 assigned,
             } => {
                 write!(f, "Assigned {} {} {}", assignee, op.lexeme, assigned)
-            } // Add more match arms for other node types if needed
+            }
+            Expr::Function { params, .. } => write!(f, "func({}) {{...}}", join(params)),
+            Expr::Call { callee, args, .. } => write!(f, "{}({})", callee, join(args)),
+            Expr::Member { object, property } => write!(f, "{}.{}", object, property),
+            Expr::List(items) => write!(f, "[{}]", join(items)),
+            Expr::Index { object, index } => write!(f, "{}[{}]", object, index),
+            Expr::Propagate { value, .. } => write!(f, "{}?", value),
+            Expr::Match { value, arms, .. } => write!(f, "match {} {{ {} }}", value, join(arms)),
+            // Add more match arms for other node types if needed
         }
     }
 }
+
+///Joins items with `, `, as in parameter and argument lists
+pub fn join<T: fmt::Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
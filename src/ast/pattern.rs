@@ -0,0 +1,137 @@
+use core::fmt;
+
+use crate::{stdlib::prelude::variant_name, tokens::symbol::Symbol};
+
+use super::expr::{join, Expr};
+
+/// What a `match` arm compares its value against.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    /// `_`, matching anything
+    Wildcard,
+    /// `name`, matching anything and binding it to `name`
+    Binding(Symbol),
+    /// A number, string, `true`, `false` or `null`, matching values equal
+    /// to it
+    Literal(Expr),
+    /// `1..10` or `"a"..="z"`, matching numbers or strings between the two
+    Range {
+        start: Expr,
+        end: Expr,
+        inclusive: bool,
+    },
+    /// `[first, second]` or `[first, ..rest]`, matching lists of the same
+    /// length, or of at least as many items when there is a rest pattern.
+    /// `(first, second)` is the same pattern written as a tuple.
+    List {
+        items: Vec<Pattern>,
+        rest: Option<Rest>,
+    },
+    /// `Shape.Circle(r)` or `State.Idle`, matching values of that variant
+    /// whose fields match the patterns in parentheses
+    Variant {
+        enum_name: Symbol,
+        variant: Symbol,
+        fields: Vec<Pattern>,
+    },
+}
+
+/// The `..` or `..name` at the end of a list pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rest {
+    /// The name the remaining items are bound to, as a list
+    pub binding: Option<Symbol>,
+}
+
+/// `pattern if guard => body`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expr>,
+    pub body: Expr,
+    pub line: usize,
+    /// Where the arm starts on its line, for diagnostics
+    pub position: usize,
+}
+
+impl Pattern {
+    ///Checks whether the pattern matches every value
+    pub fn is_irrefutable(&self) -> bool {
+        matches!(self, Pattern::Wildcard | Pattern::Binding(_))
+    }
+
+    ///Returns the names the pattern binds, in order
+    pub fn bindings(&self) -> Vec<Symbol> {
+        match self {
+            Pattern::Binding(name) => vec![*name],
+            Pattern::List { items, rest } => items
+                .iter()
+                .flat_map(Pattern::bindings)
+                .chain(rest.as_ref().and_then(|rest| rest.binding))
+                .collect(),
+            Pattern::Variant { fields, .. } => fields.iter().flat_map(Pattern::bindings).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pattern::Wildcard => write!(f, "_"),
+            Pattern::Binding(name) => write!(f, "{}", name),
+            Pattern::Literal(value) => write!(f, "{}", constant(value)),
+            Pattern::Range {
+                start,
+                end,
+                inclusive,
+            } => write!(
+                f,
+                "{}{}{}",
+                constant(start),
+                if *inclusive { "..=" } else { ".." },
+                constant(end)
+            ),
+            Pattern::List { items, rest } => {
+                let mut items: Vec<String> = items.iter().map(Pattern::to_string).collect();
+                if let Some(rest) = rest {
+                    items.push(match rest.binding {
+                        Some(name) => format!("..{}", name),
+                        None => "..".to_string(),
+                    });
+                }
+                write!(f, "[{}]", join(&items))
+            }
+            Pattern::Variant {
+                enum_name,
+                variant,
+                fields,
+            } => {
+                write!(f, "{}", variant_name(*enum_name, *variant))?;
+                if !fields.is_empty() {
+                    write!(f, "({})", join(fields))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+///Formats the constant of a literal or range pattern, which is written
+///without the parentheses `Expr` puts around negative numbers
+fn constant(expr: &Expr) -> String {
+    match expr {
+        Expr::Unary { op, right } => format!("{}{}", op.lexeme, right),
+        expr => expr.to_string(),
+    }
+}
+
+impl fmt::Display for MatchArm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.pattern)?;
+        if let Some(guard) = &self.guard {
+            write!(f, " if {}", guard)?;
+        }
+        write!(f, " => {}", self.body)
+    }
+}
@@ -1,36 +1,182 @@
 use core::fmt;
+use std::rc::Rc;
 
-use crate::tokens::token::Token;
+use crate::{
+    parser::lookups::Fixity,
+    tokens::{symbol::Symbol, token::Token},
+};
 
-use super::expr::Expr;
+use super::expr::{join, Expr};
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum Stmt {
     ExpressionStmt {
         expression: Expr,
+        line: usize,
     },
     BlockStmt {
         body: Vec<Stmt>,
+        line: usize,
+        end_line: usize,
     },
     IfStmt {
         condition: Expr,
         consequent: Box<Stmt>,
         alternate: Option<Box<Stmt>>,
+        line: usize,
     },
     VarDeclarationStmt {
-        identifier: String,
+        identifier: Symbol,
         isConstant: bool,
         assignedValue: Expr,
-        explicitType: Option<Token>,
+        explicitType: Option<Token<'static>>,
+        line: usize,
+    },
+    ReturnStmt {
+        value: Option<Expr>,
+        line: usize,
+    },
+    /// `infix <|> 6 left = func(a, b) { ... };` or
+    /// `prefix ~ = func(a) { ... };`
+    OperatorDeclStmt {
+        operator: Token<'static>,
+        fixity: Fixity,
+        function: Expr,
+        line: usize,
+    },
+    /// `import "util.ndl" as util;` or `import { a, b } from "lib";`
+    ImportStmt {
+        path: String,
+        import: Import,
+        line: usize,
+    },
+    /// `export let x = ...;`
+    ExportStmt {
+        declaration: Box<Stmt>,
+        line: usize,
+    },
+    /// `try { ... } catch (e) { ... } finally { ... }`, with at least one
+    /// of `catch` and `finally`. The blocks are `BlockStmt`s.
+    TryStmt {
+        body: Box<Stmt>,
+        catch: Option<Catch>,
+        finally: Option<Box<Stmt>>,
+        line: usize,
+    },
+    /// `throw value;`
+    ThrowStmt {
+        value: Expr,
+        line: usize,
+    },
+    /// `test "name" { ... }`, run by `ND-Lang test` and skipped
+    /// otherwise. The body is a `BlockStmt`.
+    TestStmt {
+        name: String,
+        body: Box<Stmt>,
+        line: usize,
+    },
+    /// `enum Shape { Circle(r), Rect(w, h) }`
+    EnumDeclStmt {
+        name: Symbol,
+        variants: Rc<Vec<Variant>>,
+        line: usize,
+        end_line: usize,
     },
 }
 
+/// The `catch (name) { ... }` of a `try`. The name is optional.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Catch {
+    pub name: Option<Symbol>,
+    pub body: Box<Stmt>,
+}
+
+/// One variant of an `enum`, with the names of its fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    pub name: Symbol,
+    pub fields: Vec<Symbol>,
+    pub line: usize,
+}
+
+/// What an `import` binds in the importing file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Import {
+    /// The whole module, under the name given after `as`
+    Module(Symbol),
+    /// The listed exports, under their own names
+    Names(Vec<Symbol>),
+}
+
+impl Stmt {
+    ///Returns the line the statement starts on
+    pub fn line(&self) -> usize {
+        match self {
+            Stmt::ExpressionStmt { line, .. }
+            | Stmt::BlockStmt { line, .. }
+            | Stmt::IfStmt { line, .. }
+            | Stmt::VarDeclarationStmt { line, .. }
+            | Stmt::ReturnStmt { line, .. }
+            | Stmt::OperatorDeclStmt { line, .. }
+            | Stmt::ImportStmt { line, .. }
+            | Stmt::ExportStmt { line, .. }
+            | Stmt::EnumDeclStmt { line, .. }
+            | Stmt::TryStmt { line, .. }
+            | Stmt::ThrowStmt { line, .. }
+            | Stmt::TestStmt { line, .. } => *line,
+        }
+    }
+
+    ///Returns the last line the statement spans
+    pub fn end_line(&self) -> usize {
+        match self {
+            Stmt::BlockStmt { end_line, .. } | Stmt::EnumDeclStmt { end_line, .. } => *end_line,
+            Stmt::IfStmt {
+                consequent,
+                alternate,
+                ..
+            } => match alternate {
+                Some(alternate) => alternate.end_line(),
+                None => consequent.end_line(),
+            },
+            Stmt::TryStmt {
+                body,
+                catch,
+                finally,
+                ..
+            } => match (finally, catch) {
+                (Some(finally), _) => finally.end_line(),
+                (None, Some(catch)) => catch.body.end_line(),
+                (None, None) => body.end_line(),
+            },
+            Stmt::ExpressionStmt {
+                expression: value, ..
+            }
+            | Stmt::ThrowStmt { value, .. }
+            | Stmt::VarDeclarationStmt {
+                assignedValue: value,
+                ..
+            }
+            | Stmt::OperatorDeclStmt {
+                function: value, ..
+            }
+            | Stmt::ReturnStmt {
+                value: Some(value), ..
+            } => value.end_line().unwrap_or(self.line()),
+            Stmt::ReturnStmt { value: None, line } | Stmt::ImportStmt { line, .. } => *line,
+            Stmt::ExportStmt { declaration, .. } => declaration.end_line(),
+            Stmt::TestStmt { body, .. } => body.end_line(),
+        }
+    }
+}
+
 impl fmt::Display for Stmt {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Stmt::ExpressionStmt { expression } => {
+            Stmt::ExpressionStmt { expression, .. } => {
                 write!(f, "{}", expression)
             }
-            Stmt::BlockStmt { body } => {
+            Stmt::BlockStmt { body, .. } => {
                 writeln!(f, "Block Stmt: {{")?;
                 for stmt in body {
                     writeln!(f, "    {}", stmt)?;
@@ -41,6 +187,7 @@ impl fmt::Display for Stmt {
                 condition,
                 consequent,
                 alternate,
+                ..
             } => {
                 writeln!(f, "If Statement:")?;
                 writeln!(f, "    Condition: {}", condition)?;
@@ -59,6 +206,7 @@ impl fmt::Display for Stmt {
                 isConstant,
                 assignedValue,
                 explicitType,
+                ..
             } => {
                 write!(
                     f,
@@ -73,6 +221,56 @@ impl fmt::Display for Stmt {
                     assignedValue
                 )
             }
+            Stmt::ReturnStmt { value, .. } => match value {
+                Some(value) => write!(f, "ret {}", value),
+                None => write!(f, "ret"),
+            },
+            Stmt::OperatorDeclStmt {
+                operator,
+                fixity,
+                function,
+                ..
+            } => write!(f, "{} = {}", fixity.declaration(operator.lexeme), function),
+            Stmt::ImportStmt { path, import, .. } => match import {
+                Import::Module(alias) => write!(f, "import \"{}\" as {}", path, alias),
+                Import::Names(names) => {
+                    write!(f, "import {{ {} }} from \"{}\"", join(names), path)
+                }
+            },
+            Stmt::ExportStmt { declaration, .. } => write!(f, "export {}", declaration),
+            Stmt::EnumDeclStmt { name, variants, .. } => {
+                write!(f, "enum {} {{ {} }}", name, join(variants))
+            }
+            Stmt::TryStmt {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                write!(f, "try {}", body)?;
+                if let Some(catch) = catch {
+                    match catch.name {
+                        Some(name) => write!(f, " catch ({}) {}", name, catch.body)?,
+                        None => write!(f, " catch {}", catch.body)?,
+                    }
+                }
+                if let Some(finally) = finally {
+                    write!(f, " finally {}", finally)?;
+                }
+                Ok(())
+            }
+            Stmt::ThrowStmt { value, .. } => write!(f, "throw {}", value),
+            Stmt::TestStmt { name, body, .. } => write!(f, "test \"{}\" {}", name, body),
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.fields.is_empty() {
+            write!(f, "{}", self.name)
+        } else {
+            write!(f, "{}({})", self.name, join(&self.fields))
         }
     }
 }
@@ -0,0 +1,289 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+};
+
+use crate::{
+    ast::{expr::Expr, stmt::Stmt},
+    errors::diagnostic::Diagnostic,
+    interpreter::{
+        environment::Environment,
+        interpreter::{Interpreter, StackFrame},
+    },
+    object::object::Object,
+    parser::{lookups::PREC, parser::Parser},
+    scanner::scanner::Scanner,
+    tokens::symbol::Symbol,
+};
+
+use super::debugger::{DebugHook, Debugger, PauseReason, Step};
+
+/// The message of the error a program stops with when the user quits
+pub const STOPPED: &str = "Stopped by the debugger";
+
+const HELP: &str = "\
+break [FILE:]LINE    pause when LINE is reached (b)
+delete [FILE:]LINE   remove a breakpoint (d)
+breakpoints          list the breakpoints
+continue             run until the next breakpoint (c)
+step                 run the next statement, going into calls (s)
+next                 run the next statement, stepping over calls (n)
+finish               run until the current call returns (o)
+print EXPR           evaluate EXPR in the paused frame (p)
+watch EXPR           show EXPR at every pause (w)
+unwatch N            stop showing watch N
+locals               show the local variables
+globals              show the global variables
+backtrace            show the calls being run (bt)
+list                 show the code around the paused line (l)
+quit                 stop the program (q)";
+
+/// `ND-Lang debug`: pauses the program, shows where it is and reads
+/// commands until told to go on. Lines count from 1, as in editors.
+pub struct CliDebugger<R, W> {
+    debugger: Debugger,
+    /// The file being debugged, for breakpoints given by line only
+    file: String,
+    input: R,
+    output: W,
+    watches: Vec<String>,
+    /// The lines of the files shown so far, by name
+    sources: HashMap<String, Option<Vec<String>>>,
+    /// Set once the input ends, to let the program run to the end
+    detached: bool,
+}
+
+impl<R: BufRead, W: Write> CliDebugger<R, W> {
+    pub fn new(file: &str, input: R, output: W) -> Self {
+        Self {
+            debugger: Debugger::new(),
+            file: file.to_string(),
+            input,
+            output,
+            watches: Vec::new(),
+            sources: HashMap::new(),
+            detached: false,
+        }
+    }
+
+    ///Runs commands until one resumes the program
+    fn prompt(&mut self, interpreter: &mut Interpreter) -> Result<(), Diagnostic> {
+        loop {
+            let _ = write!(self.output, "(ndb) ");
+            let _ = self.output.flush();
+            let mut line = String::new();
+            if matches!(self.input.read_line(&mut line), Ok(0) | Err(_)) {
+                self.detached = true;
+                return Ok(());
+            }
+            let line = line.trim();
+            let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+            let argument = argument.trim();
+            let depth = interpreter.depth();
+
+            let step = match command {
+                "c" | "continue" => Some(Step::Continue),
+                "s" | "step" => Some(Step::In),
+                "n" | "next" => Some(Step::Over { depth }),
+                "o" | "finish" => Some(Step::Out { depth }),
+                _ => None,
+            };
+            if let Some(step) = step {
+                self.debugger.resume(step);
+                return Ok(());
+            }
+
+            match command {
+                "" => continue,
+                "q" | "quit" => {
+                    return Err(Diagnostic::new(0, 0, STOPPED.to_string(), "".to_string()))
+                }
+                "b" | "break" => self.breakpoint(argument, true),
+                "d" | "delete" => self.breakpoint(argument, false),
+                "breakpoints" => {
+                    let lines: Vec<String> = self
+                        .debugger
+                        .breakpoints()
+                        .iter()
+                        .map(|breakpoint| format!("{}:{}", breakpoint.file, breakpoint.line))
+                        .collect();
+                    self.print_list(&lines, "No breakpoints");
+                }
+                "p" | "print" => {
+                    let environment = interpreter.stack()[0].environment.clone();
+                    let value = evaluate(interpreter, environment, argument);
+                    self.print(&value);
+                }
+                "w" | "watch" => {
+                    self.watches.push(argument.to_string());
+                    self.print(&format!("Watch {}: {}", self.watches.len(), argument));
+                }
+                "unwatch" => match argument.parse::<usize>() {
+                    Ok(number) if (1..=self.watches.len()).contains(&number) => {
+                        self.watches.remove(number - 1);
+                    }
+                    _ => self.print(&format!("No watch {}", argument)),
+                },
+                "locals" => {
+                    let frame = &interpreter.stack()[0];
+                    let lines = variables(interpreter.locals(frame));
+                    self.print_list(&lines, "No local variables");
+                }
+                "globals" => {
+                    let lines = variables(interpreter.globals().bindings());
+                    self.print_list(&lines, "No global variables");
+                }
+                "bt" | "backtrace" => {
+                    let lines: Vec<String> = interpreter
+                        .stack()
+                        .iter()
+                        .enumerate()
+                        .map(|(number, frame)| {
+                            format!("#{} {} ({})", number, frame.name, place(frame))
+                        })
+                        .collect();
+                    self.print_list(&lines, "");
+                }
+                "l" | "list" => {
+                    let frame = &interpreter.stack()[0];
+                    self.list(&frame.source, frame.line + 1, 2);
+                }
+                "h" | "help" => self.print(HELP),
+                _ => self.print(&format!("Unknown command \"{}\", try \"help\"", command)),
+            }
+        }
+    }
+
+    ///Handles `break` and `delete`
+    fn breakpoint(&mut self, argument: &str, add: bool) {
+        let (file, line) = match argument.rsplit_once(':') {
+            Some((file, line)) => (file.to_string(), line),
+            None => (self.file.clone(), argument),
+        };
+        let Ok(line) = line.parse::<usize>() else {
+            return self.print("Expected [FILE:]LINE");
+        };
+        let message = match (add, add && self.debugger.add_breakpoint(&file, line)) {
+            (true, true) => format!("Breakpoint set at {}:{}", file, line),
+            (true, false) => format!("There already is a breakpoint at {}:{}", file, line),
+            (false, _) if self.debugger.remove_breakpoint(&file, line) => {
+                format!("Breakpoint removed from {}:{}", file, line)
+            }
+            (false, _) => format!("No breakpoint at {}:{}", file, line),
+        };
+        self.print(&message);
+    }
+
+    ///Shows where the program paused, with the watches
+    fn show_pause(&mut self, interpreter: &mut Interpreter, reason: PauseReason) {
+        let frame = &interpreter.stack()[0];
+        let (source, line) = (frame.source.to_string(), frame.line + 1);
+        let reason = match reason {
+            PauseReason::Breakpoint => " (breakpoint)",
+            PauseReason::Step => "",
+        };
+        self.print(&format!("Paused at {}:{}{}", source, line, reason));
+        self.list(&source, line, 0);
+
+        let environment = frame.environment.clone();
+        for (number, watch) in self.watches.clone().iter().enumerate() {
+            let value = evaluate(interpreter, environment.clone(), watch);
+            self.print(&format!("  {}: {} = {}", number + 1, watch, value));
+        }
+    }
+
+    ///Shows the lines of `source` up to `around` lines around `line`
+    fn list(&mut self, source: &str, line: usize, around: usize) {
+        let lines = self
+            .sources
+            .entry(source.to_string())
+            .or_insert_with(|| {
+                std::fs::read_to_string(source)
+                    .ok()
+                    .map(|text| text.lines().map(str::to_string).collect())
+            })
+            .clone();
+        let Some(lines) = lines else {
+            return;
+        };
+        let first = line.saturating_sub(around).max(1);
+        for number in first..=(line + around).min(lines.len()) {
+            let marker = if number == line { "->" } else { "  " };
+            let text = format!("{} {:>4} | {}", marker, number, lines[number - 1]);
+            self.print(&text);
+        }
+    }
+
+    fn print(&mut self, text: &str) {
+        let _ = writeln!(self.output, "{}", text);
+    }
+
+    fn print_list(&mut self, lines: &[String], empty: &str) {
+        if lines.is_empty() {
+            return self.print(empty);
+        }
+        for line in lines {
+            self.print(line);
+        }
+    }
+}
+
+impl<R: BufRead, W: Write> DebugHook for CliDebugger<R, W> {
+    fn before_statement(
+        &mut self,
+        interpreter: &mut Interpreter,
+        stmt: &Stmt,
+    ) -> Result<(), Diagnostic> {
+        if self.detached {
+            return Ok(());
+        }
+        let line = stmt.line() + 1;
+        let Some(reason) =
+            self.debugger
+                .should_pause(interpreter.source_name(), line, interpreter.depth())
+        else {
+            return Ok(());
+        };
+        self.show_pause(interpreter, reason);
+        self.prompt(interpreter)
+    }
+}
+
+///Describes where a frame is, with lines counted from 1
+fn place(frame: &StackFrame) -> String {
+    format!("{}:{}", frame.source, frame.line + 1)
+}
+
+///Lists variables as `name = value`, by name
+fn variables(mut bindings: Vec<(Symbol, Object)>) -> Vec<String> {
+    bindings.sort_by(|(a, _), (b, _)| a.as_str().cmp(&b.as_str()));
+    bindings
+        .iter()
+        .map(|(name, value)| format!("{} = {:?}", name, value))
+        .collect()
+}
+
+///Evaluates the text of an expression in a paused frame, describing the
+///value or the error
+fn evaluate(interpreter: &mut Interpreter, environment: Environment, text: &str) -> String {
+    let expr = match parse_expression(text) {
+        Ok(expr) => expr,
+        Err(message) => return message,
+    };
+    match interpreter.evaluate_in(environment, &expr) {
+        Ok(value) => format!("{:?}", value),
+        Err(error) => error.to_string(),
+    }
+}
+
+///Parses the text of an expression typed at the prompt or sent by an
+///editor
+pub fn parse_expression(text: &str) -> Result<Expr, String> {
+    let mut parser = Parser::new(Scanner::new(text, "<debugger>".to_string()));
+    let expr = parser.parse_expr(PREC::DefaultBp);
+    match parser.errors().first() {
+        Some(error) => Err(error.message().to_string()),
+        None if !parser.is_eof() => Err(format!("Unexpected \"{}\"", parser.at().lexeme)),
+        None => Ok(expr),
+    }
+}
@@ -0,0 +1,457 @@
+use std::{
+    cell::RefCell,
+    io::{self, BufRead, Write},
+    rc::Rc,
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    ast::stmt::Stmt,
+    engine::engine::{Engine, Error},
+    errors::diagnostic::Diagnostic,
+    interpreter::interpreter::Interpreter,
+    lsp::server::{read_message, write_message},
+    object::object::Object,
+    stdlib::io::redirect_output,
+    tokens::symbol::Symbol,
+};
+
+use super::{
+    cli::{parse_expression, STOPPED},
+    debugger::{DebugHook, Debugger, PauseReason, Step},
+};
+
+/// The only thread of a program, as DAP clients see it
+const THREAD_ID: i64 = 1;
+
+/// What a `variablesReference` handed to the client stands for. They
+/// are only valid while the program stays paused.
+enum Reference {
+    Locals(usize),
+    Globals,
+    /// The items of a list, the fields of a variant or an error
+    Value(Object),
+}
+
+/// What the program should do once a request is handled
+enum Action {
+    Stay,
+    Resume(Step),
+    Disconnect,
+}
+
+/// The state of a debugging session, shared by the server, which runs
+/// until the program starts, and the hook the interpreter calls while
+/// the program runs.
+struct Session<R, W> {
+    reader: R,
+    writer: W,
+    seq: i64,
+    debugger: Debugger,
+    references: Vec<Reference>,
+    /// What the program printed and was not sent to the client yet
+    output: Rc<RefCell<Vec<u8>>>,
+    /// The file given by `launch`
+    program: Option<String>,
+    /// Set until the first pause when `launch` asks to stop on entry
+    stop_on_entry: bool,
+    configured: bool,
+}
+
+/// A Debug Adapter Protocol server for `ND-Lang dap`. The client talks
+/// to it over `reader` and `writer`, and what the program prints is sent
+/// as `output` events. The program runs on the server's thread: while it
+/// is paused, the hook reads and answers requests until one resumes it.
+pub struct DapServer<R, W> {
+    session: Rc<RefCell<Session<R, W>>>,
+}
+
+/// Writes what the program prints into the session's buffer
+struct Captured(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(bytes)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<R: BufRead + 'static, W: Write + 'static> DapServer<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        Self {
+            session: Rc::new(RefCell::new(Session {
+                reader,
+                writer,
+                seq: 0,
+                debugger: Debugger::new(),
+                references: Vec::new(),
+                output: Rc::new(RefCell::new(Vec::new())),
+                program: None,
+                stop_on_entry: false,
+                configured: false,
+            })),
+        }
+    }
+
+    ///Serves requests until the client disconnects or closes the stream
+    pub fn run(&mut self) -> io::Result<()> {
+        loop {
+            let Some(request) = self.session.borrow_mut().read()? else {
+                return Ok(());
+            };
+            let action = self.session.borrow_mut().handle(&request, None)?;
+            if let Action::Disconnect = action {
+                return Ok(());
+            }
+
+            let ready = {
+                let session = self.session.borrow();
+                session.configured && session.program.is_some()
+            };
+            if ready {
+                self.launch()?;
+                // Keep answering until the client disconnects
+                self.session.borrow_mut().program = None;
+            }
+        }
+    }
+
+    ///Runs the launched program to its end
+    fn launch(&mut self) -> io::Result<()> {
+        let (program, output) = {
+            let session = self.session.borrow();
+            (
+                session.program.clone().unwrap_or_default(),
+                session.output.clone(),
+            )
+        };
+
+        let mut engine = Engine::new();
+        engine.set_debug_hook(Box::new(Hook(self.session.clone())));
+        redirect_output(Some(Box::new(Captured(output))));
+        let result = engine.run_file(&program);
+        redirect_output(None);
+
+        let mut session = self.session.borrow_mut();
+        let exit_code = match result {
+            Ok(_) => 0,
+            Err(Error::Runtime(error)) if error.message() == STOPPED => return Ok(()),
+            Err(error) => {
+                let mut text = error.to_string();
+                for diagnostic in error.diagnostics() {
+                    for entry in diagnostic.trace() {
+                        text.push_str(&format!("\n    {}", entry));
+                    }
+                }
+                session.event(
+                    "output",
+                    json!({ "category": "stderr", "output": text + "\n" }),
+                )?;
+                1
+            }
+        };
+        session.event("exited", json!({ "exitCode": exit_code }))?;
+        session.event("terminated", json!({}))
+    }
+}
+
+impl<R: BufRead, W: Write> Session<R, W> {
+    fn read(&mut self) -> io::Result<Option<Value>> {
+        read_message(&mut self.reader)
+    }
+
+    fn send(&mut self, mut message: Value) -> io::Result<()> {
+        // Anything printed so far comes first
+        let printed = std::mem::take(&mut *self.output.borrow_mut());
+        if !printed.is_empty() {
+            let output = String::from_utf8_lossy(&printed).into_owned();
+            self.send(json!({
+                "type": "event",
+                "event": "output",
+                "body": { "category": "stdout", "output": output },
+            }))?;
+        }
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        write_message(&mut self.writer, &message)
+    }
+
+    fn event(&mut self, event: &str, body: Value) -> io::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+
+    ///Answers one request. `interpreter` is the paused program, if there
+    ///is one.
+    fn handle(
+        &mut self,
+        request: &Value,
+        interpreter: Option<&mut Interpreter>,
+    ) -> io::Result<Action> {
+        let command = request["command"].as_str().unwrap_or_default();
+        let arguments = &request["arguments"];
+        let depth = interpreter
+            .as_ref()
+            .map_or(0, |interpreter| interpreter.depth());
+
+        let mut action = Action::Stay;
+        let result = match command {
+            "initialize" => Ok(json!({
+                "supportsConfigurationDoneRequest": true,
+                "supportsEvaluateForHovers": true,
+            })),
+            "launch" => match arguments["program"].as_str() {
+                Some(program) => {
+                    self.program = Some(program.to_string());
+                    self.stop_on_entry = arguments["stopOnEntry"] == true;
+                    if !self.stop_on_entry {
+                        self.debugger.resume(Step::Continue);
+                    }
+                    Ok(json!({}))
+                }
+                None => Err("\"launch\" needs the path of the program".to_string()),
+            },
+            "configurationDone" => {
+                self.configured = true;
+                Ok(json!({}))
+            }
+            "setBreakpoints" => Ok(self.set_breakpoints(arguments)),
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })),
+            "disconnect" => {
+                action = Action::Disconnect;
+                Ok(json!({}))
+            }
+            "continue" => {
+                action = Action::Resume(Step::Continue);
+                Ok(json!({ "allThreadsContinued": true }))
+            }
+            "next" | "stepIn" | "stepOut" => {
+                action = Action::Resume(match command {
+                    "next" => Step::Over { depth },
+                    "stepIn" => Step::In,
+                    _ => Step::Out { depth },
+                });
+                Ok(json!({}))
+            }
+            "stackTrace" | "scopes" | "variables" | "evaluate" => match interpreter {
+                Some(interpreter) => self.inspect(command, arguments, interpreter),
+                None => Err("The program is not paused".to_string()),
+            },
+            _ => Err(format!("Unsupported request \"{}\"", command)),
+        };
+
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": command,
+            "success": result.is_ok(),
+        });
+        match result {
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = json!(message),
+        }
+        self.send(response)?;
+        if command == "initialize" {
+            self.event("initialized", json!({}))?;
+        }
+        Ok(action)
+    }
+
+    fn set_breakpoints(&mut self, arguments: &Value) -> Value {
+        let file = arguments["source"]["path"].as_str().unwrap_or_default();
+        self.debugger.clear_breakpoints(file);
+        let lines: Vec<usize> = arguments["breakpoints"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|breakpoint| breakpoint["line"].as_u64())
+            .map(|line| line as usize)
+            .collect();
+        for line in &lines {
+            self.debugger.add_breakpoint(file, *line);
+        }
+        let breakpoints: Vec<Value> = lines
+            .iter()
+            .map(|line| json!({ "verified": true, "line": line }))
+            .collect();
+        json!({ "breakpoints": breakpoints })
+    }
+
+    ///Answers the requests about the paused program
+    fn inspect(
+        &mut self,
+        command: &str,
+        arguments: &Value,
+        interpreter: &mut Interpreter,
+    ) -> Result<Value, String> {
+        let stack = interpreter.stack();
+        let frame = arguments["frameId"].as_u64().unwrap_or(0) as usize;
+        match command {
+            "stackTrace" => {
+                let frames: Vec<Value> = stack
+                    .iter()
+                    .enumerate()
+                    .map(|(id, frame)| {
+                        json!({
+                            "id": id,
+                            "name": frame.name,
+                            "source": { "path": frame.source.as_ref() },
+                            "line": frame.line + 1,
+                            "column": frame.position.max(1),
+                        })
+                    })
+                    .collect();
+                Ok(json!({ "stackFrames": frames, "totalFrames": stack.len() }))
+            }
+            "scopes" => {
+                if frame >= stack.len() {
+                    return Err(format!("No frame {}", frame));
+                }
+                let locals = self.reference(Reference::Locals(frame));
+                let globals = self.reference(Reference::Globals);
+                Ok(json!({ "scopes": [
+                    { "name": "Locals", "variablesReference": locals, "expensive": false },
+                    { "name": "Globals", "variablesReference": globals, "expensive": false },
+                ] }))
+            }
+            "variables" => {
+                let reference = arguments["variablesReference"].as_u64().unwrap_or(0) as usize;
+                let mut variables: Vec<(String, Object)> =
+                    match self.references.get(reference.wrapping_sub(1)) {
+                        Some(Reference::Locals(frame)) => named(interpreter.locals(&stack[*frame])),
+                        Some(Reference::Globals) => named(interpreter.globals().bindings()),
+                        Some(Reference::Value(value)) => children(value),
+                        None => return Err(format!("No variables for reference {}", reference)),
+                    };
+                if !matches!(self.references[reference - 1], Reference::Value(_)) {
+                    variables.sort_by(|(a, _), (b, _)| a.cmp(b));
+                }
+                let variables: Vec<Value> = variables
+                    .into_iter()
+                    .map(|(name, value)| {
+                        json!({
+                            "name": name,
+                            "value": format!("{:?}", value),
+                            "type": value.type_name(),
+                            "variablesReference": self.value_reference(value),
+                        })
+                    })
+                    .collect();
+                Ok(json!({ "variables": variables }))
+            }
+            "evaluate" => {
+                let text = arguments["expression"].as_str().unwrap_or_default();
+                let environment = match stack.get(frame) {
+                    Some(frame) => frame.environment.clone(),
+                    None => return Err(format!("No frame {}", frame)),
+                };
+                let expr = parse_expression(text)?;
+                let value = interpreter
+                    .evaluate_in(environment, &expr)
+                    .map_err(|error| error.to_string())?;
+                Ok(json!({
+                    "result": format!("{:?}", value),
+                    "type": value.type_name(),
+                    "variablesReference": self.value_reference(value),
+                }))
+            }
+            _ => unreachable!("only requests about the paused program get here"),
+        }
+    }
+
+    fn reference(&mut self, reference: Reference) -> usize {
+        self.references.push(reference);
+        self.references.len()
+    }
+
+    ///Returns the reference to expand a value by, or 0 for values
+    ///without parts
+    fn value_reference(&mut self, value: Object) -> usize {
+        if children(&value).is_empty() {
+            return 0;
+        }
+        self.reference(Reference::Value(value))
+    }
+
+    ///Tells the client the program paused and answers its requests until
+    ///one lets the program go on
+    fn pause(&mut self, interpreter: &mut Interpreter, reason: &str) -> io::Result<Action> {
+        self.event(
+            "stopped",
+            json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+        )?;
+        loop {
+            let Some(request) = self.read()? else {
+                return Ok(Action::Disconnect);
+            };
+            match self.handle(&request, Some(interpreter))? {
+                Action::Stay => continue,
+                action => {
+                    self.references.clear();
+                    return Ok(action);
+                }
+            }
+        }
+    }
+}
+
+/// The session, as the interpreter sees it
+struct Hook<R, W>(Rc<RefCell<Session<R, W>>>);
+
+impl<R: BufRead, W: Write> DebugHook for Hook<R, W> {
+    fn before_statement(
+        &mut self,
+        interpreter: &mut Interpreter,
+        stmt: &Stmt,
+    ) -> Result<(), Diagnostic> {
+        let mut session = self.0.borrow_mut();
+        let reason = session.debugger.should_pause(
+            interpreter.source_name(),
+            stmt.line() + 1,
+            interpreter.depth(),
+        );
+        let reason = match reason {
+            None => return Ok(()),
+            Some(PauseReason::Breakpoint) => "breakpoint",
+            Some(PauseReason::Step) if std::mem::take(&mut session.stop_on_entry) => "entry",
+            Some(PauseReason::Step) => "step",
+        };
+        match session.pause(interpreter, reason) {
+            Ok(Action::Resume(step)) => {
+                session.debugger.resume(step);
+                Ok(())
+            }
+            Ok(_) | Err(_) => Err(Diagnostic::new(0, 0, STOPPED.to_string(), "".to_string())),
+        }
+    }
+}
+
+///Names the bindings of a scope
+fn named(bindings: Vec<(Symbol, Object)>) -> Vec<(String, Object)> {
+    bindings
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect()
+}
+
+///Returns the parts of a value a client can expand it into
+fn children(value: &Object) -> Vec<(String, Object)> {
+    match value {
+        Object::List(items) => items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| (index.to_string(), item.clone()))
+            .collect(),
+        Object::Variant(variant) => variant
+            .declaration()
+            .fields
+            .iter()
+            .map(|field| field.to_string())
+            .zip(variant.values.iter().cloned())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
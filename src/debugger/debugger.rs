@@ -0,0 +1,131 @@
+use std::path::Path;
+
+use crate::{
+    ast::stmt::Stmt, errors::diagnostic::Diagnostic, interpreter::interpreter::Interpreter,
+};
+
+/// Follows a running program, the way a debugger does. The interpreter
+/// calls it before running each statement; returning an error stops the
+/// program with it, past any `try`, and detaches the hook.
+pub trait DebugHook {
+    fn before_statement(
+        &mut self,
+        interpreter: &mut Interpreter,
+        stmt: &Stmt,
+    ) -> Result<(), Diagnostic>;
+}
+
+/// How the program goes on after a pause.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Step {
+    /// Run until a breakpoint
+    Continue,
+    /// Pause at the next statement, in a call if there is one
+    In,
+    /// Pause at the next statement of the call at `depth` or of a caller
+    Over { depth: usize },
+    /// Pause once the call at `depth` returns
+    Out { depth: usize },
+}
+
+/// Why the program paused.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PauseReason {
+    Step,
+    Breakpoint,
+}
+
+/// A place to pause at. Lines count from 1, as in editors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breakpoint {
+    pub file: String,
+    pub line: usize,
+}
+
+/// The breakpoints and stepping of a debugging session, whatever the
+/// debugger is driven by. Programs start paused at their first statement.
+pub struct Debugger {
+    breakpoints: Vec<Breakpoint>,
+    step: Step,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self {
+            breakpoints: Vec::new(),
+            step: Step::In,
+        }
+    }
+
+    ///Adds a breakpoint, returning false if there already is one there
+    pub fn add_breakpoint(&mut self, file: &str, line: usize) -> bool {
+        let breakpoint = Breakpoint {
+            file: file.to_string(),
+            line,
+        };
+        if self.breakpoints.contains(&breakpoint) {
+            return false;
+        }
+        self.breakpoints.push(breakpoint);
+        true
+    }
+
+    ///Removes a breakpoint, returning false if there was none there
+    pub fn remove_breakpoint(&mut self, file: &str, line: usize) -> bool {
+        let count = self.breakpoints.len();
+        self.breakpoints
+            .retain(|breakpoint| !(breakpoint.file == file && breakpoint.line == line));
+        self.breakpoints.len() != count
+    }
+
+    ///Removes the breakpoints of a file
+    pub fn clear_breakpoints(&mut self, file: &str) {
+        self.breakpoints
+            .retain(|breakpoint| !same_file(&breakpoint.file, file));
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    ///Lets the program go on until the next pause `step` asks for
+    pub fn resume(&mut self, step: Step) {
+        self.step = step;
+    }
+
+    ///Checks whether the program pauses before a statement on `line` of
+    ///file `source`, run `depth` calls deep
+    pub fn should_pause(&self, source: &str, line: usize, depth: usize) -> Option<PauseReason> {
+        let breakpoint = self
+            .breakpoints
+            .iter()
+            .any(|breakpoint| breakpoint.line == line && same_file(source, &breakpoint.file));
+        if breakpoint {
+            return Some(PauseReason::Breakpoint);
+        }
+        let step = match self.step {
+            Step::Continue => false,
+            Step::In => true,
+            Step::Over { depth: from } => depth <= from,
+            Step::Out { depth: from } => depth < from,
+        };
+        step.then_some(PauseReason::Step)
+    }
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+///Checks whether a breakpoint's `file` names the file `source` comes
+///from. Files may be given by path, by file name, or by module name for
+///imported modules.
+pub fn same_file(source: &str, file: &str) -> bool {
+    let (source, file) = (Path::new(source), Path::new(file));
+    source == file
+        || source.ends_with(file)
+        || file.ends_with(source)
+        || file.file_stem() == Some(source.as_os_str())
+}
@@ -0,0 +1,278 @@
+use std::{fmt::Display, rc::Rc};
+
+use crate::{object::object::Object, stdlib::prelude::is_builtin};
+
+use super::engine::Value;
+
+/// A Rust type that can be made from an ND-Lang value, as the parameters
+/// of functions registered with `Engine::register_fn`.
+pub trait FromValue: Sized {
+    /// What the value must be, for error messages: "a number"
+    const EXPECTED: &'static str;
+
+    fn from_value(value: &Value) -> Option<Self>;
+}
+
+/// A Rust type that can be turned into an ND-Lang value.
+pub trait IntoValue {
+    fn into_value(self) -> Value;
+}
+
+/// What a registered function returns: a value, or a `Result` whose
+/// error becomes a runtime error in the script.
+pub trait IntoResult {
+    fn into_result(self) -> Result<Value, String>;
+}
+
+impl<T: IntoValue> IntoResult for T {
+    fn into_result(self) -> Result<Value, String> {
+        Ok(self.into_value())
+    }
+}
+
+impl<T: IntoValue, E: Display> IntoResult for Result<T, E> {
+    fn into_result(self) -> Result<Value, String> {
+        self.map(IntoValue::into_value)
+            .map_err(|error| error.to_string())
+    }
+}
+
+impl FromValue for Value {
+    const EXPECTED: &'static str = "a value";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        Some(value.clone())
+    }
+}
+
+impl FromValue for f64 {
+    const EXPECTED: &'static str = "a number";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Object::Num(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! integer_from_value {
+    ($($int:ty),*) => {$(
+        impl FromValue for $int {
+            const EXPECTED: &'static str = "a whole number";
+
+            fn from_value(value: &Value) -> Option<Self> {
+                match value {
+                    Object::Num(n) if n.fract() == 0.0 => {
+                        // Out of range numbers do not convert
+                        let n = *n as i128;
+                        <$int>::try_from(n).ok()
+                    }
+                    _ => None,
+                }
+            }
+        }
+
+        impl IntoValue for $int {
+            fn into_value(self) -> Value {
+                Object::Num(self as f64)
+            }
+        }
+    )*};
+}
+
+integer_from_value!(i32, i64, u32, u64, usize);
+
+impl FromValue for bool {
+    const EXPECTED: &'static str = "a bool";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Object::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for String {
+    const EXPECTED: &'static str = "a string";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Object::Str(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    const EXPECTED: &'static str = "a list";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Object::List(items) => items.iter().map(T::from_value).collect(),
+            _ => None,
+        }
+    }
+}
+
+/// `None` and `null` convert to `None`, and `Some(value)` to `Some`. Any
+/// other value converts as `T`.
+impl<T: FromValue> FromValue for Option<T> {
+    const EXPECTED: &'static str = T::EXPECTED;
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Object::Nil => Some(None),
+            Object::Variant(variant)
+                if is_builtin(&variant.enum_type) && variant.enum_type.name == "Option" =>
+            {
+                // `Some` comes first in the enum
+                match variant.values.first() {
+                    Some(held) if variant.variant == 0 => T::from_value(held).map(Some),
+                    _ => Some(None),
+                }
+            }
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+
+impl IntoValue for Value {
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+impl IntoValue for f64 {
+    fn into_value(self) -> Value {
+        Object::Num(self)
+    }
+}
+
+impl IntoValue for bool {
+    fn into_value(self) -> Value {
+        Object::Bool(self)
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self) -> Value {
+        Object::Str(self)
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self) -> Value {
+        Object::Str(self.to_string())
+    }
+}
+
+impl IntoValue for () {
+    fn into_value(self) -> Value {
+        Object::Nil
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self) -> Value {
+        Object::List(Rc::new(
+            self.into_iter().map(IntoValue::into_value).collect(),
+        ))
+    }
+}
+
+/// `None` converts to `null`
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self) -> Value {
+        self.map_or(Object::Nil, IntoValue::into_value)
+    }
+}
+
+/// The arguments of a call from Rust into a script: a tuple of values
+/// that convert with `IntoValue`, or a `Vec<Value>`.
+pub trait IntoArgs {
+    fn into_args(self) -> Vec<Value>;
+}
+
+impl IntoArgs for Vec<Value> {
+    fn into_args(self) -> Vec<Value> {
+        self
+    }
+}
+
+macro_rules! into_args {
+    ($($arg:ident),*) => {
+        impl<$($arg: IntoValue),*> IntoArgs for ($($arg,)*) {
+            #[allow(non_snake_case)]
+            fn into_args(self) -> Vec<Value> {
+                let ($($arg,)*) = self;
+                vec![$($arg.into_value()),*]
+            }
+        }
+    };
+}
+
+into_args!();
+into_args!(A);
+into_args!(A, B);
+into_args!(A, B, C);
+into_args!(A, B, C, D);
+into_args!(A, B, C, D, E);
+into_args!(A, B, C, D, E, G);
+
+///Converts argument `index` of the call to function `name`
+pub fn argument<T: FromValue>(name: &str, args: &[Value], index: usize) -> Result<T, String> {
+    let value = args.get(index).unwrap_or(&Object::Nil);
+    T::from_value(value).ok_or_else(|| {
+        // Numbers are shown, since their type is rarely the problem
+        let found = match value {
+            Object::Num(n) => n.to_string(),
+            value => value.type_name().to_string(),
+        };
+        format!(
+            "{} expects {} as argument {}, not {}",
+            name,
+            T::EXPECTED,
+            index + 1,
+            found
+        )
+    })
+}
+
+/// A Rust closure that can be called from ND-Lang, with its parameters
+/// converted by `FromValue` and its result by `IntoResult`. `Args` is the
+/// tuple of its parameter types.
+pub trait HostFunction<Args>: 'static {
+    fn arity(&self) -> usize;
+
+    fn call(&self, name: &str, args: &[Value]) -> Result<Value, String>;
+}
+
+macro_rules! host_function {
+    ($($arg:ident $index:tt),*) => {
+        impl<F, R, $($arg),*> HostFunction<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> R + 'static,
+            R: IntoResult,
+            $($arg: FromValue,)*
+        {
+            fn arity(&self) -> usize {
+                <[&str]>::len(&[$(stringify!($arg)),*])
+            }
+
+            #[allow(unused_variables)]
+            fn call(&self, name: &str, args: &[Value]) -> Result<Value, String> {
+                self($(argument::<$arg>(name, args, $index)?),*).into_result()
+            }
+        }
+    };
+}
+
+host_function!();
+host_function!(A 0);
+host_function!(A 0, B 1);
+host_function!(A 0, B 1, C 2);
+host_function!(A 0, B 1, C 2, D 3);
+host_function!(A 0, B 1, C 2, D 3, E 4);
+host_function!(A 0, B 1, C 2, D 3, E 4, G 5);
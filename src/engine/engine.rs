@@ -0,0 +1,346 @@
+use core::fmt;
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crate::{
+    debugger::debugger::DebugHook,
+    errors::diagnostic::Diagnostic,
+    interpreter::interpreter::{Interpreter, Unwind},
+    object::object::{NativeFunction, Object},
+    optimizer::optimizer::{OptLevel, Optimizer},
+    parser::{lookups::OperatorTable, parser::Parser},
+    profiler::profiler::Profiler,
+    scanner::scanner::Scanner,
+    stdlib::fs::Capabilities,
+    tokens::symbol::Symbol,
+};
+
+use super::conversion::{argument, FromValue, HostFunction, IntoArgs, IntoResult, IntoValue};
+
+/// A value passed between Rust and ND-Lang.
+pub type Value = Object;
+
+/// Why running a program failed.
+#[derive(Debug)]
+pub enum Error {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// The program did not parse; it never started running
+    Syntax(Vec<Diagnostic>),
+    /// The program stopped on an error while running
+    Runtime(Diagnostic),
+    /// A value returned to Rust did not have the type asked for
+    Conversion(String),
+}
+
+impl Error {
+    ///Returns the diagnostics describing the error, if it has any
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        match self {
+            Error::Io(_) | Error::Conversion(_) => &[],
+            Error::Syntax(diagnostics) => diagnostics,
+            Error::Runtime(diagnostic) => std::slice::from_ref(diagnostic),
+        }
+    }
+
+    ///Prints the error the way the command line does
+    pub fn report(&self) {
+        match self {
+            Error::Io(error) => println!("{}", error),
+            Error::Conversion(message) => println!("{}", message),
+            _ => self.diagnostics().iter().for_each(Diagnostic::report),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(error) => write!(f, "{}", error),
+            Error::Conversion(message) => write!(f, "{}", message),
+            _ => {
+                let lines: Vec<String> = self.diagnostics().iter().map(|d| d.to_string()).collect();
+                write!(f, "{}", lines.join("\n"))
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+/// Runs ND-Lang programs from Rust. Globals, declared operators and
+/// imported modules persist from one `eval` to the next, the way they do
+/// in the prompt.
+///
+/// ```
+/// use nd_lang::engine::engine::{Engine, Value};
+///
+/// let mut engine = Engine::new();
+/// engine.set_global("width", Value::Num(4.0));
+/// assert_eq!(engine.eval("width * 2;").unwrap(), Value::Num(8.0));
+/// ```
+pub struct Engine {
+    interpreter: Interpreter,
+    /// The grammar, including the operators declared by earlier runs
+    operators: OperatorTable,
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Self {
+            interpreter: Interpreter::new(),
+            operators: OperatorTable::new(),
+        }
+    }
+
+    ///Runs `source` and returns the value of its last statement, if that
+    ///is an expression
+    pub fn eval(&mut self, source: &str) -> Result<Value, Error> {
+        self.eval_named(source, "<eval>")
+    }
+
+    ///Runs `source`, naming it `file_name` in diagnostics
+    pub fn eval_named(&mut self, source: &str, file_name: &str) -> Result<Value, Error> {
+        // The parser pulls tokens from the scanner as it needs them
+        let scanner = Scanner::new(source, file_name.to_string());
+        let mut parser = Parser::with_operators(scanner, self.operators.clone());
+        let statements = parser.parse();
+        if !parser.errors().is_empty() {
+            return Err(Error::Syntax(parser.errors().clone()));
+        }
+        // Operators declared by a source that did not run are left out
+        self.operators = parser.operators().clone();
+        let statements = Optimizer::new(self.interpreter.opt_level()).optimize(statements);
+        self.interpreter
+            .set_source_name(parser.scanner().file_name());
+        self.interpreter
+            .interpret(&statements)
+            .map_err(Error::Runtime)
+    }
+
+    ///Runs a file. Its imports are resolved relative to it.
+    pub fn run_file(&mut self, path: impl AsRef<Path>) -> Result<Value, Error> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)?;
+        self.interpreter.set_file(path);
+        self.eval_named(&source, &path.display().to_string())
+    }
+
+    ///Defines or replaces a global variable
+    pub fn set_global(&mut self, name: &str, value: impl IntoValue) {
+        self.interpreter
+            .globals()
+            .define(Symbol::intern(name), value.into_value(), false);
+    }
+
+    ///Makes a Rust closure callable from scripts as the global `name`.
+    ///Arguments are converted with `FromValue` and checked before the
+    ///closure runs; returning `Err` raises a runtime error in the script.
+    ///
+    /// ```
+    /// use nd_lang::{Engine, Value};
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.register_fn("greet", |name: String, times: i64| {
+    ///     if times < 0 {
+    ///         return Err("cannot greet a negative number of times");
+    ///     }
+    ///     Ok(format!("hi {}", name).repeat(times as usize))
+    /// });
+    /// assert_eq!(engine.eval("greet(\"ann\", 1);").unwrap(), Value::Str("hi ann".to_string()));
+    /// assert!(engine.eval("greet(\"ann\", -1);").is_err());
+    /// ```
+    pub fn register_fn<Args>(&mut self, name: &str, function: impl HostFunction<Args>) {
+        let arity = function.arity();
+        let qualified = name.to_string();
+        let native = NativeFunction::new(name, Some(arity), move |args| {
+            function.call(&qualified, args)
+        });
+        self.set_global(name, Object::Native(Rc::new(native)));
+    }
+
+    ///Makes a Rust closure taking any number of arguments of the same
+    ///type callable from scripts as the global `name`
+    pub fn register_variadic_fn<T: FromValue, R: IntoResult>(
+        &mut self,
+        name: &str,
+        function: impl Fn(Vec<T>) -> R + 'static,
+    ) {
+        let qualified = name.to_string();
+        let native = NativeFunction::new(name, None, move |args| {
+            let args = (0..args.len())
+                .map(|index| argument(&qualified, args, index))
+                .collect::<Result<Vec<T>, String>>()?;
+            function(args).into_result()
+        });
+        self.set_global(name, Object::Native(Rc::new(native)));
+    }
+
+    ///Returns the value of a global variable
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.interpreter.globals().get(Symbol::intern(name))
+    }
+
+    ///Returns a handle to the value of a global variable
+    pub fn handle(&self, name: &str) -> Option<Handle> {
+        self.get_global(name).map(Handle)
+    }
+
+    ///Calls the script function bound to the global `name`, converting
+    ///its result to `R`
+    ///
+    /// ```
+    /// use nd_lang::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.eval("let area = func(w, h) { ret w * h; };").unwrap();
+    /// let area: f64 = engine.call_fn("area", (3, 4.5)).unwrap();
+    /// assert_eq!(area, 13.5);
+    /// ```
+    pub fn call_fn<R: FromValue>(&mut self, name: &str, args: impl IntoArgs) -> Result<R, Error> {
+        let function = self.get_global(name).ok_or_else(|| {
+            Error::Runtime(Diagnostic::new(
+                0,
+                0,
+                format!("Undefined function \"{}\"", name),
+                "".to_string(),
+            ))
+        })?;
+        self.call_value(function, args)
+    }
+
+    ///Calls the function a handle holds, converting its result to `R`
+    pub fn call_handle<R: FromValue>(
+        &mut self,
+        handle: &Handle,
+        args: impl IntoArgs,
+    ) -> Result<R, Error> {
+        self.call_value(handle.0.clone(), args)
+    }
+
+    fn call_value<R: FromValue>(&mut self, callee: Value, args: impl IntoArgs) -> Result<R, Error> {
+        let result = match self.interpreter.call(callee, args.into_args()) {
+            Ok(value) => value,
+            Err(Unwind::Error(diagnostic) | Unwind::Stop(diagnostic)) => {
+                return Err(Error::Runtime(diagnostic))
+            }
+            // Calls catch the `ret` of the function they run
+            Err(Unwind::Return(value)) => value,
+        };
+        R::from_value(&result).ok_or_else(|| {
+            Error::Conversion(format!(
+                "Expected the script to return {}, not {}",
+                R::EXPECTED,
+                result.type_name()
+            ))
+        })
+    }
+
+    ///Returns the global variables and their values, sorted by name
+    pub fn globals(&self) -> Vec<(String, Value)> {
+        let mut globals: Vec<(String, Value)> = self
+            .interpreter
+            .globals()
+            .bindings()
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            // Leave out the functions of declared operators, whose names
+            // are not identifiers
+            .filter(|(name, _)| !name.contains(' '))
+            .collect();
+        globals.sort_by(|a, b| a.0.cmp(&b.0));
+        globals
+    }
+
+    ///Returns the values the program exported with `export`, in the order
+    ///they were exported
+    pub fn exports(&self) -> Vec<(String, Value)> {
+        self.interpreter
+            .exports()
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect()
+    }
+
+    ///Adds a directory to look for imported modules in
+    pub fn add_search_path(&mut self, path: impl Into<PathBuf>) {
+        self.interpreter.add_search_path(path);
+    }
+
+    ///Sets what scripts may do with files. Nothing is allowed by default.
+    pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+        self.interpreter.set_capabilities(capabilities);
+    }
+
+    ///Sets how many calls scripts can nest before they are stopped with
+    ///a stack overflow
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.interpreter.set_max_depth(max_depth);
+    }
+
+    ///Sets how much programs, and the modules they import, are optimized
+    ///before they run
+    pub fn set_opt_level(&mut self, level: OptLevel) {
+        self.interpreter.set_opt_level(level);
+    }
+
+    ///Attaches a debugger, which is called before each statement runs
+    pub fn set_debug_hook(&mut self, hook: Box<dyn DebugHook>) {
+        self.interpreter.set_debug_hook(hook);
+    }
+
+    ///Attaches a profiler, which times the statements and calls run from
+    ///then on
+    pub fn set_profiler(&mut self, profiler: Rc<RefCell<Profiler>>) {
+        self.interpreter.set_profiler(profiler);
+    }
+
+    pub fn interpreter(&mut self) -> &mut Interpreter {
+        &mut self.interpreter
+    }
+}
+
+/// A script value held by Rust, like a function to call back later. It
+/// keeps the value alive and unchanged across calls, even once the
+/// script rebinds the name it was read from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Handle(Value);
+
+impl Handle {
+    pub fn value(&self) -> &Value {
+        &self.0
+    }
+
+    pub fn is_callable(&self) -> bool {
+        matches!(self.0, Object::Func(_) | Object::Native(_))
+    }
+}
+
+impl FromValue for Handle {
+    const EXPECTED: &'static str = "a value";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        Some(Handle(value.clone()))
+    }
+}
+
+impl IntoValue for Handle {
+    fn into_value(self) -> Value {
+        self.0
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,103 @@
+use core::fmt;
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// The file the error was found in, when it is not the one being run
+    file: Option<String>,
+    position: usize,
+    line: usize,
+    message: String,
+    tip: String,
+    /// The kind of a runtime error, like `TypeError`, which `catch`
+    /// blocks can tell errors apart by
+    kind: Option<Box<str>>,
+    /// The calls being run when a runtime error happened, innermost first
+    trace: Box<[String]>,
+}
+
+impl Diagnostic {
+    pub fn new(position: usize, line: usize, message: String, tip: String) -> Self {
+        Self {
+            file: None,
+            position,
+            line,
+            message,
+            tip,
+            kind: None,
+            trace: Box::default(),
+        }
+    }
+
+    ///Records the kind of a runtime error
+    pub fn with_kind(mut self, kind: &str) -> Self {
+        self.kind = Some(kind.into());
+        self
+    }
+
+    ///Records the calls being run when a runtime error happened
+    pub fn with_trace(mut self, trace: Vec<String>) -> Self {
+        self.trace = trace.into_boxed_slice();
+        self
+    }
+
+    ///Records the file the error was found in, unless it is already known
+    pub fn in_file(mut self, file: String) -> Self {
+        if self.file.is_none() {
+            self.file = Some(file);
+        }
+        self
+    }
+
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn tip(&self) -> &str {
+        &self.tip
+    }
+
+    pub fn kind(&self) -> Option<&str> {
+        self.kind.as_deref()
+    }
+
+    pub fn trace(&self) -> &[String] {
+        &self.trace
+    }
+
+    pub fn report(&self) {
+        println!("{}", self);
+        if !self.trace.is_empty() {
+            println!("Traceback, most recent call first:");
+            for entry in self.trace.iter() {
+                println!("    {}", entry);
+            }
+        }
+        println!("{}", self.tip);
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{} ", file)?;
+        }
+        // Lines are counted from 0, but shown from 1 as in the traceback
+        write!(f, "[{}::{}] ", self.line + 1, self.position)?;
+        if let Some(kind) = &self.kind {
+            write!(f, "{}: ", kind)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
@@ -0,0 +1,645 @@
+use crate::{
+    ast::{
+        expr::{join, Expr},
+        pattern::MatchArm,
+        stmt::{Import, Stmt, Variant},
+    },
+    errors::diagnostic::Diagnostic,
+    parser::parser::Parser,
+    scanner::scanner::Scanner,
+    tokens::symbol::Symbol,
+    tokens::token::Comment,
+};
+
+/// Options controlling the layout produced by the `Formatter`.
+#[derive(Debug, Clone)]
+pub struct FormatConfig {
+    /// Number of spaces per indentation level
+    pub indent_width: usize,
+    /// Column after which expressions are broken over several lines
+    pub line_width: usize,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        Self {
+            indent_width: 4,
+            line_width: 80,
+        }
+    }
+}
+
+/// Pretty-prints a parsed program back into source code, re-inserting
+/// the comments collected by the `Scanner` next to the statements they
+/// were written beside.
+pub struct Formatter<'a> {
+    config: &'a FormatConfig,
+    /// Whether each line of the source is blank
+    blank_lines: Vec<bool>,
+    comments: &'a [Comment<'a>],
+    next_comment: usize,
+    output: String,
+    depth: usize,
+    last_line: Option<usize>,
+    /// The last lines of the statements being written, outermost first.
+    /// A comment trailing one of them waits for its statement to end.
+    open_statements: Vec<usize>,
+}
+
+impl<'a> Formatter<'a> {
+    pub fn new(config: &'a FormatConfig, source: &str, comments: &'a [Comment<'a>]) -> Self {
+        Self {
+            config,
+            blank_lines: source.lines().map(|line| line.trim().is_empty()).collect(),
+            comments,
+            next_comment: 0,
+            output: String::new(),
+            depth: 0,
+            last_line: None,
+            open_statements: Vec::new(),
+        }
+    }
+
+    ///Formats the statements and returns the resulting source
+    pub fn format(mut self, statements: &[Stmt]) -> String {
+        for stmt in statements {
+            self.format_stmt(stmt);
+        }
+        self.flush_comments(usize::MAX);
+        if !self.output.is_empty() && !self.output.ends_with('\n') {
+            self.output.push('\n');
+        }
+        self.output
+    }
+
+    fn indent(&self) -> String {
+        " ".repeat(self.depth * self.config.indent_width)
+    }
+
+    ///Starts a new output line at the current indentation, keeping a
+    ///single blank line where the source had one or more
+    fn begin_line(&mut self, line: usize) {
+        let after_blank = line
+            .checked_sub(1)
+            .and_then(|previous| self.blank_lines.get(previous))
+            .is_some_and(|blank| *blank);
+        if self.last_line.is_some_and(|last_line| line > last_line) && after_blank {
+            self.output.push('\n');
+        }
+        let indent = self.indent();
+        self.output.push_str(&indent);
+    }
+
+    ///Writes every pending comment located before `line`. Trailing
+    ///comments are appended to the line that was written last.
+    fn flush_comments(&mut self, line: usize) {
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.line >= line
+                || comment.trailing && self.open_statements.contains(&comment.line)
+            {
+                break;
+            }
+            self.next_comment += 1;
+
+            if comment.trailing && self.last_line.is_some() {
+                if self.output.ends_with('\n') {
+                    self.output.pop();
+                }
+                self.output.push(' ');
+                self.output.push_str(comment.text);
+                self.output.push('\n');
+            } else {
+                self.begin_line(comment.line);
+                self.output.push_str(comment.text);
+                self.output.push('\n');
+            }
+            self.last_line = Some(comment.line);
+        }
+    }
+
+    ///Checks whether a comment is waiting to be written before `line`
+    fn has_comments_before(&self, line: usize) -> bool {
+        self.comments
+            .get(self.next_comment)
+            .is_some_and(|comment| comment.line < line)
+    }
+
+    fn format_stmt(&mut self, stmt: &Stmt) {
+        self.flush_comments(stmt.line());
+        self.begin_line(stmt.line());
+        self.open_statements.push(stmt.end_line());
+        self.write_stmt(stmt);
+        self.open_statements.pop();
+        self.output.push('\n');
+        self.last_line = Some(stmt.end_line());
+        // Pick up a comment that trails the end of this statement
+        self.flush_comments(stmt.end_line() + 1);
+    }
+
+    ///Writes a statement starting at the current position of the output
+    fn write_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::ExpressionStmt { expression, .. } => {
+                let column = self.indent().len();
+                let expr = self.format_expr_wrapped(expression, column);
+                self.output.push_str(&expr);
+                self.output.push(';');
+            }
+            Stmt::VarDeclarationStmt {
+                identifier,
+                isConstant,
+                assignedValue,
+                explicitType,
+                ..
+            } => {
+                let mut head = format!(
+                    "{} {}",
+                    if *isConstant { "const" } else { "let" },
+                    identifier
+                );
+                if let Some(explicit_type) = explicitType {
+                    head.push_str(&format!(": {}", explicit_type.lexeme));
+                }
+                head.push_str(" = ");
+                let column = self.indent().len() + head.len();
+                let value = self.format_expr_wrapped(assignedValue, column);
+                self.output.push_str(&head);
+                self.output.push_str(&value);
+                self.output.push(';');
+            }
+            Stmt::BlockStmt {
+                body,
+                line,
+                end_line,
+            } => self.write_block(body, *line, *end_line),
+            Stmt::IfStmt {
+                condition,
+                consequent,
+                alternate,
+                ..
+            } => {
+                let column = self.indent().len() + 3;
+                let condition = self.format_expr_wrapped(condition, column);
+                self.output.push_str(&format!("if {}", condition));
+                self.write_body(consequent);
+
+                if let Some(alternate) = alternate {
+                    if matches!(consequent.as_ref(), Stmt::BlockStmt { .. }) {
+                        self.output.push_str(" else");
+                    } else {
+                        self.output.push('\n');
+                        let indent = self.indent();
+                        self.output.push_str(&indent);
+                        self.output.push_str("else");
+                    }
+                    match alternate.as_ref() {
+                        Stmt::IfStmt { .. } => {
+                            self.output.push(' ');
+                            self.write_stmt(alternate);
+                        }
+                        _ => self.write_body(alternate),
+                    }
+                }
+            }
+            Stmt::ReturnStmt { value, .. } => {
+                self.output.push_str("ret");
+                if let Some(value) = value {
+                    let column = self.indent().len() + 4;
+                    let value = self.format_expr_wrapped(value, column);
+                    self.output.push(' ');
+                    self.output.push_str(&value);
+                }
+                self.output.push(';');
+            }
+            Stmt::OperatorDeclStmt {
+                operator,
+                fixity,
+                function,
+                ..
+            } => {
+                let head = format!("{} = ", fixity.declaration(operator.lexeme));
+                let column = self.indent().len() + head.len();
+                let function = self.format_expr_wrapped(function, column);
+                self.output.push_str(&head);
+                self.output.push_str(&function);
+                self.output.push(';');
+            }
+            Stmt::ImportStmt { path, import, .. } => {
+                let import = match import {
+                    Import::Module(alias) => format!("import \"{}\" as {};", path, alias),
+                    Import::Names(names) => {
+                        format!("import {{ {} }} from \"{}\";", join(names), path)
+                    }
+                };
+                self.output.push_str(&import);
+            }
+            Stmt::ExportStmt { declaration, .. } => {
+                self.output.push_str("export ");
+                self.write_stmt(declaration);
+            }
+            Stmt::EnumDeclStmt {
+                name,
+                variants,
+                line,
+                end_line,
+            } => self.write_enum(*name, variants, *line, *end_line),
+            Stmt::TryStmt {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                self.output.push_str("try ");
+                self.write_stmt(body);
+                if let Some(catch) = catch {
+                    match catch.name {
+                        Some(name) => self.output.push_str(&format!(" catch ({}) ", name)),
+                        None => self.output.push_str(" catch "),
+                    }
+                    self.write_stmt(&catch.body);
+                }
+                if let Some(finally) = finally {
+                    self.output.push_str(" finally ");
+                    self.write_stmt(finally);
+                }
+            }
+            Stmt::ThrowStmt { value, .. } => {
+                let column = self.indent().len() + 6;
+                let value = self.format_expr_wrapped(value, column);
+                self.output.push_str("throw ");
+                self.output.push_str(&value);
+                self.output.push(';');
+            }
+            Stmt::TestStmt { name, body, .. } => {
+                self.output.push_str(&format!("test \"{}\" ", name));
+                self.write_stmt(body);
+            }
+        }
+    }
+
+    ///Writes an `enum` with each variant on its own line
+    fn write_enum(&mut self, name: Symbol, variants: &[Variant], line: usize, end_line: usize) {
+        self.output.push_str(&format!("enum {} ", name));
+        if variants.is_empty() && !self.has_comments_before(end_line) {
+            self.output.push_str("{}");
+            self.last_line = Some(end_line);
+            return;
+        }
+
+        self.output.push_str("{\n");
+        self.last_line = Some(line);
+        self.depth += 1;
+        for (position, variant) in variants.iter().enumerate() {
+            self.flush_comments(variant.line);
+            self.begin_line(variant.line);
+            self.output.push_str(&format!("{},\n", variant));
+            self.last_line = Some(variant.line);
+            // A comment trailing the line belongs to its last variant
+            let next_line = variants.get(position + 1).map(|next| next.line);
+            if next_line != Some(variant.line) {
+                self.flush_comments(variant.line + 1);
+            }
+        }
+        self.flush_comments(end_line);
+        self.depth -= 1;
+
+        let indent = self.indent();
+        self.output.push_str(&indent);
+        self.output.push('}');
+        self.last_line = Some(end_line);
+    }
+
+    ///Writes the body of an `if`/`else`: blocks stay on the same line,
+    ///single statements go on the next line one level deeper
+    fn write_body(&mut self, body: &Stmt) {
+        match body {
+            Stmt::BlockStmt {
+                body,
+                line,
+                end_line,
+            } => {
+                self.output.push(' ');
+                self.write_block(body, *line, *end_line);
+            }
+            _ => {
+                self.output.push('\n');
+                self.depth += 1;
+                let indent = self.indent();
+                self.output.push_str(&indent);
+                self.write_stmt(body);
+                self.depth -= 1;
+            }
+        }
+    }
+
+    fn write_block(&mut self, body: &[Stmt], line: usize, end_line: usize) {
+        if body.is_empty() && !self.has_comments_before(end_line) {
+            self.output.push_str("{}");
+            self.last_line = Some(end_line);
+            return;
+        }
+
+        self.output.push_str("{\n");
+        self.last_line = Some(line);
+        self.depth += 1;
+        for stmt in body {
+            self.format_stmt(stmt);
+        }
+        self.flush_comments(end_line);
+        self.depth -= 1;
+
+        let indent = self.indent();
+        self.output.push_str(&indent);
+        self.output.push('}');
+        self.last_line = Some(end_line);
+    }
+
+    ///Formats an expression, breaking it over several lines when it does
+    ///not fit in the configured line width: binary operators go on
+    ///continuation lines, and the arguments of calls and the items of
+    ///lists one per line
+    fn format_expr_wrapped(&mut self, expr: &Expr, column: usize) -> String {
+        // Only function literals and matches record lines, and their
+        // bodies are laid out like any other block
+        if expr.end_line().is_some() {
+            return self.format_expr_with_bodies(expr);
+        }
+
+        let flat = format_expr(expr);
+        if column + flat.len() <= self.config.line_width {
+            return flat;
+        }
+
+        match expr {
+            Expr::BinaryOp { left, op, right } => {
+                let continuation =
+                    format!("{}{}", self.indent(), " ".repeat(self.config.indent_width));
+                let left = self.format_expr_wrapped(left, column);
+                let right =
+                    self.format_expr_wrapped(right, continuation.len() + op.lexeme.len() + 1);
+                format!("{}\n{}{} {}", left, continuation, op.lexeme, right)
+            }
+            Expr::Call { callee, args, .. } if !args.is_empty() => {
+                let callee = self.format_expr_wrapped(callee, column);
+                format!("{}({})", callee, self.format_items_wrapped(args))
+            }
+            Expr::List(items) if !items.is_empty() => {
+                format!("[{}]", self.format_items_wrapped(items))
+            }
+            _ => flat,
+        }
+    }
+
+    ///Formats the arguments of a call or the items of a list one per
+    ///line, one level deeper than the line they start on
+    fn format_items_wrapped(&mut self, items: &[Expr]) -> String {
+        self.depth += 1;
+        let indent = self.indent();
+        let items: Vec<String> = items
+            .iter()
+            .map(|item| {
+                let item = self.format_expr_wrapped(item, indent.len());
+                format!("{}{}", indent, item)
+            })
+            .collect();
+        self.depth -= 1;
+        format!("\n{}\n{}", items.join(",\n"), self.indent())
+    }
+
+    ///Formats an expression containing function literals
+    fn format_expr_with_bodies(&mut self, expr: &Expr) -> String {
+        match expr {
+            Expr::Unary { op, right } => {
+                format!("{}{}", op.lexeme, self.format_expr_with_bodies(right))
+            }
+            Expr::BinaryOp { left, op, right } => format!(
+                "{} {} {}",
+                self.format_expr_with_bodies(left),
+                op.lexeme,
+                self.format_expr_with_bodies(right)
+            ),
+            Expr::Assignment {
+                assignee,
+                op,
+                assigned,
+            } => format!(
+                "{} {} {}",
+                self.format_expr_with_bodies(assignee),
+                op.lexeme,
+                self.format_expr_with_bodies(assigned)
+            ),
+            Expr::Grouping { group } => format!("({})", self.format_expr_with_bodies(group)),
+            Expr::Call { callee, args, .. } => {
+                let callee = self.format_expr_with_bodies(callee);
+                let args: Vec<String> = args
+                    .iter()
+                    .map(|arg| self.format_expr_with_bodies(arg))
+                    .collect();
+                format!("{}({})", callee, args.join(", "))
+            }
+            Expr::Function {
+                params,
+                body,
+                line,
+                end_line,
+            } => self.format_function(params, body, *line, *end_line),
+            Expr::List(items) => {
+                let items: Vec<String> = items
+                    .iter()
+                    .map(|item| self.format_expr_with_bodies(item))
+                    .collect();
+                format!("[{}]", items.join(", "))
+            }
+            Expr::Index { object, index } => format!(
+                "{}[{}]",
+                self.format_expr_with_bodies(object),
+                self.format_expr_with_bodies(index)
+            ),
+            Expr::Member { object, property } => {
+                format!("{}.{}", self.format_expr_with_bodies(object), property)
+            }
+            Expr::Propagate { value, .. } => format!("{}?", self.format_expr_with_bodies(value)),
+            Expr::Match {
+                value,
+                arms,
+                line,
+                end_line,
+            } => self.format_match(value, arms, *line, *end_line),
+            _ => format_expr(expr),
+        }
+    }
+
+    ///Formats a function literal, writing its body one level deeper than
+    ///the line it starts on
+    fn format_function(
+        &mut self,
+        params: &[Symbol],
+        body: &[Stmt],
+        line: usize,
+        end_line: usize,
+    ) -> String {
+        let output = std::mem::replace(&mut self.output, format!("func({}) ", join(params)));
+        self.write_block(body, line, end_line);
+        std::mem::replace(&mut self.output, output)
+    }
+
+    ///Formats a `match`, writing each arm on its own line one level deeper
+    ///than the line it starts on
+    fn format_match(
+        &mut self,
+        value: &Expr,
+        arms: &[MatchArm],
+        line: usize,
+        end_line: usize,
+    ) -> String {
+        let value = self.format_expr_with_bodies(value);
+        let output = std::mem::replace(&mut self.output, format!("match {} {{\n", value));
+        self.last_line = Some(line);
+        self.depth += 1;
+        for arm in arms {
+            self.flush_comments(arm.line);
+            self.begin_line(arm.line);
+            let mut head = arm.pattern.to_string();
+            if let Some(guard) = &arm.guard {
+                head.push_str(&format!(" if {}", self.format_expr_with_bodies(guard)));
+            }
+            let body = self.format_expr_with_bodies(&arm.body);
+            self.output.push_str(&format!("{} => {},\n", head, body));
+            self.last_line = Some(arm.body.end_line().unwrap_or(arm.line));
+            self.flush_comments(self.last_line.unwrap_or(arm.line) + 1);
+        }
+        self.flush_comments(end_line);
+        self.depth -= 1;
+
+        let indent = self.indent();
+        self.output.push_str(&indent);
+        self.output.push('}');
+        self.last_line = Some(end_line);
+        std::mem::replace(&mut self.output, output)
+    }
+}
+
+///Formats a whole source file, or returns its syntax errors
+pub fn format_source(
+    source: &str,
+    file_name: String,
+    config: &FormatConfig,
+) -> Result<String, Vec<Diagnostic>> {
+    let mut parser = Parser::new(Scanner::new(source, file_name));
+    let statements = parser.parse();
+    if !parser.errors().is_empty() {
+        return Err(parser.errors().clone());
+    }
+    Ok(Formatter::new(config, source, parser.scanner().comments()).format(&statements))
+}
+
+/// A line that differs between a file and its formatted version
+#[derive(Debug, PartialEq)]
+pub enum LineChange<'a> {
+    /// A line of the file, with its number, that formatting removes
+    Removed(usize, &'a str),
+    /// A line formatting adds, with its number in the formatted file
+    Added(usize, &'a str),
+}
+
+///Returns the lines to remove from `old` and add to get `new`, keeping
+///the longest sequence of lines the two share
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<LineChange<'a>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // Lines both sides start and end with are never part of the diff
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (removed, added) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    // shared[i][j] is the length of the longest common subsequence of
+    // removed[i..] and added[j..]
+    let mut shared = vec![vec![0usize; added.len() + 1]; removed.len() + 1];
+    for i in (0..removed.len()).rev() {
+        for j in (0..added.len()).rev() {
+            shared[i][j] = if removed[i] == added[j] {
+                shared[i + 1][j + 1] + 1
+            } else {
+                shared[i + 1][j].max(shared[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < removed.len() || j < added.len() {
+        if i < removed.len() && j < added.len() && removed[i] == added[j] {
+            i += 1;
+            j += 1;
+        } else if j == added.len() || (i < removed.len() && shared[i + 1][j] >= shared[i][j + 1]) {
+            changes.push(LineChange::Removed(prefix + i + 1, removed[i]));
+            i += 1;
+        } else {
+            changes.push(LineChange::Added(prefix + j + 1, added[j]));
+            j += 1;
+        }
+    }
+    changes
+}
+
+///Formats an expression on a single line
+pub fn format_expr(expr: &Expr) -> String {
+    match expr {
+        Expr::Number(x) => x.to_string(),
+        Expr::String(x) => format!("\"{}\"", x),
+        Expr::Bool(x) => x.to_string(),
+        Expr::Null => "null".to_string(),
+        Expr::Identifier(x) => x.to_string(),
+        Expr::Unary { op, right } => format!("{}{}", op.lexeme, format_expr(right)),
+        Expr::BinaryOp { left, op, right } => {
+            format!("{} {} {}", format_expr(left), op.lexeme, format_expr(right))
+        }
+        Expr::Assignment {
+            assignee,
+            op,
+            assigned,
+        } => format!(
+            "{} {} {}",
+            format_expr(assignee),
+            op.lexeme,
+            format_expr(assigned)
+        ),
+        Expr::Grouping { group } => format!("({})", format_expr(group)),
+        Expr::Function { params, .. } => format!("func({}) {{...}}", join(params)),
+        Expr::Call { callee, args, .. } => {
+            let args: Vec<String> = args.iter().map(format_expr).collect();
+            format!("{}({})", format_expr(callee), args.join(", "))
+        }
+        Expr::Member { object, property } => format!("{}.{}", format_expr(object), property),
+        Expr::Propagate { value, .. } => format!("{}?", format_expr(value)),
+        Expr::List(items) => {
+            let items: Vec<String> = items.iter().map(format_expr).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Expr::Index { object, index } => format!("{}[{}]", format_expr(object), format_expr(index)),
+        Expr::Match { value, arms, .. } => {
+            let arms: Vec<String> = arms
+                .iter()
+                .map(|arm| match &arm.guard {
+                    Some(guard) => format!(
+                        "{} if {} => {}",
+                        arm.pattern,
+                        format_expr(guard),
+                        format_expr(&arm.body)
+                    ),
+                    None => format!("{} => {}", arm.pattern, format_expr(&arm.body)),
+                })
+                .collect();
+            format!("match {} {{ {} }}", format_expr(value), arms.join(", "))
+        }
+    }
+}
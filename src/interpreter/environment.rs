@@ -0,0 +1,102 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::{object::object::Object, tokens::symbol::Symbol};
+
+struct Binding {
+    value: Object,
+    constant: bool,
+}
+
+struct Scope {
+    values: HashMap<Symbol, Binding>,
+    parent: Option<Environment>,
+}
+
+/// Why an assignment failed
+pub enum AssignError {
+    Undefined,
+    Constant,
+}
+
+/// A chain of scopes mapping names to values. Cloning an environment
+/// gives another handle to the same scopes, which is how functions keep
+/// the scope they were created in.
+#[derive(Clone)]
+pub struct Environment(Rc<RefCell<Scope>>);
+
+impl Environment {
+    pub fn new() -> Self {
+        Self(Rc::new(RefCell::new(Scope {
+            values: HashMap::new(),
+            parent: None,
+        })))
+    }
+
+    ///Creates a scope nested in this one
+    pub fn child(&self) -> Self {
+        Self(Rc::new(RefCell::new(Scope {
+            values: HashMap::new(),
+            parent: Some(self.clone()),
+        })))
+    }
+
+    ///Checks whether both handles refer to the same scope
+    pub fn same_scope(&self, other: &Environment) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+
+    ///Binds `name` in this scope, shadowing any outer binding
+    pub fn define(&self, name: Symbol, value: Object, constant: bool) {
+        self.0
+            .borrow_mut()
+            .values
+            .insert(name, Binding { value, constant });
+    }
+
+    ///Looks `name` up through the enclosing scopes
+    pub fn get(&self, name: Symbol) -> Option<Object> {
+        let scope = self.0.borrow();
+        match scope.values.get(&name) {
+            Some(binding) => Some(binding.value.clone()),
+            None => scope.parent.as_ref()?.get(name),
+        }
+    }
+
+    ///Returns the names bound in this scope, outer scopes left out, with
+    ///their values
+    pub fn bindings(&self) -> Vec<(Symbol, Object)> {
+        self.0
+            .borrow()
+            .values
+            .iter()
+            .map(|(name, binding)| (*name, binding.value.clone()))
+            .collect()
+    }
+
+    ///Returns the scope this one is nested in
+    pub fn parent(&self) -> Option<Environment> {
+        self.0.borrow().parent.clone()
+    }
+
+    ///Updates the closest binding of `name`
+    pub fn assign(&self, name: Symbol, value: Object) -> Result<(), AssignError> {
+        let mut scope = self.0.borrow_mut();
+        match scope.values.get_mut(&name) {
+            Some(binding) if binding.constant => Err(AssignError::Constant),
+            Some(binding) => {
+                binding.value = value;
+                Ok(())
+            }
+            None => match &scope.parent {
+                Some(parent) => parent.assign(name, value),
+                None => Err(AssignError::Undefined),
+            },
+        }
+    }
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
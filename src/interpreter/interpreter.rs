@@ -1,42 +1,1439 @@
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
 use crate::{
-    ast::{expr::Expr, stmt::Stmt},
-    tokens::token_type::TokenType,
+    ast::{
+        expr::Expr,
+        pattern::{MatchArm, Pattern, Rest},
+        stmt::{Catch, Import, Stmt},
+    },
+    debugger::debugger::DebugHook,
+    errors::diagnostic::Diagnostic,
+    object::object::{
+        module_name, EnumType, ErrorValue, Function, Module, NativeFunction, Object, VariantValue,
+    },
+    optimizer::optimizer::{OptLevel, Optimizer},
+    parser::{lookups::operator_binding, parser::Parser},
+    profiler::profiler::Profiler,
+    scanner::scanner::Scanner,
+    stdlib::{
+        assert::{self, Assertion, ASSERTION_ERROR},
+        fs::Capabilities,
+        prelude::{is_builtin, prelude},
+    },
+    tokens::{symbol::Symbol, token::Token, token_type::TokenType},
+};
+
+use super::{
+    environment::{AssignError, Environment},
+    modules::ModuleLoader,
 };
 
+/// The methods of `Result` and `Option` values
+const BUILTIN_METHODS: [&str; 7] = [
+    "is_ok",
+    "is_err",
+    "is_some",
+    "is_none",
+    "unwrap",
+    "unwrap_or",
+    "map",
+];
+
+/// How many calls can be nested before a script is stopped with a stack
+/// overflow, unless the embedder sets another limit. The stack grows as
+/// needed, so the limit holds on threads of any size.
+pub const DEFAULT_MAX_DEPTH: usize = 1000;
+
+/// The stack left below which a call moves to a new stack segment
+pub(crate) const STACK_RED_ZONE: usize = 256 * 1024;
+/// The size of each new stack segment
+pub(crate) const STACK_GROWTH: usize = 4 * 1024 * 1024;
+
+/// Why evaluation stopped before reaching the end of a statement.
+pub enum Unwind {
+    /// A `ret` statement, carrying the value returned
+    Return(Object),
+    Error(Diagnostic),
+    /// The debugger stopping the program. `try` cannot catch it, but
+    /// `finally` blocks still run.
+    Stop(Diagnostic),
+}
+
+impl From<Diagnostic> for Unwind {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Unwind::Error(diagnostic)
+    }
+}
+
+/// Runs parsed statements by walking the tree. Globals live as long as
+/// the interpreter, so a prompt can run one line at a time.
+///
+/// Every module runs in an interpreter of its own, sharing the
+/// `ModuleLoader` of the program.
 pub struct Interpreter {
-    statements: Vec<Stmt>,
+    globals: Environment,
+    environment: Environment,
+    /// The line of the statement being run, for error messages
+    line: usize,
+    modules: Rc<RefCell<ModuleLoader>>,
+    /// The file being run, which imports are resolved against
+    file: Option<PathBuf>,
+    /// The names declared with `export` so far
+    exports: Vec<Symbol>,
+    /// The name of the file the code being run comes from
+    source: Rc<str>,
+    /// Where on `line` the innermost call being run starts, or 0 when
+    /// that is not known
+    position: usize,
+    /// The calls being run, outermost first
+    frames: Vec<Frame>,
+    /// How many calls can be nested
+    max_depth: usize,
+    /// The debugger to tell about each statement before it runs
+    debug_hook: Option<Box<dyn DebugHook>>,
+    /// The profiler timing the statements and calls, shared with the
+    /// interpreters of imported modules
+    profiler: Option<Rc<RefCell<Profiler>>>,
+    /// How much imported modules are optimized before they run
+    opt_level: OptLevel,
+}
+
+/// A call being run: the name of the function, where it was called from
+/// and the scope of the caller.
+struct Frame {
+    name: String,
+    source: Rc<str>,
+    line: usize,
+    position: usize,
+    environment: Environment,
+}
+
+/// A call on the stack, as shown to debuggers: the function, the scope
+/// it runs in and where it is at. That is the statement being run for the
+/// innermost frame, and the call to the next frame for the others.
+pub struct StackFrame {
+    pub name: String,
+    pub source: Rc<str>,
+    pub line: usize,
+    pub position: usize,
+    pub environment: Environment,
+}
+
+impl StackFrame {
+    ///Returns where the frame is at, as `file:line:position`
+    pub fn location(&self) -> String {
+        location(&self.source, self.line, self.position)
+    }
 }
 
 impl Interpreter {
-    pub fn new(statements: Vec<Stmt>) -> Self {
-        Self { statements }
+    pub fn new() -> Self {
+        Self::with_modules(Rc::new(RefCell::new(ModuleLoader::new())), None)
+    }
+
+    fn with_modules(modules: Rc<RefCell<ModuleLoader>>, file: Option<PathBuf>) -> Self {
+        let globals = prelude().child();
+        Self {
+            environment: globals.clone(),
+            globals,
+            line: 0,
+            modules,
+            file,
+            exports: Vec::new(),
+            source: "<eval>".into(),
+            position: 0,
+            frames: Vec::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            debug_hook: None,
+            profiler: None,
+            opt_level: OptLevel::default(),
+        }
+    }
+
+    ///Attaches a debugger, which is called before each statement runs
+    pub fn set_debug_hook(&mut self, hook: Box<dyn DebugHook>) {
+        self.debug_hook = Some(hook);
+    }
+
+    ///Attaches a profiler, which is told about each statement and call
+    pub fn set_profiler(&mut self, profiler: Rc<RefCell<Profiler>>) {
+        self.profiler = Some(profiler);
+    }
+
+    ///Names the file the statements to run come from, for stack traces
+    pub fn set_source_name(&mut self, name: &str) {
+        self.source = name.into();
+    }
+
+    ///Sets how many calls can be nested before the script is stopped
+    ///with a stack overflow
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
     }
 
-    // HACK: MAKE LOOKUP TABLE TO STORE INTERPRETER FUNCTIONS
-    pub fn interpret<T>(&self) -> T {
-        for stmt in &self.statements {
-            return match stmt {
-                Stmt::ExpressionStmt { expression } => self.interpret_expr(&expression),
-                _ => todo!(),
+    ///Sets how much imported modules are optimized before they run
+    pub fn set_opt_level(&mut self, level: OptLevel) {
+        self.opt_level = level;
+    }
+
+    pub fn opt_level(&self) -> OptLevel {
+        self.opt_level
+    }
+
+    ///Returns the values exported so far, by name
+    pub fn exports(&self) -> Vec<(Symbol, Object)> {
+        self.exports
+            .iter()
+            .filter_map(|name| Some((*name, self.globals.get(*name)?)))
+            .collect()
+    }
+
+    ///Sets the file the statements come from. Imports are resolved
+    ///relative to it, and importing it again is an import cycle.
+    pub fn set_file(&mut self, path: &Path) {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        // The file stays marked for the whole run
+        let _ = self.modules.borrow_mut().begin(&path);
+        self.file = Some(path);
+    }
+
+    ///Sets what scripts may do with files. Nothing is allowed by default.
+    pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+        self.modules.borrow_mut().set_capabilities(capabilities);
+    }
+
+    ///Adds a directory to look for imported modules in
+    pub fn add_search_path(&mut self, path: impl Into<PathBuf>) {
+        self.modules.borrow_mut().add_search_path(path);
+    }
+
+    ///Returns the global scope
+    pub fn globals(&self) -> &Environment {
+        &self.globals
+    }
+
+    ///Runs the statements and returns the value of the last one, if it
+    ///is an expression statement
+    pub fn interpret(&mut self, statements: &[Stmt]) -> Result<Object, Diagnostic> {
+        let mut result = Object::Nil;
+        for stmt in statements {
+            result = match self.execute(stmt) {
+                Ok(value) => value,
+                Err(Unwind::Error(diagnostic) | Unwind::Stop(diagnostic)) => {
+                    return Err(diagnostic)
+                }
+                Err(Unwind::Return(_)) => {
+                    return Err(self.error("Cannot return from outside a function"))
+                }
             };
         }
+        Ok(result)
     }
 
-    pub fn interpret_expr<T>(&self, expr: &Expr) -> T {
-        match expr {
-            Expr::BinaryOp { left, right, op } => {
-                let left = self.interpret_expr(left);
-                let right = self.interpret_expr(right);
+    fn execute(&mut self, stmt: &Stmt) -> Result<Object, Unwind> {
+        self.line = stmt.line();
+        self.position = 0;
+        // Blocks and exports are left to the statements in them, and
+        // tests are skipped
+        let stepped = !matches!(
+            stmt,
+            Stmt::BlockStmt { .. } | Stmt::ExportStmt { .. } | Stmt::TestStmt { .. }
+        );
+        if stepped && self.debug_hook.is_some() {
+            self.pause(stmt)?;
+        }
+        if let Some(profiler) = self.profiler.as_ref().filter(|_| stepped) {
+            profiler.borrow_mut().statement(&self.source, self.line + 1);
+        }
+        match stmt {
+            Stmt::ExpressionStmt { expression, .. } => self.evaluate(expression),
+            Stmt::BlockStmt { body, .. } => {
+                let environment = self.environment.child();
+                self.execute_block(body, environment)
+            }
+            Stmt::IfStmt {
+                condition,
+                consequent,
+                alternate,
+                ..
+            } => {
+                if self.evaluate(condition)?.is_truthy() {
+                    self.execute(consequent)?;
+                } else if let Some(alternate) = alternate {
+                    self.execute(alternate)?;
+                }
+                Ok(Object::Nil)
+            }
+            Stmt::VarDeclarationStmt {
+                identifier,
+                isConstant,
+                assignedValue,
+                explicitType,
+                ..
+            } => {
+                let value = self.evaluate(assignedValue)?;
+                if let Some(annotation) = explicitType {
+                    self.check_annotation(*identifier, annotation, &value)?;
+                }
+                self.environment.define(*identifier, value, *isConstant);
+                Ok(Object::Nil)
+            }
+            Stmt::ReturnStmt { value, .. } => {
+                let value = match value {
+                    Some(value) => self.evaluate(value)?,
+                    None => Object::Nil,
+                };
+                Err(Unwind::Return(value))
+            }
+            Stmt::OperatorDeclStmt {
+                operator,
+                fixity,
+                function,
+                ..
+            } => {
+                let function = self.evaluate(function)?;
+                let name = fixity.binding_name(operator.lexeme);
+                self.environment.define(name, function, true);
+                Ok(Object::Nil)
+            }
+            Stmt::ImportStmt { path, import, .. } => self.execute_import(path, import),
+            Stmt::ExportStmt { declaration, .. } => {
+                if !self.environment.same_scope(&self.globals) {
+                    return Err(self
+                        .error("Only declarations at the top of a file can be exported")
+                        .into());
+                }
+                self.execute(declaration)?;
+                match declaration.as_ref() {
+                    Stmt::VarDeclarationStmt { identifier, .. } => self.exports.push(*identifier),
+                    Stmt::EnumDeclStmt { name, .. } => self.exports.push(*name),
+                    _ => {}
+                }
+                Ok(Object::Nil)
+            }
+            Stmt::TryStmt {
+                body,
+                catch,
+                finally,
+                ..
+            } => self.execute_try(body, catch.as_ref(), finally.as_deref()),
+            Stmt::ThrowStmt { value, .. } => self.execute_throw(value),
+            // Tests only run under `ND-Lang test`, one at a time
+            Stmt::TestStmt { .. } => Ok(Object::Nil),
+            Stmt::EnumDeclStmt { name, variants, .. } => {
+                let enum_type = EnumType {
+                    name: *name,
+                    variants: variants.clone(),
+                };
+                self.environment
+                    .define(*name, Object::Enum(Rc::new(enum_type)), true);
+                Ok(Object::Nil)
+            }
+        }
+    }
+
+    ///Hands the program to the debugger before `stmt` runs. The debugger
+    ///is detached meanwhile, so what it evaluates does not reach it again.
+    fn pause(&mut self, stmt: &Stmt) -> Result<(), Unwind> {
+        let Some(mut hook) = self.debug_hook.take() else {
+            return Ok(());
+        };
+        let result = hook.before_statement(self, stmt);
+        // A debugger that stopped the program is done with it, and leaves
+        // the `finally` blocks to run without pausing
+        if result.is_ok() {
+            self.debug_hook = Some(hook);
+        }
+        result.map_err(Unwind::Stop)
+    }
+
+    fn execute_import(&mut self, path: &str, import: &Import) -> Result<Object, Unwind> {
+        let module = self.import(path)?;
+        match import {
+            Import::Module(alias) => {
+                self.environment
+                    .define(*alias, Object::Module(module), true);
+            }
+            Import::Names(names) => {
+                for name in names {
+                    let value = self.export_of(&module, *name)?;
+                    self.environment.define(*name, value, true);
+                }
+            }
+        }
+        Ok(Object::Nil)
+    }
+
+    fn execute_try(
+        &mut self,
+        body: &Stmt,
+        catch: Option<&Catch>,
+        finally: Option<&Stmt>,
+    ) -> Result<Object, Unwind> {
+        let mut result = self.execute(body);
+        if let (Err(Unwind::Error(diagnostic)), Some(catch)) = (&result, catch) {
+            let environment = self.environment.child();
+            if let Some(name) = catch.name {
+                let error = ErrorValue::from(diagnostic);
+                environment.define(name, Object::Error(Rc::new(error)), false);
+            }
+            result = self.execute_block(std::slice::from_ref(catch.body.as_ref()), environment);
+        }
+        // An error or `ret` in the `finally` block wins over the
+        // outcome of the others
+        if let Some(finally) = finally {
+            self.execute(finally)?;
+        }
+        result.map(|_| Object::Nil)
+    }
+
+    fn execute_throw(&mut self, value: &Expr) -> Result<Object, Unwind> {
+        let mut error = match self.evaluate(value)? {
+            Object::Error(error) => error.as_ref().clone(),
+            value => ErrorValue::new("Error", &value.to_string()),
+        };
+        // Errors thrown again keep the trace of their first throw
+        if error.trace.is_empty() {
+            error.trace = self.trace(0);
+        }
+        let diagnostic = Diagnostic::new(0, self.line, error.message, "".to_string())
+            .with_kind(&error.kind)
+            .with_trace(error.trace);
+        Err(Unwind::Error(diagnostic))
+    }
+
+    ///Evaluates the module `path` refers to, or returns it from the cache
+    ///if it ran already
+    pub fn import(&mut self, path: &str) -> Result<Rc<Module>, Diagnostic> {
+        if let Some(module) = self.modules.borrow().builtin(path) {
+            return Ok(module);
+        }
+        let resolved = self.modules.borrow().resolve(path, self.file.as_deref());
+        let resolved = match resolved {
+            Some(resolved) => resolved,
+            None => {
+                let mut searched = vec![match self.file.as_deref().and_then(Path::parent) {
+                    Some(directory) => directory.display().to_string(),
+                    None => ".".to_string(),
+                }];
+                for directory in self.modules.borrow().search_paths() {
+                    searched.push(directory.display().to_string());
+                }
+                return Err(Diagnostic::new(
+                    0,
+                    self.line,
+                    format!("Cannot find module \"{}\"", path),
+                    format!("Searched in {}", searched.join(", ")),
+                ));
+            }
+        };
+
+        if let Some(module) = self.modules.borrow().cached(&resolved) {
+            return Ok(module);
+        }
+        if let Err(cycle) = self.modules.borrow_mut().begin(&resolved) {
+            return Err(Diagnostic::new(
+                0,
+                self.line,
+                format!("Circular import: {}", cycle),
+                "Modules cannot import each other; move what they share into a third module"
+                    .to_string(),
+            ));
+        }
+
+        let module = self.evaluate_module(&resolved);
+        self.modules
+            .borrow_mut()
+            .finish(&resolved, module.as_ref().ok().cloned());
+        module
+    }
+
+    ///Parses and runs a module file in an interpreter of its own
+    fn evaluate_module(&mut self, path: &Path) -> Result<Rc<Module>, Diagnostic> {
+        let name = module_name(path);
+        // Reading a module from elsewhere is reading any file, so the
+        // sandbox of `fs` applies to it too
+        if !self.modules.borrow().can_import(path) {
+            return Err(Diagnostic::new(
+                0,
+                self.line,
+                format!("Not allowed to import \"{}\"", name),
+                "Allow reading it with --allow-read".to_string(),
+            ));
+        }
+        let source = std::fs::read_to_string(path).map_err(|error| {
+            Diagnostic::new(
+                0,
+                self.line,
+                format!("Cannot read module \"{}\": {}", name, error),
+                "".to_string(),
+            )
+        })?;
 
+        let mut parser = Parser::new(Scanner::new(&source, name.clone()));
+        let statements = parser.parse();
+        // The message of a syntax error may quote the module's source,
+        // which the importing script must not be able to catch and read
+        if let Some(error) = parser.errors().first() {
+            return Err(Diagnostic::new(
+                0,
+                self.line,
+                format!(
+                    "Module \"{}\" has a syntax error on line {}",
+                    name,
+                    error.line() + 1
+                ),
+                "Fix the module before importing it".to_string(),
+            ));
+        }
+        let statements = Optimizer::new(self.opt_level).optimize(statements);
+
+        let mut interpreter =
+            Interpreter::with_modules(self.modules.clone(), Some(path.to_path_buf()));
+        interpreter.set_source_name(parser.scanner().file_name());
+        interpreter.set_max_depth(self.max_depth);
+        interpreter.profiler = self.profiler.clone();
+        interpreter.opt_level = self.opt_level;
+        interpreter
+            .interpret(&statements)
+            .map_err(|error| error.in_file(name))?;
+
+        Ok(Rc::new(Module {
+            path: path.to_path_buf(),
+            exports: interpreter.exports().into_iter().collect(),
+        }))
+    }
+
+    ///Returns the function of built-in module `module` called `name`,
+    ///bound to `receiver`
+    fn method(&self, receiver: Object, module: &str, name: Symbol) -> Result<Object, Diagnostic> {
+        let method = self
+            .modules
+            .borrow()
+            .builtin(module)
+            .and_then(|module| module.exports.get(&name).cloned());
+        match method {
+            Some(Object::Native(function)) => Ok(Object::Native(Rc::new(function.bind(receiver)))),
+            _ => Err(self.error(&format!(
+                "Values of type {} have no method \"{}\"",
+                receiver.type_name(),
+                name
+            ))),
+        }
+    }
+
+    fn index(&self, object: Object, index: Object) -> Result<Object, Diagnostic> {
+        let position = match index {
+            Object::Num(n) if n.fract() == 0.0 && n >= 0.0 => n as usize,
+            index => {
+                return Err(self
+                    .error(&format!(
+                        "Cannot index with {}, indexes are whole numbers from 0",
+                        index
+                    ))
+                    .with_kind("TypeError"))
+            }
+        };
+        let (item, length) = match &object {
+            Object::List(items) => (items.get(position).cloned(), items.len()),
+            Object::Str(text) => (
+                text.chars()
+                    .nth(position)
+                    .map(|c| Object::Str(c.to_string())),
+                text.chars().count(),
+            ),
+            _ => {
+                return Err(self
+                    .error(&format!(
+                        "Cannot index a value of type {}",
+                        object.type_name()
+                    ))
+                    .with_kind("TypeError"))
+            }
+        };
+        item.ok_or_else(|| {
+            self.error(&format!(
+                "Index {} is out of range for a {} of length {}",
+                position,
+                object.type_name(),
+                length
+            ))
+            .with_kind("IndexError")
+        })
+    }
+
+    ///Returns the value of variant `name` of an enum if it has no fields,
+    ///or the function making values of it from their fields
+    fn variant(&self, enum_type: &Rc<EnumType>, name: Symbol) -> Result<Object, Diagnostic> {
+        let Some(position) = enum_type
+            .variants
+            .iter()
+            .position(|variant| variant.name == name)
+        else {
+            return Err(self.error(&format!(
+                "Enum \"{}\" has no variant \"{}\"",
+                enum_type.name, name
+            )));
+        };
+
+        let arity = enum_type.variants[position].fields.len();
+        let make = {
+            let enum_type = enum_type.clone();
+            move |values: &[Object]| {
+                Object::Variant(Rc::new(VariantValue {
+                    enum_type: enum_type.clone(),
+                    variant: position,
+                    values: values.to_vec(),
+                }))
+            }
+        };
+        if arity == 0 {
+            return Ok(make(&[]));
+        }
+        let constructor = NativeFunction::new(
+            &format!("{}.{}", enum_type.name, name),
+            Some(arity),
+            move |values| Ok(make(values)),
+        );
+        Ok(Object::Native(Rc::new(constructor)))
+    }
+
+    fn export_of(&self, module: &Module, name: Symbol) -> Result<Object, Diagnostic> {
+        match module.exports.get(&name) {
+            Some(value) => Ok(value.clone()),
+            None => Err(self.error(&format!(
+                "Module \"{}\" does not export \"{}\"",
+                module.name(),
+                name
+            ))),
+        }
+    }
+
+    ///Runs statements in the given scope, restoring the current scope
+    ///afterwards
+    pub fn execute_block(
+        &mut self,
+        body: &[Stmt],
+        environment: Environment,
+    ) -> Result<Object, Unwind> {
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let result = body
+            .iter()
+            .try_for_each(|stmt| self.execute(stmt).map(|_| ()));
+        self.environment = previous;
+        result.map(|_| Object::Nil)
+    }
+
+    pub fn evaluate(&mut self, expr: &Expr) -> Result<Object, Unwind> {
+        // Deeply nested expressions recurse through here, so the stack
+        // grows as it does for calls
+        stacker::maybe_grow(STACK_RED_ZONE, STACK_GROWTH, || self.evaluate_nested(expr))
+    }
+
+    fn evaluate_nested(&mut self, expr: &Expr) -> Result<Object, Unwind> {
+        match expr {
+            Expr::Number(n) => match n.parse::<f64>() {
+                Ok(n) => Ok(Object::Num(n)),
+                Err(_) => Err(self.error(&format!("Invalid number \"{}\"", n)).into()),
+            },
+            Expr::String(s) => Ok(Object::Str(s.to_string())),
+            Expr::Bool(b) => Ok(Object::Bool(*b)),
+            Expr::Null => Ok(Object::Nil),
+            Expr::Identifier(name) => self.lookup(*name),
+            Expr::Grouping { group } => self.evaluate(group),
+            Expr::Unary { op, right } => {
+                let right = self.evaluate(right)?;
                 match op.ttype {
-                    TokenType::PLUS => return left + right,
-                    TokenType::STAR => return left * right,
-                    TokenType::POW => return left.pow(right),
-                    _ => todo!(),
+                    TokenType::OPERATOR => self.call_operator("prefix", op, vec![right]),
+                    TokenType::BANG => Ok(Object::Bool(!right.is_truthy())),
+                    TokenType::MINUS => match right {
+                        Object::Num(n) => Ok(Object::Num(-n)),
+                        right => Err(self.type_error(op, &[right]).into()),
+                    },
+                    _ => Err(self.unsupported(op).into()),
+                }
+            }
+            Expr::BinaryOp { left, op, right } => {
+                let left = self.evaluate(left)?;
+                // `&&` and `||` only evaluate their right side when needed
+                match op.ttype {
+                    TokenType::AND if !left.is_truthy() => return Ok(left),
+                    TokenType::OR if left.is_truthy() => return Ok(left),
+                    TokenType::AND | TokenType::OR => return self.evaluate(right),
+                    _ => {}
+                }
+
+                let right = self.evaluate(right)?;
+                if op.ttype == TokenType::OPERATOR {
+                    return self.call_operator("infix", op, vec![left, right]);
                 }
+                Ok(self.binary(op, left, right)?)
             }
-            Expr::Number(n) => n.parse::<T>().unwrap(),
-            _ => todo!(),
+            Expr::Assignment {
+                assignee,
+                op,
+                assigned,
+            } => {
+                let name = match assignee.as_ref() {
+                    Expr::Identifier(name) => *name,
+                    _ => return Err(self.error_at(op, "Can only assign to a variable").into()),
+                };
+                let mut value = self.evaluate(assigned)?;
+                // `+=` and `-=` update the current value
+                let update = match op.ttype {
+                    TokenType::PLUSEQUALS => Some(TokenType::PLUS),
+                    TokenType::MINUSEQUALS => Some(TokenType::MINUS),
+                    _ => None,
+                };
+                if let Some(ttype) = update {
+                    let current = self.lookup(name)?;
+                    let mut op = op.clone();
+                    op.ttype = ttype;
+                    value = self.binary(&op, current, value)?;
+                }
+
+                match self.environment.assign(name, value.clone()) {
+                    Ok(()) => Ok(value),
+                    Err(AssignError::Undefined) => Err(self
+                        .error_at(op, &format!("Undefined variable \"{}\"", name))
+                        .with_kind("NameError")
+                        .into()),
+                    Err(AssignError::Constant) => Err(self
+                        .error_at(op, &format!("Cannot assign to constant \"{}\"", name))
+                        .into()),
+                }
+            }
+            Expr::Function { params, body, .. } => Ok(Object::Func(Rc::new(Function {
+                params: params.clone(),
+                body: body.clone(),
+                closure: self.environment.clone(),
+                source: self.source.clone(),
+            }))),
+            Expr::List(items) => {
+                let items = items
+                    .iter()
+                    .map(|item| self.evaluate(item))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Object::List(Rc::new(items)))
+            }
+            Expr::Index { object, index } => {
+                let object = self.evaluate(object)?;
+                let index = self.evaluate(index)?;
+                Ok(self.index(object, index)?)
+            }
+            Expr::Member { object, property } => {
+                let object = self.evaluate(object)?;
+                self.member(object, *property)
+            }
+            Expr::Propagate { value, line } => {
+                let value = self.evaluate(value)?;
+                self.line = *line;
+                match &value {
+                    Object::Variant(variant) if is_builtin(&variant.enum_type) => {
+                        match variant.values.first() {
+                            Some(held) if variant.variant == 0 => Ok(held.clone()),
+                            _ => Err(Unwind::Return(value)),
+                        }
+                    }
+                    _ => Err(self
+                        .error(&format!(
+                            "\"?\" needs a Result or an Option, not a value of type {}",
+                            value.type_name()
+                        ))
+                        .with_kind("TypeError")
+                        .into()),
+                }
+            }
+            Expr::Call {
+                callee,
+                args,
+                line,
+                position,
+            } => self.evaluate_call(callee, args, *line, *position),
+            Expr::Match {
+                value, arms, line, ..
+            } => self.evaluate_match(value, arms, *line),
+        }
+    }
+
+    ///Evaluates a call. Kept out of `evaluate`, like the other large
+    ///cases, so each nested call takes less of the Rust stack.
+    fn evaluate_call(
+        &mut self,
+        callee_expr: &Expr,
+        args: &[Expr],
+        line: usize,
+        position: usize,
+    ) -> Result<Object, Unwind> {
+        // Methods of `Result` and `Option` values are run here, as
+        // `map` calls back into the program
+        let (callee, receiver) = match callee_expr {
+            Expr::Member { object, property } => match self.evaluate(object)? {
+                Object::Variant(value)
+                    if is_builtin(&value.enum_type)
+                        && BUILTIN_METHODS.iter().any(|method| *property == *method) =>
+                {
+                    (Object::Nil, Some((value, *property)))
+                }
+                object => (self.member(object, *property)?, None),
+            },
+            callee_expr => (self.evaluate(callee_expr)?, None),
+        };
+        let args = args
+            .iter()
+            .map(|arg| self.evaluate(arg))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.line = line;
+        self.position = position;
+
+        if self.frames.len() >= self.max_depth {
+            let message = format!("Stack overflow: more than {} nested calls", self.max_depth);
+            return Err(Diagnostic::new(
+                position,
+                line,
+                message,
+                "Check that the recursion has a base case that is reached".to_string(),
+            )
+            .with_kind("RecursionError")
+            .with_trace(self.trace(position))
+            .into());
+        }
+
+        let name = match callee_expr {
+            Expr::Identifier(_) | Expr::Member { .. } => callee_expr.to_string(),
+            _ => "<anonymous>".to_string(),
+        };
+        if let Some(profiler) = &self.profiler {
+            profiler.borrow_mut().enter(&name);
+        }
+        self.frames.push(Frame {
+            name,
+            source: self.source.clone(),
+            line,
+            position,
+            environment: self.environment.clone(),
+        });
+        // Each call takes tens of kilobytes of stack in debug builds, so
+        // the stack grows on the heap rather than overflowing the thread
+        let result = stacker::maybe_grow(STACK_RED_ZONE, STACK_GROWTH, || match receiver {
+            Some((value, method)) => self.builtin_method(&value, method, args),
+            None => self.call(callee, args),
+        });
+        self.frames.pop();
+        if let Some(profiler) = &self.profiler {
+            profiler.borrow_mut().exit();
+        }
+        result
+    }
+
+    ///Evaluates the first arm whose pattern matches `value`
+    fn evaluate_match(
+        &mut self,
+        value: &Expr,
+        arms: &[MatchArm],
+        line: usize,
+    ) -> Result<Object, Unwind> {
+        let value = self.evaluate(value)?;
+        for arm in arms {
+            let environment = self.environment.child();
+            if !self.matches(&arm.pattern, &value, &environment)? {
+                continue;
+            }
+            let previous = std::mem::replace(&mut self.environment, environment);
+            let result = self.evaluate_arm(arm);
+            self.environment = previous;
+            if let Some(result) = result? {
+                return Ok(result);
+            }
+        }
+        self.line = line;
+        let message = format!("No match arm matches {:?}", value);
+        Err(self.error(&message).into())
+    }
+
+    ///Reads `property` of a value: an export of a module, a method of a
+    ///string, a variant of an enum or a field of a variant or an error
+    fn member(&self, object: Object, property: Symbol) -> Result<Object, Unwind> {
+        match object {
+            Object::Module(module) => Ok(self.export_of(&module, property)?),
+            Object::Str(text) => Ok(self.method(Object::Str(text), "string", property)?),
+            Object::Enum(enum_type) => Ok(self.variant(&enum_type, property)?),
+            Object::Error(error) => match property.as_str().unwrap_or_default() {
+                "message" => Ok(Object::Str(error.message.clone())),
+                "kind" => Ok(Object::Str(error.kind.clone())),
+                "trace" => {
+                    let trace = error.trace.iter().cloned().map(Object::Str).collect();
+                    Ok(Object::List(Rc::new(trace)))
+                }
+                _ => Err(self
+                    .error(&format!("Errors have no field \"{}\"", property))
+                    .into()),
+            },
+            Object::Variant(value) => value.field(property).ok_or_else(|| {
+                self.error(&format!(
+                    "\"{}.{}\" has no field \"{}\"",
+                    value.enum_type.name,
+                    value.declaration().name,
+                    property
+                ))
+                .into()
+            }),
+            object => Err(self
+                .error(&format!(
+                    "Cannot read \"{}\" of a value of type {}",
+                    property,
+                    object.type_name()
+                ))
+                .into()),
+        }
+    }
+
+    ///Runs method `name` of a `Result` or `Option` value
+    fn builtin_method(
+        &mut self,
+        value: &Rc<VariantValue>,
+        name: Symbol,
+        args: Vec<Object>,
+    ) -> Result<Object, Unwind> {
+        let is_result = value.enum_type.name == "Result";
+        let arity = match name.as_str().unwrap_or_default() {
+            "unwrap_or" | "map" => 1,
+            _ => 0,
+        };
+        if args.len() != arity {
+            let message = format!(
+                "{} expects {} arguments but got {}",
+                name,
+                arity,
+                args.len()
+            );
+            return Err(self.error(&message).with_kind("TypeError").into());
+        }
+
+        // `Ok` and `Some` come first in their enums
+        let held = value.values.first().filter(|_| value.variant == 0);
+        match (name.as_str().unwrap_or_default(), held) {
+            ("is_ok", _) if is_result => Ok(Object::Bool(held.is_some())),
+            ("is_err", _) if is_result => Ok(Object::Bool(held.is_none())),
+            ("is_some", _) if !is_result => Ok(Object::Bool(held.is_some())),
+            ("is_none", _) if !is_result => Ok(Object::Bool(held.is_none())),
+            ("unwrap", Some(held)) => Ok(held.clone()),
+            ("unwrap", None) => Err(self
+                .error(&format!(
+                    "Called unwrap on {}",
+                    Object::Variant(value.clone())
+                ))
+                .with_kind("ValueError")
+                .into()),
+            ("unwrap_or", Some(held)) => Ok(held.clone()),
+            ("unwrap_or", None) => Ok(args[0].clone()),
+            ("map", Some(held)) => {
+                let mapped = self.call(args[0].clone(), vec![held.clone()])?;
+                Ok(Object::Variant(Rc::new(VariantValue {
+                    enum_type: value.enum_type.clone(),
+                    variant: 0,
+                    values: vec![mapped],
+                })))
+            }
+            ("map", None) => Ok(Object::Variant(value.clone())),
+            _ => Err(self
+                .error(&format!(
+                    "{} values have no method \"{}\"",
+                    value.enum_type.name, name
+                ))
+                .into()),
+        }
+    }
+
+    ///Evaluates the body of an arm whose pattern matched, or returns
+    ///`None` if its guard is false
+    fn evaluate_arm(&mut self, arm: &MatchArm) -> Result<Option<Object>, Unwind> {
+        if let Some(guard) = &arm.guard {
+            if !self.evaluate(guard)?.is_truthy() {
+                return Ok(None);
+            }
+        }
+        self.evaluate(&arm.body).map(Some)
+    }
+
+    ///Checks whether `value` matches `pattern`, binding the names in the
+    ///pattern in `environment`
+    fn matches(
+        &mut self,
+        pattern: &Pattern,
+        value: &Object,
+        environment: &Environment,
+    ) -> Result<bool, Unwind> {
+        match pattern {
+            Pattern::Wildcard => Ok(true),
+            Pattern::Binding(name) => {
+                environment.define(*name, value.clone(), false);
+                Ok(true)
+            }
+            Pattern::Literal(literal) => Ok(self.evaluate(literal)? == *value),
+            Pattern::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                let start = self.evaluate(start)?;
+                let end = self.evaluate(end)?;
+                let ordering = |a: &Object, b: &Object| match (a, b) {
+                    (Object::Num(a), Object::Num(b)) => a.partial_cmp(b),
+                    (Object::Str(a), Object::Str(b)) => Some(a.cmp(b)),
+                    _ => None,
+                };
+                let above_start = ordering(&start, value).is_some_and(Ordering::is_le);
+                let below_end = match ordering(value, &end) {
+                    Some(Ordering::Less) => true,
+                    Some(Ordering::Equal) => *inclusive,
+                    _ => false,
+                };
+                Ok(above_start && below_end)
+            }
+            Pattern::List {
+                items: patterns,
+                rest,
+            } => {
+                let Object::List(items) = value else {
+                    return Ok(false);
+                };
+                let fits = match rest {
+                    Some(_) => items.len() >= patterns.len(),
+                    None => items.len() == patterns.len(),
+                };
+                if !fits {
+                    return Ok(false);
+                }
+                for (pattern, item) in patterns.iter().zip(items.iter()) {
+                    if !self.matches(pattern, item, environment)? {
+                        return Ok(false);
+                    }
+                }
+                if let Some(Rest {
+                    binding: Some(name),
+                }) = rest
+                {
+                    let rest = items[patterns.len()..].to_vec();
+                    environment.define(*name, Object::List(Rc::new(rest)), false);
+                }
+                Ok(true)
+            }
+            Pattern::Variant {
+                enum_name,
+                variant,
+                fields,
+            } => {
+                let enum_type = match self.lookup(*enum_name)? {
+                    Object::Enum(enum_type) => enum_type,
+                    object => {
+                        return Err(self
+                            .error(&format!(
+                                "\"{}\" is a {}, not an enum",
+                                enum_name,
+                                object.type_name()
+                            ))
+                            .into())
+                    }
+                };
+                if !enum_type
+                    .variants
+                    .iter()
+                    .any(|declared| declared.name == *variant)
+                {
+                    return Err(self
+                        .error(&format!(
+                            "Enum \"{}\" has no variant \"{}\"",
+                            enum_name, variant
+                        ))
+                        .into());
+                }
+                let Object::Variant(value) = value else {
+                    return Ok(false);
+                };
+                if !Rc::ptr_eq(&value.enum_type, &enum_type)
+                    || value.declaration().name != *variant
+                    || value.values.len() != fields.len()
+                {
+                    return Ok(false);
+                }
+                for (pattern, item) in fields.iter().zip(value.values.iter()) {
+                    if !self.matches(pattern, item, environment)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+        }
+    }
+
+    ///Calls a function value with the given arguments
+    pub fn call(&mut self, callee: Object, args: Vec<Object>) -> Result<Object, Unwind> {
+        let function = match callee {
+            Object::Func(function) => function,
+            Object::Native(native) => match assert::assertion(&native) {
+                Some(assertion) => return self.call_assertion(assertion, args),
+                None => return self.call_native(&native, &args),
+            },
+            callee => {
+                let message = format!("Cannot call a value of type {}", callee.type_name());
+                return Err(self.error(&message).with_kind("TypeError").into());
+            }
+        };
+        if args.len() != function.params.len() {
+            let message = format!(
+                "Expected {} arguments but got {}",
+                function.params.len(),
+                args.len()
+            );
+            return Err(self.error(&message).with_kind("TypeError").into());
+        }
+
+        let environment = function.closure.child();
+        for (param, arg) in function.params.iter().zip(args) {
+            environment.define(*param, arg, false);
+        }
+
+        let (line, position) = (self.line, self.position);
+        let source = std::mem::replace(&mut self.source, function.source.clone());
+        let result = self.execute_block(&function.body, environment);
+        self.source = source;
+        (self.line, self.position) = (line, position);
+        match result {
+            Ok(_) => Ok(Object::Nil),
+            Err(Unwind::Return(value)) => Ok(value),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn call_native(&mut self, native: &NativeFunction, args: &[Object]) -> Result<Object, Unwind> {
+        if let Some(arity) = native.arity {
+            if args.len() != arity {
+                let message = format!(
+                    "{} expects {} arguments but got {}",
+                    native.name,
+                    arity,
+                    args.len()
+                );
+                return Err(self.error(&message).with_kind("TypeError").into());
+            }
+        }
+        (native.function)(args).map_err(|message| self.error(&message).into())
+    }
+
+    fn call_assertion(
+        &mut self,
+        assertion: Assertion,
+        args: Vec<Object>,
+    ) -> Result<Object, Unwind> {
+        let (fewest, most) = assertion.arity();
+        if args.len() < fewest || args.len() > most {
+            let message = format!(
+                "{} expects {} or {} arguments but got {}",
+                assertion.name(),
+                fewest,
+                most,
+                args.len()
+            );
+            return Err(self.error(&message).with_kind("TypeError").into());
+        }
+        let failure = match assertion {
+            Assertion::Assert => assert::check(&args),
+            Assertion::AssertEq => assert::check_eq(&args),
+            Assertion::AssertThrows => {
+                let kind = match args.get(1) {
+                    Some(Object::Str(kind)) => Some(kind.clone()),
+                    Some(kind) => {
+                        let message = format!(
+                            "assert_throws expects a string kind but got {}",
+                            kind.type_name()
+                        );
+                        return Err(self.error(&message).with_kind("TypeError").into());
+                    }
+                    None => None,
+                };
+                match self.call(args[0].clone(), Vec::new()) {
+                    Err(Unwind::Error(diagnostic)) => {
+                        let error = ErrorValue::from(&diagnostic);
+                        match kind {
+                            Some(kind) if kind != error.kind => Err(format!(
+                                "assert_throws expected a {} but got {}: {}",
+                                kind, error.kind, error.message
+                            )),
+                            _ => return Ok(Object::Error(Rc::new(error))),
+                        }
+                    }
+                    Err(stop @ Unwind::Stop(_)) => return Err(stop),
+                    Ok(value) | Err(Unwind::Return(value)) => Err(format!(
+                        "assert_throws expected an error but the function returned {:?}",
+                        value
+                    )),
+                }
+            }
+        };
+        match failure {
+            Ok(()) => Ok(Object::Nil),
+            Err(message) => Err(self.error(&message).with_kind(ASSERTION_ERROR).into()),
+        }
+    }
+
+    ///Calls the function bound to a declared operator
+    fn call_operator(
+        &mut self,
+        keyword: &str,
+        op: &Token,
+        args: Vec<Object>,
+    ) -> Result<Object, Unwind> {
+        let name = operator_binding(keyword, op.lexeme);
+        match self.environment.get(name) {
+            Some(function) => self.call(function, args),
+            None => Err(self
+                .error_at(
+                    op,
+                    &format!("Operator \"{}\" is not defined here", op.lexeme),
+                )
+                .into()),
+        }
+    }
+
+    fn binary(&self, op: &Token, left: Object, right: Object) -> Result<Object, Diagnostic> {
+        let value = match (op.ttype.clone(), &left, &right) {
+            (TokenType::EQUALEQUAL, _, _) => Object::Bool(left == right),
+            (TokenType::BANGEQUAL, _, _) => Object::Bool(left != right),
+            (TokenType::PLUS, Object::Num(a), Object::Num(b)) => Object::Num(a + b),
+            // Anything can be appended to a string
+            (TokenType::PLUS, Object::Str(_), _) | (TokenType::PLUS, _, Object::Str(_)) => {
+                Object::Str(format!("{}{}", left, right))
+            }
+            (TokenType::MINUS, Object::Num(a), Object::Num(b)) => Object::Num(a - b),
+            (TokenType::STAR, Object::Num(a), Object::Num(b)) => Object::Num(a * b),
+            (TokenType::SLASH | TokenType::MODULO, Object::Num(_), Object::Num(b)) if *b == 0.0 => {
+                return Err(self
+                    .error_at(op, "Division by zero")
+                    .with_kind("ZeroDivisionError"))
+            }
+            (TokenType::SLASH, Object::Num(a), Object::Num(b)) => Object::Num(a / b),
+            (TokenType::MODULO, Object::Num(a), Object::Num(b)) => Object::Num(a % b),
+            (TokenType::POW, Object::Num(a), Object::Num(b)) => Object::Num(a.powf(*b)),
+            (TokenType::LESS, Object::Num(a), Object::Num(b)) => Object::Bool(a < b),
+            (TokenType::LESSEQUAL, Object::Num(a), Object::Num(b)) => Object::Bool(a <= b),
+            (TokenType::GREATER, Object::Num(a), Object::Num(b)) => Object::Bool(a > b),
+            (TokenType::GREATEREQUAL, Object::Num(a), Object::Num(b)) => Object::Bool(a >= b),
+            (TokenType::LESS, Object::Str(a), Object::Str(b)) => Object::Bool(a < b),
+            (TokenType::LESSEQUAL, Object::Str(a), Object::Str(b)) => Object::Bool(a <= b),
+            (TokenType::GREATER, Object::Str(a), Object::Str(b)) => Object::Bool(a > b),
+            (TokenType::GREATEREQUAL, Object::Str(a), Object::Str(b)) => Object::Bool(a >= b),
+            _ => return Err(self.type_error(op, &[left, right])),
+        };
+        Ok(value)
+    }
+
+    fn lookup(&self, name: Symbol) -> Result<Object, Unwind> {
+        match self.environment.get(name) {
+            Some(value) => Ok(value),
+            None => Err(self
+                .error(&format!("Undefined variable \"{}\"", name))
+                .with_kind("NameError")
+                .into()),
+        }
+    }
+
+    ///Returns the calls being run, innermost first, ending with the top
+    ///level of the script, for debuggers to show
+    pub fn stack(&self) -> Vec<StackFrame> {
+        self.stack_at(0)
+    }
+
+    fn stack_at(&self, position: usize) -> Vec<StackFrame> {
+        let mut stack = Vec::new();
+        let mut source = self.source.clone();
+        let (mut line, mut position) = (self.line, position);
+        let mut environment = self.environment.clone();
+        for frame in self.frames.iter().rev() {
+            stack.push(StackFrame {
+                name: frame.name.clone(),
+                source,
+                line,
+                position,
+                environment,
+            });
+            source = frame.source.clone();
+            (line, position) = (frame.line, frame.position);
+            environment = frame.environment.clone();
+        }
+        stack.push(StackFrame {
+            name: "<script>".to_string(),
+            source,
+            line,
+            position,
+            environment,
+        });
+        stack
+    }
+
+    ///Returns the names bound in the scopes of a frame, innermost first,
+    ///leaving out the globals
+    pub fn locals(&self, frame: &StackFrame) -> Vec<(Symbol, Object)> {
+        let mut locals = Vec::new();
+        let mut scope = Some(frame.environment.clone());
+        while let Some(environment) = scope {
+            if environment.same_scope(&self.globals) {
+                break;
+            }
+            for binding in environment.bindings() {
+                if !locals.iter().any(|(name, _)| *name == binding.0) {
+                    locals.push(binding);
+                }
+            }
+            scope = environment.parent();
+        }
+        locals
+    }
+
+    ///Evaluates an expression in the scope of a frame, as debuggers do
+    ///for a paused program
+    pub fn evaluate_in(
+        &mut self,
+        environment: Environment,
+        expr: &Expr,
+    ) -> Result<Object, Diagnostic> {
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let result = self.evaluate(expr);
+        self.environment = previous;
+        result.map_err(|unwind| match unwind {
+            Unwind::Error(diagnostic) | Unwind::Stop(diagnostic) => diagnostic,
+            Unwind::Return(_) => self.error("Cannot return from outside a function"),
+        })
+    }
+
+    ///Returns how many calls are being run
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    ///Returns the name of the file the code being run comes from
+    pub fn source_name(&self) -> &str {
+        &self.source
+    }
+
+    ///Describes the calls being run, innermost first, each with where
+    ///it is at: `position` on the current line for the innermost one, and
+    ///the call to the next one for the others. Runs of the same entry,
+    ///as in deep recursion, are shown once.
+    fn trace(&self, position: usize) -> Vec<String> {
+        let entries = self
+            .stack_at(position)
+            .into_iter()
+            .map(|frame| format!("at {} ({})", frame.name, frame.location()));
+
+        let mut trace: Vec<String> = Vec::new();
+        let mut repeated = 0;
+        for entry in entries {
+            if trace.last() == Some(&entry) {
+                repeated += 1;
+                continue;
+            }
+            if repeated > 0 {
+                trace.push(format!("... repeated {} more times", repeated));
+                repeated = 0;
+            }
+            trace.push(entry);
+        }
+        if repeated > 0 {
+            trace.push(format!("... repeated {} more times", repeated));
+        }
+        trace
+    }
+
+    ///Checks that a binding annotated as `Result` or `Option` holds one.
+    ///Other annotations are not checked.
+    fn check_annotation(
+        &self,
+        name: Symbol,
+        annotation: &Token,
+        value: &Object,
+    ) -> Result<(), Diagnostic> {
+        if !["Result", "Option"].contains(&annotation.lexeme) {
+            return Ok(());
+        }
+        let value_type = match value {
+            Object::Variant(value) if is_builtin(&value.enum_type) => {
+                value.enum_type.name.as_str().unwrap_or_default()
+            }
+            value => value.type_name(),
+        };
+        if value_type == annotation.lexeme {
+            return Ok(());
         }
+        let tip = match annotation.lexeme {
+            "Result" => "Wrap the value: Ok(value) or Err(error)",
+            _ => "Wrap the value: Some(value), or use None",
+        };
+        Err(Diagnostic::new(
+            annotation.position,
+            annotation.line,
+            format!(
+                "\"{}\" is declared as {} but its value is a {}",
+                name, annotation.lexeme, value_type
+            ),
+            tip.to_string(),
+        )
+        .with_trace(self.trace(annotation.position))
+        .with_kind("TypeError"))
+    }
+
+    ///Creates an error at the innermost call being run, for errors without
+    ///a token of their own to point at
+    fn error(&self, message: &str) -> Diagnostic {
+        Diagnostic::new(
+            self.position,
+            self.line,
+            message.to_string(),
+            "".to_string(),
+        )
+        .with_trace(self.trace(self.position))
+    }
+
+    fn error_at(&self, token: &Token, message: &str) -> Diagnostic {
+        Diagnostic::new(
+            token.position,
+            token.line,
+            message.to_string(),
+            "".to_string(),
+        )
+        .with_trace(self.trace(token.position))
+    }
+
+    fn type_error(&self, op: &Token, operands: &[Object]) -> Diagnostic {
+        let types: Vec<&str> = operands.iter().map(Object::type_name).collect();
+        self.error_at(
+            op,
+            &format!("Cannot apply \"{}\" to {}", op.lexeme, types.join(" and ")),
+        )
+        .with_kind("TypeError")
+    }
+
+    fn unsupported(&self, op: &Token) -> Diagnostic {
+        self.error_at(op, &format!("Unsupported operator \"{}\"", op.lexeme))
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+///Formats where code is in a stack trace: `file:line:position`, with
+///lines counted from 1 and without the position when it is not known
+fn location(source: &str, line: usize, position: usize) -> String {
+    match position {
+        0 => format!("{}:{}", source, line + 1),
+        position => format!("{}:{}:{}", source, line + 1, position),
     }
 }
@@ -0,0 +1,141 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crate::{
+    object::object::{module_name, Module},
+    stdlib::{
+        fs::{self, Capabilities},
+        io, math, string,
+    },
+};
+
+/// The extension added to module paths written without one
+pub const MODULE_EXTENSION: &str = "ndl";
+
+/// Finds module files and keeps the modules evaluated so far, so each
+/// file runs once no matter how many files import it. Shared by the
+/// interpreters of every module in a program.
+#[derive(Default)]
+pub struct ModuleLoader {
+    /// The modules implemented in Rust, by name
+    builtins: HashMap<String, Rc<Module>>,
+    /// What the `fs` module is allowed to do, and which files outside the
+    /// program's directory and the search paths can be imported
+    capabilities: Rc<RefCell<Capabilities>>,
+    /// Directories searched after the one of the importing file
+    search_paths: Vec<PathBuf>,
+    cache: HashMap<PathBuf, Rc<Module>>,
+    /// The modules being evaluated, each imported by the one before it
+    loading: Vec<PathBuf>,
+}
+
+impl ModuleLoader {
+    ///Creates a loader knowing the standard library modules
+    pub fn new() -> Self {
+        let mut loader = Self::default();
+        loader.register_builtin(math::module());
+        loader.register_builtin(string::module());
+        loader.register_builtin(io::module());
+        loader.register_builtin(fs::module(loader.capabilities.clone()));
+        loader
+    }
+
+    ///Sets what scripts may do with files, for every module of the
+    ///program, including those that imported `fs` already
+    pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+        *self.capabilities.borrow_mut() = capabilities;
+    }
+
+    ///Checks whether scripts may import the module file at `path`. Files
+    ///in the directory of the program, or of the working directory without
+    ///one, and in the search paths always can; others need to be readable.
+    pub fn can_import(&self, path: &Path) -> bool {
+        let program = match self.loading.first().and_then(|entry| entry.parent()) {
+            Some(directory) => directory.to_path_buf(),
+            None => PathBuf::from("."),
+        };
+        let inside = std::iter::once(&program)
+            .chain(&self.search_paths)
+            .filter_map(|directory| directory.canonicalize().ok())
+            .any(|directory| path.starts_with(directory));
+        inside || self.capabilities.borrow().can_read(path)
+    }
+
+    ///Makes a module implemented in Rust importable by its name. Built-in
+    ///modules take precedence over files.
+    pub fn register_builtin(&mut self, module: Module) {
+        self.builtins.insert(module.name(), Rc::new(module));
+    }
+
+    ///Returns the built-in module called `name`
+    pub fn builtin(&self, name: &str) -> Option<Rc<Module>> {
+        self.builtins.get(name).cloned()
+    }
+
+    ///Adds a directory to look for modules in
+    pub fn add_search_path(&mut self, path: impl Into<PathBuf>) {
+        self.search_paths.push(path.into());
+    }
+
+    pub fn search_paths(&self) -> &[PathBuf] {
+        &self.search_paths
+    }
+
+    ///Finds the file `path` refers to when imported from `importer`:
+    ///relative to the importing file first, then in the search paths.
+    ///`.ndl` is added to paths without an extension.
+    pub fn resolve(&self, path: &str, importer: Option<&Path>) -> Option<PathBuf> {
+        let mut path = PathBuf::from(path);
+        if path.extension().is_none() {
+            path.set_extension(MODULE_EXTENSION);
+        }
+        if path.is_absolute() {
+            return path.canonicalize().ok();
+        }
+
+        // Without an importing file, as in the prompt, paths are relative
+        // to the working directory
+        let base = match importer.and_then(Path::parent) {
+            Some(directory) => directory.to_path_buf(),
+            None => PathBuf::from("."),
+        };
+        std::iter::once(&base)
+            .chain(&self.search_paths)
+            .map(|directory| directory.join(&path))
+            .find(|candidate| candidate.is_file())
+            .and_then(|candidate| candidate.canonicalize().ok())
+    }
+
+    ///Returns the module evaluated from `path`, if it was already
+    pub fn cached(&self, path: &Path) -> Option<Rc<Module>> {
+        self.cache.get(path).cloned()
+    }
+
+    ///Marks `path` as being evaluated. If it already is, the imports go
+    ///round in a circle, which is returned as the chain of file names.
+    pub fn begin(&mut self, path: &Path) -> Result<(), String> {
+        if let Some(start) = self.loading.iter().position(|loading| loading == path) {
+            let chain: Vec<String> = self.loading[start..]
+                .iter()
+                .chain(std::iter::once(&path.to_path_buf()))
+                .map(|path| module_name(path))
+                .collect();
+            return Err(chain.join(" -> "));
+        }
+        self.loading.push(path.to_path_buf());
+        Ok(())
+    }
+
+    ///Marks `path` as evaluated, caching the module if it ran without
+    ///errors
+    pub fn finish(&mut self, path: &Path, module: Option<Rc<Module>>) {
+        self.loading.retain(|loading| loading != path);
+        if let Some(module) = module {
+            self.cache.insert(path.to_path_buf(), module);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! The ND-Lang scanner, parser, interpreter and tooling, shared by the
+//! `ND-Lang` binary and the benchmarks. Programs embedding ND-Lang start
+//! from `Engine`.
+
+// Every module lives in a directory of the same name, as `parser::parser`
+#![allow(clippy::module_inception)]
+
+pub use engine::{
+    conversion::{FromValue, IntoArgs, IntoValue},
+    engine::{Engine, Error, Handle, Value},
+};
+
+pub mod debugger {
+    pub mod cli;
+    pub mod dap;
+    pub mod debugger;
+}
+pub mod engine {
+    pub mod conversion;
+    pub mod engine;
+}
+pub mod errors {
+    pub mod diagnostic;
+}
+pub mod formatter {
+    pub mod formatter;
+}
+pub mod interpreter {
+    pub mod environment;
+    pub mod interpreter;
+    pub mod modules;
+}
+pub mod linter {
+    pub mod linter;
+}
+pub mod lsp {
+    pub mod analysis;
+    pub mod server;
+}
+pub mod tokens {
+    pub mod symbol;
+    pub mod token;
+    pub mod token_type;
+}
+pub mod ast {
+    pub mod cst;
+    pub mod expr;
+    pub mod pattern;
+    pub mod stmt;
+}
+pub mod parser {
+    pub mod exhaustiveness;
+    pub mod helpers;
+    pub mod lookups;
+    pub mod parser;
+}
+pub mod optimizer {
+    pub mod optimizer;
+}
+pub mod profiler {
+    pub mod profiler;
+}
+pub mod object {
+    pub mod object;
+}
+pub mod stdlib {
+    pub mod assert;
+    pub mod fs;
+    pub mod helpers;
+    pub mod io;
+    pub mod math;
+    pub mod prelude;
+    pub mod string;
+}
+pub mod testing {
+    pub mod runner;
+}
+pub mod scanner {
+    pub mod scanner;
+}
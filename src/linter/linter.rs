@@ -0,0 +1,595 @@
+use core::fmt;
+use std::collections::HashMap;
+
+use crate::{
+    ast::{
+        expr::Expr,
+        stmt::{Import, Stmt},
+    },
+    optimizer::optimizer::{OptLevel, Optimizer},
+    tokens::{symbol::Symbol, token::Comment, token_type::TokenType},
+};
+
+/// How a rule's findings are reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Not reported at all
+    Allow,
+    Warn,
+    /// Reported as an error, failing `ND-Lang lint`
+    Deny,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Padded, to line up in `ND-Lang lint --rules`
+        f.pad(match self {
+            Level::Allow => "allowed",
+            Level::Warn => "warning",
+            Level::Deny => "error",
+        })
+    }
+}
+
+/// A check the linter makes, with the id it is configured and suppressed
+/// by.
+pub struct Rule {
+    pub id: &'static str,
+    pub level: Level,
+    pub description: &'static str,
+}
+
+pub const UNUSED_VARIABLE: &str = "unused-variable";
+pub const SHADOWED_BINDING: &str = "shadowed-binding";
+pub const PREFER_CONST: &str = "prefer-const";
+pub const UNREACHABLE_CODE: &str = "unreachable-code";
+pub const SELF_ASSIGNMENT: &str = "self-assignment";
+pub const CONSTANT_CONDITION: &str = "constant-condition";
+pub const ASSIGNMENT_IN_CONDITION: &str = "assignment-in-condition";
+pub const EMPTY_BLOCK: &str = "empty-block";
+
+/// Every rule, with the level it is reported at unless configured
+/// otherwise
+pub const RULES: [Rule; 8] = [
+    Rule {
+        id: UNUSED_VARIABLE,
+        level: Level::Warn,
+        description: "a variable is declared but never read; names starting with _ are left out",
+    },
+    Rule {
+        id: SHADOWED_BINDING,
+        level: Level::Warn,
+        description: "a declaration hides another of the same name",
+    },
+    Rule {
+        id: PREFER_CONST,
+        level: Level::Warn,
+        description: "a let variable is never assigned again, so it could be const",
+    },
+    Rule {
+        id: UNREACHABLE_CODE,
+        level: Level::Warn,
+        description: "statements follow a ret or throw and never run",
+    },
+    Rule {
+        id: SELF_ASSIGNMENT,
+        level: Level::Deny,
+        description: "a variable is assigned to itself",
+    },
+    Rule {
+        id: CONSTANT_CONDITION,
+        level: Level::Warn,
+        description: "an if condition always has the same value",
+    },
+    Rule {
+        id: ASSIGNMENT_IN_CONDITION,
+        level: Level::Warn,
+        description:
+            "an if condition is an assignment, likely meant as ==; wrap it in parentheses if not",
+    },
+    Rule {
+        id: EMPTY_BLOCK,
+        level: Level::Warn,
+        description: "a block has no statements and no comment saying why",
+    },
+];
+
+/// The level each rule is reported at.
+#[derive(Debug, Clone)]
+pub struct LintConfig {
+    levels: HashMap<&'static str, Level>,
+}
+
+impl LintConfig {
+    ///Sets the level of the rule `id`, returning false if there is no
+    ///such rule
+    pub fn set(&mut self, id: &str, level: Level) -> bool {
+        match RULES.iter().find(|rule| rule.id == id) {
+            Some(rule) => {
+                self.levels.insert(rule.id, level);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn level(&self, id: &str) -> Level {
+        self.levels.get(id).copied().unwrap_or(Level::Allow)
+    }
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            levels: RULES.iter().map(|rule| (rule.id, rule.level)).collect(),
+        }
+    }
+}
+
+/// Something a rule found. Lines count from 1, as in editors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+    pub rule: &'static str,
+    pub level: Level,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}[{}] {}",
+            self.line, self.level, self.rule, self.message
+        )
+    }
+}
+
+/// A `// lint-allow rule, ...` comment, for the line it ends or the
+/// next one, or a `// lint-allow-file rule, ...` comment, for the whole
+/// file. Without rules, every rule is suppressed.
+struct Suppression {
+    /// The line suppressed, or `None` for the whole file
+    line: Option<usize>,
+    rules: Vec<String>,
+}
+
+impl Suppression {
+    fn parse(comment: &Comment) -> Option<Self> {
+        let text = comment.text.trim_start_matches('/').trim();
+        let (file, rules) = match text.strip_prefix("lint-allow-file") {
+            Some(rules) => (true, rules),
+            None => (false, text.strip_prefix("lint-allow")?),
+        };
+        if !rules.is_empty() && !rules.starts_with([' ', ':']) {
+            return None;
+        }
+        let rules = rules
+            .split(|c: char| c == ',' || c == ':' || c.is_whitespace())
+            .filter(|rule| !rule.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        // A comment on a line of its own is about the line after it
+        let line = comment.line + 1 + usize::from(!comment.trailing);
+        Some(Self {
+            line: (!file).then_some(line),
+            rules,
+        })
+    }
+
+    fn covers(&self, lint: &Lint) -> bool {
+        self.line.is_none_or(|line| line == lint.line)
+            && (self.rules.is_empty() || self.rules.iter().any(|rule| rule == lint.rule))
+    }
+}
+
+/// A name declared in a scope.
+struct Binding {
+    name: Symbol,
+    line: usize,
+    /// Whether the name is a `let`/`const` variable, the only bindings
+    /// checked for use
+    variable: bool,
+    constant: bool,
+    exported: bool,
+    /// Whether its declaration was reached yet. Names are known from the
+    /// start of their scope, as functions can refer to those declared
+    /// after them.
+    declared: bool,
+    used: bool,
+    assigned: bool,
+}
+
+/// Checks parsed statements against the rules. Findings are collected
+/// as the tree is walked, then the suppressed and allowed ones dropped.
+pub struct Linter<'a> {
+    config: &'a LintConfig,
+    lints: Vec<Lint>,
+    /// The bindings of the scopes around the code being checked,
+    /// outermost first
+    scopes: Vec<Vec<Binding>>,
+    /// The lines comments are on, which keep blocks from being empty
+    comment_lines: Vec<usize>,
+    /// Folds conditions to tell whether they are constant
+    folder: Optimizer,
+}
+
+impl<'a> Linter<'a> {
+    pub fn new(config: &'a LintConfig) -> Self {
+        Self {
+            config,
+            lints: Vec::new(),
+            scopes: Vec::new(),
+            comment_lines: Vec::new(),
+            folder: Optimizer::new(OptLevel::Fold),
+        }
+    }
+
+    ///Checks the statements of a file, given with its comments, returning
+    ///what was found in the order of the lines
+    pub fn lint(mut self, statements: &[Stmt], comments: &[Comment]) -> Vec<Lint> {
+        self.comment_lines = comments.iter().map(|comment| comment.line).collect();
+        self.scope(&[], statements, |linter| linter.block(statements));
+
+        let suppressions: Vec<Suppression> =
+            comments.iter().filter_map(Suppression::parse).collect();
+        let mut lints: Vec<Lint> = self
+            .lints
+            .into_iter()
+            .filter(|lint| lint.level != Level::Allow)
+            .filter(|lint| !suppressions.iter().any(|s| s.covers(lint)))
+            .collect();
+        lints.sort_by(|a, b| a.line.cmp(&b.line).then(a.rule.cmp(b.rule)));
+        lints
+    }
+
+    fn report(&mut self, rule: &'static str, line: usize, message: String) {
+        self.lints.push(Lint {
+            rule,
+            level: self.config.level(rule),
+            line: line + 1,
+            message,
+        });
+    }
+
+    ///Checks code in a scope of its own, holding `params` and the names
+    ///`body` declares, then the use of the variables in it
+    fn scope(&mut self, params: &[(Symbol, usize)], body: &[Stmt], check: impl FnOnce(&mut Self)) {
+        self.scopes.push(Vec::new());
+        for &(name, line) in params {
+            self.shadowing(name, line);
+            self.bind(name, line, false, true);
+        }
+        for stmt in body {
+            for (name, line, variable) in declarations(stmt) {
+                self.bind(name, line, variable, false);
+            }
+        }
+
+        check(self);
+
+        let bindings = self.scopes.pop().unwrap_or_default();
+        for binding in bindings.iter().filter(|binding| binding.variable) {
+            if !binding.used && !binding.exported && !binding.name.to_string().starts_with('_') {
+                let message = format!("\"{}\" is never used", binding.name);
+                self.report(UNUSED_VARIABLE, binding.line, message);
+            } else if !binding.constant && !binding.assigned {
+                let message = format!(
+                    "\"{}\" is never assigned again; declare it with const",
+                    binding.name
+                );
+                self.report(PREFER_CONST, binding.line, message);
+            }
+        }
+    }
+
+    fn bind(&mut self, name: Symbol, line: usize, variable: bool, declared: bool) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(Binding {
+                name,
+                line,
+                variable,
+                constant: false,
+                exported: false,
+                declared,
+                used: false,
+                assigned: false,
+            });
+        }
+    }
+
+    ///Marks the declaration of `name` on `line` as reached
+    fn declare(&mut self, name: Symbol, line: usize, constant: bool, exported: bool) {
+        self.shadowing(name, line);
+        let binding = self.scopes.last_mut().and_then(|scope| {
+            scope
+                .iter_mut()
+                .find(|binding| binding.name == name && binding.line == line && !binding.declared)
+        });
+        if let Some(binding) = binding {
+            binding.declared = true;
+            binding.constant = constant;
+            binding.exported = exported;
+        }
+    }
+
+    ///Reports a declaration of `name` hiding one reached before
+    fn shadowing(&mut self, name: Symbol, line: usize) {
+        let shadowed = self
+            .scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|binding| binding.name == name && binding.declared)
+            .map(|binding| binding.line);
+        if let Some(shadowed) = shadowed {
+            let message = format!(
+                "\"{}\" shadows the binding declared on line {}",
+                name,
+                shadowed + 1
+            );
+            self.report(SHADOWED_BINDING, line, message);
+        }
+    }
+
+    ///Returns the binding `name` refers to: the last one declared in the
+    ///closest scope that has one, or the first if none is declared yet
+    fn resolve(&mut self, name: Symbol) -> Option<&mut Binding> {
+        let scope = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find(|scope| scope.iter().any(|binding| binding.name == name))?;
+        let declared = scope
+            .iter()
+            .rposition(|binding| binding.name == name && binding.declared);
+        let index = match declared {
+            Some(index) => index,
+            None => scope.iter().position(|binding| binding.name == name)?,
+        };
+        scope.get_mut(index)
+    }
+
+    ///Checks the statements of a block, in the scope already opened
+    fn block(&mut self, body: &[Stmt]) {
+        let (mut exited, mut reported) = (false, false);
+        for stmt in body {
+            // Only the first statement that never runs is reported
+            if exited && !reported {
+                let message =
+                    "This code never runs: the statements before it always return or throw";
+                self.report(UNREACHABLE_CODE, stmt.line(), message.to_string());
+                reported = true;
+            }
+            self.statement(stmt);
+            exited |= always_exits(stmt);
+        }
+    }
+
+    fn statement(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::ExpressionStmt { expression, .. } => self.expression(expression),
+            Stmt::BlockStmt {
+                body,
+                line,
+                end_line,
+            } => {
+                let commented = self
+                    .comment_lines
+                    .iter()
+                    .any(|comment| (line..=end_line).contains(&comment));
+                if body.is_empty() && !commented {
+                    let message = "This block is empty; remove it or say why in a comment";
+                    self.report(EMPTY_BLOCK, *line, message.to_string());
+                }
+                self.scope(&[], body, |linter| linter.block(body));
+            }
+            Stmt::IfStmt {
+                condition,
+                consequent,
+                alternate,
+                line,
+            } => {
+                self.condition(condition, *line);
+                self.expression(condition);
+                self.statement(consequent);
+                if let Some(alternate) = alternate {
+                    self.statement(alternate);
+                }
+            }
+            Stmt::VarDeclarationStmt { .. } => self.var_declaration(stmt, false),
+            Stmt::ReturnStmt { value, .. } => {
+                if let Some(value) = value {
+                    self.expression(value);
+                }
+            }
+            Stmt::OperatorDeclStmt { function, .. } => self.expression(function),
+            Stmt::ImportStmt { line, .. } | Stmt::EnumDeclStmt { line, .. } => {
+                for (name, _, _) in declarations(stmt) {
+                    self.declare(name, *line, true, false);
+                }
+            }
+            Stmt::ExportStmt { declaration, .. } => match declaration.as_ref() {
+                Stmt::VarDeclarationStmt { .. } => self.var_declaration(declaration, true),
+                declaration => self.statement(declaration),
+            },
+            Stmt::TryStmt {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                self.statement(body);
+                if let Some(catch) = catch {
+                    let names: Vec<(Symbol, usize)> = catch
+                        .name
+                        .map(|name| (name, catch.body.line()))
+                        .into_iter()
+                        .collect();
+                    self.scope(&names, &[], |linter| linter.statement(&catch.body));
+                }
+                if let Some(finally) = finally {
+                    self.statement(finally);
+                }
+            }
+            Stmt::ThrowStmt { value, .. } => self.expression(value),
+            Stmt::TestStmt { body, .. } => self.statement(body),
+        }
+    }
+
+    fn var_declaration(&mut self, stmt: &Stmt, exported: bool) {
+        let Stmt::VarDeclarationStmt {
+            identifier,
+            isConstant,
+            assignedValue,
+            line,
+            ..
+        } = stmt
+        else {
+            return;
+        };
+        // Functions can call themselves, so their name is declared first
+        if matches!(assignedValue, Expr::Function { .. }) {
+            self.declare(*identifier, *line, *isConstant, exported);
+            self.expression(assignedValue);
+        } else {
+            self.expression(assignedValue);
+            self.declare(*identifier, *line, *isConstant, exported);
+        }
+    }
+
+    ///Checks the condition of an `if`
+    fn condition(&mut self, condition: &Expr, line: usize) {
+        // A parenthesized assignment is taken as intentional
+        if let Expr::Assignment { assignee, .. } = condition {
+            let message = format!(
+                "The condition assigns to \"{}\"; compare with == or wrap the assignment in parentheses",
+                assignee
+            );
+            self.report(ASSIGNMENT_IN_CONDITION, line, message);
+            return;
+        }
+
+        let folded = self.folder.optimize_expression(condition.clone());
+        if matches!(
+            folded,
+            Expr::Number(_) | Expr::String(_) | Expr::Bool(_) | Expr::Null
+        ) {
+            let message = format!("The condition is always {}", folded);
+            self.report(CONSTANT_CONDITION, line, message);
+        }
+    }
+
+    fn expression(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Identifier(name) => {
+                if let Some(binding) = self.resolve(*name) {
+                    binding.used = true;
+                }
+            }
+            Expr::Assignment {
+                assignee,
+                op,
+                assigned,
+            } => {
+                if let Expr::Identifier(name) = assignee.as_ref() {
+                    if op.ttype == TokenType::EQUAL && ungrouped(assigned) == assignee.as_ref() {
+                        let message = format!("\"{}\" is assigned to itself", name);
+                        self.report(SELF_ASSIGNMENT, op.line, message);
+                    }
+                    // `+=` and `-=` read the variable too
+                    let reads = op.ttype != TokenType::EQUAL;
+                    if let Some(binding) = self.resolve(*name) {
+                        binding.assigned = true;
+                        binding.used |= reads;
+                    }
+                } else {
+                    self.expression(assignee);
+                }
+                self.expression(assigned);
+            }
+            Expr::Function {
+                params, body, line, ..
+            } => {
+                let params: Vec<(Symbol, usize)> =
+                    params.iter().map(|param| (*param, *line)).collect();
+                self.scope(&params, body, |linter| linter.block(body));
+            }
+            Expr::Match { value, arms, .. } => {
+                self.expression(value);
+                for arm in arms {
+                    let bindings: Vec<(Symbol, usize)> = arm
+                        .pattern
+                        .bindings()
+                        .into_iter()
+                        .map(|name| (name, arm.line))
+                        .collect();
+                    self.scope(&bindings, &[], |linter| {
+                        if let Some(guard) = &arm.guard {
+                            linter.expression(guard);
+                        }
+                        linter.expression(&arm.body);
+                    });
+                }
+            }
+            Expr::Unary { right, .. } => self.expression(right),
+            Expr::BinaryOp { left, right, .. } => {
+                self.expression(left);
+                self.expression(right);
+            }
+            Expr::Grouping { group } => self.expression(group),
+            Expr::Call { callee, args, .. } => {
+                self.expression(callee);
+                args.iter().for_each(|arg| self.expression(arg));
+            }
+            Expr::List(items) => items.iter().for_each(|item| self.expression(item)),
+            Expr::Index { object, index } => {
+                self.expression(object);
+                self.expression(index);
+            }
+            Expr::Member { object, .. } | Expr::Propagate { value: object, .. } => {
+                self.expression(object)
+            }
+            Expr::Number(_) | Expr::String(_) | Expr::Bool(_) | Expr::Null => {}
+        }
+    }
+}
+
+///Returns the names a statement declares in its scope, with their line
+///and whether they are variables
+fn declarations(stmt: &Stmt) -> Vec<(Symbol, usize, bool)> {
+    match stmt {
+        Stmt::VarDeclarationStmt {
+            identifier, line, ..
+        } => vec![(*identifier, *line, true)],
+        Stmt::EnumDeclStmt { name, line, .. } => vec![(*name, *line, false)],
+        Stmt::ImportStmt { import, line, .. } => match import {
+            Import::Module(alias) => vec![(*alias, *line, false)],
+            Import::Names(names) => names.iter().map(|name| (*name, *line, false)).collect(),
+        },
+        Stmt::ExportStmt { declaration, .. } => declarations(declaration),
+        _ => Vec::new(),
+    }
+}
+
+///Checks whether running a statement always ends in a `ret` or `throw`
+fn always_exits(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::ReturnStmt { .. } | Stmt::ThrowStmt { .. } => true,
+        Stmt::BlockStmt { body, .. } => body.iter().any(always_exits),
+        Stmt::IfStmt {
+            consequent,
+            alternate: Some(alternate),
+            ..
+        } => always_exits(consequent) && always_exits(alternate),
+        _ => false,
+    }
+}
+
+fn ungrouped(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Grouping { group } => ungrouped(group),
+        expr => expr,
+    }
+}
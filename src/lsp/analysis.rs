@@ -0,0 +1,541 @@
+use crate::{
+    ast::{expr::Expr, stmt::Stmt},
+    errors::diagnostic::Diagnostic,
+    formatter::formatter::format_expr,
+    parser::parser::Parser,
+    scanner::scanner::Scanner,
+    stdlib::prelude::{builtin_enums, builtin_variant},
+    tokens::{
+        symbol::{Symbol, SymbolScope},
+        token::Token,
+        token_type::TokenType,
+    },
+};
+
+/// A `let`/`const` binding, an imported name or an enum found in a
+/// document.
+pub struct Binding {
+    pub name: String,
+    pub is_constant: bool,
+    /// The `import` or `enum` statement the name comes from, shown as is
+    /// on hover
+    pub declaration: Option<String>,
+    pub explicit_type: Option<String>,
+    pub value: Option<Expr>,
+    /// Index of the identifier token being declared
+    pub index: usize,
+    /// Index of the token closing the block the binding lives in
+    pub scope_end: usize,
+}
+
+/// Everything the language server knows about one version of a document:
+/// its text, the errors found by the `Scanner`/`Parser` and the bindings
+/// it declares. Tokens borrow from the text, so they are scanned again
+/// whenever a request needs them.
+pub struct Analysis {
+    pub source: String,
+    pub file_name: String,
+    pub errors: Vec<Diagnostic>,
+    pub bindings: Vec<Binding>,
+    /// The names of this version of the document, dropped with it
+    symbols: SymbolScope,
+}
+
+impl Analysis {
+    pub fn new(source: &str, file_name: String) -> Self {
+        // The built-in enums are shared by every document, so their names
+        // are interned before entering the document's scope
+        builtin_enums();
+
+        let symbols = SymbolScope::new();
+        symbols.clone().enter(|| {
+            let mut parser = Parser::new(Scanner::new(source, file_name.clone()));
+            let statements = parser.parse();
+            let errors = parser.errors().clone();
+
+            let mut analysis = Self {
+                source: source.to_string(),
+                file_name,
+                errors,
+                bindings: Vec::new(),
+                symbols,
+            };
+            analysis.collect_bindings(&statements);
+            analysis.check_annotations();
+            analysis
+        })
+    }
+
+    ///Scans the document. Errors are left out; they are in `errors`.
+    pub fn tokens(&self) -> Vec<Token<'_>> {
+        self.symbols.enter(|| {
+            Scanner::new(&self.source, self.file_name.clone())
+                .filter_map(Result::ok)
+                .collect()
+        })
+    }
+
+    ///Finds the declarations in the token stream, matching each one with
+    ///its initializer from the parsed statements when there is one
+    fn collect_bindings(&mut self, statements: &[Stmt]) {
+        let mut declarations = Vec::new();
+        collect_declarations(statements, &mut declarations);
+        let mut enums = Vec::new();
+        collect_enums(statements, &mut enums);
+
+        let tokens = self.tokens();
+        let mut bindings: Vec<Binding> = Vec::new();
+        // Bindings declared in each open block, outermost first
+        let mut open_blocks: Vec<Vec<usize>> = vec![Vec::new()];
+
+        for (index, token) in tokens.iter().enumerate() {
+            match token.ttype {
+                TokenType::LEFTBRACE => open_blocks.push(Vec::new()),
+                TokenType::RIGHTBRACE if open_blocks.len() > 1 => {
+                    for binding in open_blocks.pop().unwrap_or_default() {
+                        bindings[binding].scope_end = index;
+                    }
+                }
+                TokenType::LET | TokenType::CONST => {
+                    let name = match tokens.get(index + 1) {
+                        Some(name) if name.ttype == TokenType::IDENTIFIER => name,
+                        _ => continue,
+                    };
+                    let explicit_type = match tokens.get(index + 2) {
+                        Some(colon) if colon.ttype == TokenType::COLON => {
+                            tokens.get(index + 3).map(|ty| ty.lexeme.to_string())
+                        }
+                        _ => None,
+                    };
+                    let value = declarations
+                        .iter()
+                        .find(|(identifier, line, _)| {
+                            *identifier == name.lexeme && *line == token.line
+                        })
+                        .map(|(_, _, value)| (*value).clone());
+
+                    if let Some(block) = open_blocks.last_mut() {
+                        block.push(bindings.len());
+                    }
+                    bindings.push(Binding {
+                        name: name.lexeme.to_string(),
+                        is_constant: token.ttype == TokenType::CONST,
+                        declaration: None,
+                        explicit_type,
+                        value,
+                        index: index + 1,
+                        scope_end: tokens.len(),
+                    });
+                }
+                TokenType::ENUM => {
+                    let name = match tokens.get(index + 1) {
+                        Some(name) if name.ttype == TokenType::IDENTIFIER => name,
+                        _ => continue,
+                    };
+                    let declaration = enums
+                        .iter()
+                        .find(|(enum_name, line, _)| {
+                            *enum_name == name.lexeme && *line == token.line
+                        })
+                        .map(|(_, _, declaration)| declaration.clone());
+
+                    if let Some(block) = open_blocks.last_mut() {
+                        block.push(bindings.len());
+                    }
+                    bindings.push(Binding {
+                        name: name.lexeme.to_string(),
+                        is_constant: true,
+                        declaration,
+                        explicit_type: Some("enum".to_string()),
+                        value: None,
+                        index: index + 1,
+                        scope_end: tokens.len(),
+                    });
+                }
+                TokenType::IMPORT => {
+                    let text = self.source.lines().nth(token.line).unwrap_or_default();
+                    for binding in import_bindings(&tokens, index, text) {
+                        if let Some(block) = open_blocks.last_mut() {
+                            block.push(bindings.len());
+                        }
+                        bindings.push(binding);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.bindings = bindings;
+    }
+
+    ///Returns the index of the token under the cursor
+    pub fn token_at(&self, line: usize, character: usize) -> Option<usize> {
+        self.tokens().iter().position(|token| {
+            let (start, end) = token_columns(token);
+            token.line == line
+                && token.ttype != TokenType::EOF
+                && start <= character
+                && character < end
+        })
+    }
+
+    ///Returns the bindings visible from the token at `index`, innermost
+    ///and most recent last
+    pub fn bindings_in_scope(&self, index: usize) -> Vec<&Binding> {
+        self.bindings
+            .iter()
+            .filter(|binding| binding.index <= index && index < binding.scope_end)
+            .collect()
+    }
+
+    ///Resolves the identifier at `index` to the binding it refers to
+    pub fn resolve(&self, index: usize) -> Option<&Binding> {
+        let tokens = self.tokens();
+        let token = tokens.get(index)?;
+        if token.ttype != TokenType::IDENTIFIER {
+            return None;
+        }
+        self.bindings_in_scope(index)
+            .into_iter()
+            .rev()
+            .find(|binding| binding.name == token.lexeme)
+    }
+
+    ///Describes a binding for hover: its declaration and its type
+    pub fn describe(&self, binding: &Binding) -> String {
+        self.symbols.enter(|| self.describe_in_scope(binding))
+    }
+
+    fn describe_in_scope(&self, binding: &Binding) -> String {
+        if let Some(declaration) = &binding.declaration {
+            let value_type = binding.explicit_type.as_deref().unwrap_or("unknown");
+            return format!("```nd-lang\n{}\n```\nType: `{}`", declaration, value_type);
+        }
+
+        let mut declaration = format!(
+            "{} {}",
+            if binding.is_constant { "const" } else { "let" },
+            binding.name
+        );
+        if let Some(explicit_type) = &binding.explicit_type {
+            declaration.push_str(&format!(": {}", explicit_type));
+        }
+        if let Some(value) = &binding.value {
+            declaration.push_str(&format!(" = {}", format_expr(value)));
+        }
+
+        let value_type = match &binding.explicit_type {
+            Some(explicit_type) => explicit_type.clone(),
+            None => binding
+                .value
+                .as_ref()
+                .and_then(|value| self.infer_type(value, binding.index))
+                .unwrap_or_else(|| "unknown".to_string()),
+        };
+        format!("```nd-lang\n{}\n```\nType: `{}`", declaration, value_type)
+    }
+
+    ///Finds the binding called `name` declared before the token at
+    ///`index` and visible from it
+    fn declared_before(&self, name: &str, index: usize) -> Option<&Binding> {
+        self.bindings_in_scope(index)
+            .into_iter()
+            .rev()
+            .find(|binding| binding.index < index && binding.name == name)
+    }
+
+    ///Reports bindings annotated as `Result` or `Option` whose value is
+    ///known to be something else
+    fn check_annotations(&mut self) {
+        let tokens = self.tokens();
+        let mut errors = Vec::new();
+        for binding in &self.bindings {
+            let (Some(explicit_type), Some(value)) = (&binding.explicit_type, &binding.value)
+            else {
+                continue;
+            };
+            if !["Result", "Option"].contains(&explicit_type.as_str()) {
+                continue;
+            }
+            let Some(value_type) = self.infer_type(value, binding.index) else {
+                continue;
+            };
+            if value_type != *explicit_type {
+                let token = &tokens[binding.index];
+                errors.push(Diagnostic::new(
+                    token.position,
+                    token.line,
+                    format!(
+                        "\"{}\" is declared as {} but its value is a {}",
+                        binding.name, explicit_type, value_type
+                    ),
+                    match explicit_type.as_str() {
+                        "Result" => "Wrap the value: Ok(value) or Err(error)".to_string(),
+                        _ => "Wrap the value: Some(value), or use None".to_string(),
+                    },
+                ));
+            }
+        }
+        self.errors.extend(errors);
+    }
+
+    ///Works out the type of an expression where it can be known without
+    ///running the program
+    fn infer_type(&self, expr: &Expr, index: usize) -> Option<String> {
+        match expr {
+            Expr::Number(_) => Some("number".to_string()),
+            Expr::String(_) => Some("string".to_string()),
+            Expr::Bool(_) => Some("bool".to_string()),
+            Expr::Null => Some("null".to_string()),
+            Expr::Grouping { group } => self.infer_type(group, index),
+            Expr::Unary { op, right } => match op.ttype {
+                TokenType::BANG => Some("bool".to_string()),
+                _ => self.infer_type(right, index),
+            },
+            Expr::Identifier(name) => {
+                let Some(binding) = self.declared_before(&name.to_string(), index) else {
+                    // `None`
+                    let (enum_type, variant) = builtin_variant(*name)?;
+                    return enum_type.variants[variant]
+                        .fields
+                        .is_empty()
+                        .then(|| enum_type.name.to_string());
+                };
+                match &binding.explicit_type {
+                    Some(explicit_type) => Some(explicit_type.clone()),
+                    None => self.infer_type(binding.value.as_ref()?, binding.index),
+                }
+            }
+            Expr::BinaryOp { left, op, right } => match op.ttype {
+                TokenType::LESS
+                | TokenType::LESSEQUAL
+                | TokenType::GREATER
+                | TokenType::GREATEREQUAL
+                | TokenType::EQUALEQUAL
+                | TokenType::BANGEQUAL
+                | TokenType::AND
+                | TokenType::OR => Some("bool".to_string()),
+                _ => {
+                    let left = self.infer_type(left, index)?;
+                    let right = self.infer_type(right, index)?;
+                    if left == right {
+                        Some(left)
+                    } else if op.ttype == TokenType::PLUS && (left == "string" || right == "string")
+                    {
+                        Some("string".to_string())
+                    } else {
+                        None
+                    }
+                }
+            },
+            Expr::Assignment { assigned, .. } => self.infer_type(assigned, index),
+            Expr::Function { .. } => Some("func".to_string()),
+            Expr::List(_) => Some("list".to_string()),
+            Expr::Call { callee, .. } => match callee.as_ref() {
+                // `Ok(x)`, `Err(e)` and `Some(x)`, unless the name is taken
+                Expr::Identifier(name)
+                    if self.declared_before(&name.to_string(), index).is_none() =>
+                {
+                    builtin_variant(*name).map(|(enum_type, _)| enum_type.name.to_string())
+                }
+                Expr::Member { property, .. }
+                    if ["is_ok", "is_err", "is_some", "is_none"]
+                        .iter()
+                        .any(|method| property == method) =>
+                {
+                    Some("bool".to_string())
+                }
+                _ => None,
+            },
+            Expr::Member { .. }
+            | Expr::Index { .. }
+            | Expr::Match { .. }
+            | Expr::Propagate { .. } => None,
+        }
+    }
+}
+
+///Returns the columns a token starts and ends at on its line
+pub fn token_columns(token: &Token) -> (usize, usize) {
+    let length = token.source_text().chars().count();
+    (token.position.saturating_sub(length), token.position)
+}
+
+///Returns the names bound by the `import` statement starting at token
+///`index`, written out as `text`
+fn import_bindings(tokens: &[Token], index: usize, text: &str) -> Vec<Binding> {
+    let end = tokens[index..]
+        .iter()
+        .position(|token| token.ttype == TokenType::SEMICOLON)
+        .map_or(tokens.len(), |offset| index + offset);
+    let statement = &tokens[index..end];
+
+    let mut names = Vec::new();
+    let mut in_braces = false;
+    for (offset, token) in statement.iter().enumerate() {
+        match token.ttype {
+            TokenType::LEFTBRACE => in_braces = true,
+            TokenType::RIGHTBRACE => in_braces = false,
+            TokenType::IDENTIFIER if in_braces => names.push((index + offset, false)),
+            TokenType::IDENTIFIER if offset > 0 && statement[offset - 1].lexeme == "as" => {
+                names.push((index + offset, true))
+            }
+            _ => {}
+        }
+    }
+
+    names
+        .into_iter()
+        .map(|(index, is_module)| Binding {
+            name: tokens[index].lexeme.to_string(),
+            is_constant: true,
+            declaration: Some(text.trim().to_string()),
+            explicit_type: is_module.then(|| "module".to_string()),
+            value: None,
+            index,
+            scope_end: tokens.len(),
+        })
+        .collect()
+}
+
+///Collects the name, line and value of every variable declaration
+fn collect_declarations<'a>(statements: &'a [Stmt], out: &mut Vec<(Symbol, usize, &'a Expr)>) {
+    for stmt in statements {
+        match stmt {
+            Stmt::VarDeclarationStmt {
+                identifier,
+                assignedValue,
+                line,
+                ..
+            } => {
+                out.push((*identifier, *line, assignedValue));
+                collect_function_declarations(assignedValue, out);
+            }
+            Stmt::BlockStmt { body, .. } => collect_declarations(body, out),
+            Stmt::IfStmt {
+                consequent,
+                alternate,
+                ..
+            } => {
+                collect_declarations(std::slice::from_ref(consequent.as_ref()), out);
+                if let Some(alternate) = alternate {
+                    collect_declarations(std::slice::from_ref(alternate.as_ref()), out);
+                }
+            }
+            Stmt::ExpressionStmt {
+                expression: value, ..
+            }
+            | Stmt::ReturnStmt {
+                value: Some(value), ..
+            }
+            | Stmt::OperatorDeclStmt {
+                function: value, ..
+            } => collect_function_declarations(value, out),
+            Stmt::ExportStmt { declaration, .. } => {
+                collect_declarations(std::slice::from_ref(declaration.as_ref()), out)
+            }
+            Stmt::TryStmt {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                collect_declarations(std::slice::from_ref(body.as_ref()), out);
+                if let Some(catch) = catch {
+                    collect_declarations(std::slice::from_ref(catch.body.as_ref()), out);
+                }
+                if let Some(finally) = finally {
+                    collect_declarations(std::slice::from_ref(finally.as_ref()), out);
+                }
+            }
+            Stmt::ThrowStmt { value, .. } => collect_function_declarations(value, out),
+            Stmt::TestStmt { body, .. } => {
+                collect_declarations(std::slice::from_ref(body.as_ref()), out)
+            }
+            Stmt::ReturnStmt { value: None, .. }
+            | Stmt::ImportStmt { .. }
+            | Stmt::EnumDeclStmt { .. } => {}
+        }
+    }
+}
+
+///Collects the name, line and text of every enum declaration outside of
+///functions
+fn collect_enums(statements: &[Stmt], out: &mut Vec<(Symbol, usize, String)>) {
+    for stmt in statements {
+        match stmt {
+            Stmt::EnumDeclStmt { name, line, .. } => out.push((*name, *line, stmt.to_string())),
+            Stmt::BlockStmt { body, .. } => collect_enums(body, out),
+            Stmt::IfStmt {
+                consequent,
+                alternate,
+                ..
+            } => {
+                collect_enums(std::slice::from_ref(consequent.as_ref()), out);
+                if let Some(alternate) = alternate {
+                    collect_enums(std::slice::from_ref(alternate.as_ref()), out);
+                }
+            }
+            Stmt::ExportStmt { declaration, .. } => {
+                collect_enums(std::slice::from_ref(declaration.as_ref()), out)
+            }
+            Stmt::TryStmt {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                collect_enums(std::slice::from_ref(body.as_ref()), out);
+                if let Some(catch) = catch {
+                    collect_enums(std::slice::from_ref(catch.body.as_ref()), out);
+                }
+                if let Some(finally) = finally {
+                    collect_enums(std::slice::from_ref(finally.as_ref()), out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+///Collects the declarations in the bodies of the functions in `expr`
+fn collect_function_declarations<'a>(expr: &'a Expr, out: &mut Vec<(Symbol, usize, &'a Expr)>) {
+    match expr {
+        Expr::Function { body, .. } => collect_declarations(body, out),
+        Expr::Unary { right, .. } => collect_function_declarations(right, out),
+        Expr::BinaryOp { left, right, .. } => {
+            collect_function_declarations(left, out);
+            collect_function_declarations(right, out);
+        }
+        Expr::Assignment { assigned, .. } => collect_function_declarations(assigned, out),
+        Expr::Grouping { group } => collect_function_declarations(group, out),
+        Expr::Member { object, .. } | Expr::Propagate { value: object, .. } => {
+            collect_function_declarations(object, out)
+        }
+        Expr::List(items) => {
+            for item in items {
+                collect_function_declarations(item, out);
+            }
+        }
+        Expr::Index { object, index } => {
+            collect_function_declarations(object, out);
+            collect_function_declarations(index, out);
+        }
+        Expr::Call { callee, args, .. } => {
+            collect_function_declarations(callee, out);
+            for arg in args {
+                collect_function_declarations(arg, out);
+            }
+        }
+        Expr::Match { value, arms, .. } => {
+            collect_function_declarations(value, out);
+            for arm in arms {
+                if let Some(guard) = &arm.guard {
+                    collect_function_declarations(guard, out);
+                }
+                collect_function_declarations(&arm.body, out);
+            }
+        }
+        Expr::Number(_) | Expr::String(_) | Expr::Bool(_) | Expr::Null | Expr::Identifier(_) => {}
+    }
+}
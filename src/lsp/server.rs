@@ -0,0 +1,302 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+use serde_json::{json, Value};
+
+use crate::tokens::token::KEYWORDS;
+
+use super::analysis::{token_columns, Analysis};
+
+// JSON-RPC error codes used by the server
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_REQUEST: i64 = -32600;
+
+// LSP enumerations
+const SYNC_FULL: i64 = 1;
+const SEVERITY_ERROR: i64 = 1;
+const SYMBOL_VARIABLE: i64 = 13;
+const SYMBOL_CONSTANT: i64 = 14;
+const COMPLETION_VARIABLE: i64 = 6;
+const COMPLETION_KEYWORD: i64 = 14;
+const COMPLETION_CONSTANT: i64 = 21;
+
+/// A language server speaking LSP JSON-RPC over stdio. Documents are
+/// synchronised in full on every change and re-analysed right away.
+pub struct Server {
+    documents: HashMap<String, Analysis>,
+    shutdown: bool,
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Self {
+            documents: HashMap::new(),
+            shutdown: false,
+        }
+    }
+
+    ///Serves requests from stdin until the client sends `exit`. Returns
+    ///the process exit code the protocol asks for.
+    pub fn run(&mut self) -> io::Result<i32> {
+        let stdin = io::stdin();
+        let mut reader = stdin.lock();
+        let stdout = io::stdout();
+        let mut writer = stdout.lock();
+
+        while let Some(message) = read_message(&mut reader)? {
+            if message["method"] == "exit" {
+                return Ok(if self.shutdown { 0 } else { 1 });
+            }
+            for reply in self.handle(&message) {
+                write_message(&mut writer, &reply)?;
+            }
+        }
+        Ok(1)
+    }
+
+    ///Handles one message and returns the messages to send back
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+
+        // Notifications carry no id and get no response
+        let id = match message.get("id") {
+            Some(id) => id.clone(),
+            None => return self.handle_notification(method, params),
+        };
+
+        let result = match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": SYNC_FULL,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "documentSymbolProvider": true,
+                    "completionProvider": {},
+                },
+                "serverInfo": { "name": "ND-Lang" },
+            })),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "textDocument/hover" => self.with_document(params, |analysis, index, _| {
+                let binding = analysis.resolve(index?)?;
+                Some(json!({
+                    "contents": { "kind": "markdown", "value": analysis.describe(binding) },
+                    "range": token_range(analysis, index?),
+                }))
+            }),
+            "textDocument/definition" => self.with_document(params, |analysis, index, uri| {
+                let binding = analysis.resolve(index?)?;
+                Some(json!({ "uri": uri, "range": token_range(analysis, binding.index) }))
+            }),
+            "textDocument/documentSymbol" => self.with_document(params, |analysis, _, _| {
+                let symbols: Vec<Value> = analysis
+                    .bindings
+                    .iter()
+                    .map(|binding| {
+                        let range = token_range(analysis, binding.index);
+                        json!({
+                            "name": binding.name,
+                            "kind": if binding.is_constant { SYMBOL_CONSTANT } else { SYMBOL_VARIABLE },
+                            "range": range,
+                            "selectionRange": range,
+                        })
+                    })
+                    .collect();
+                Some(Value::from(symbols))
+            }),
+            "textDocument/completion" => self.with_document(params, |analysis, _, _| {
+                Some(Value::from(completions(analysis, params)))
+            }),
+            _ => Err(json!({
+                "code": METHOD_NOT_FOUND,
+                "message": format!("Unknown method \"{}\"", method),
+            })),
+        };
+
+        vec![match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+        }]
+    }
+
+    fn handle_notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+
+        let text = match method {
+            "textDocument/didOpen" => params["textDocument"]["text"].as_str(),
+            "textDocument/didChange" => params["contentChanges"]
+                .as_array()
+                .and_then(|changes| changes.last())
+                .and_then(|change| change["text"].as_str()),
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return vec![publish_diagnostics(&uri, Vec::new())];
+            }
+            _ => None,
+        };
+
+        match text {
+            Some(text) => {
+                let analysis = Analysis::new(text, uri.clone());
+                let diagnostics = diagnostics(&analysis);
+                self.documents.insert(uri.clone(), analysis);
+                vec![publish_diagnostics(&uri, diagnostics)]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    ///Runs a request against an open document. The callback receives the
+    ///analysis, the index of the token under the cursor and the uri.
+    fn with_document<F>(&self, params: &Value, callback: F) -> Result<Value, Value>
+    where
+        F: FnOnce(&Analysis, Option<usize>, &str) -> Option<Value>,
+    {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let analysis = match self.documents.get(uri) {
+            Some(analysis) => analysis,
+            None => {
+                return Err(json!({
+                    "code": INVALID_REQUEST,
+                    "message": format!("Document \"{}\" is not open", uri),
+                }))
+            }
+        };
+
+        let index = cursor(params).and_then(|(line, character)| analysis.token_at(line, character));
+        Ok(callback(analysis, index, uri).unwrap_or(Value::Null))
+    }
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+///Returns the line and character of the cursor in a request
+fn cursor(params: &Value) -> Option<(usize, usize)> {
+    let position = &params["position"];
+    Some((
+        position["line"].as_u64()? as usize,
+        position["character"].as_u64()? as usize,
+    ))
+}
+
+fn range(line: usize, start: usize, end: usize) -> Value {
+    json!({
+        "start": { "line": line, "character": start },
+        "end": { "line": line, "character": end },
+    })
+}
+
+fn token_range(analysis: &Analysis, index: usize) -> Value {
+    let tokens = analysis.tokens();
+    let token = &tokens[index];
+    let (start, end) = token_columns(token);
+    range(token.line, start, end)
+}
+
+fn diagnostics(analysis: &Analysis) -> Vec<Value> {
+    analysis
+        .errors
+        .iter()
+        .map(|error| {
+            json!({
+                "range": range(error.line(), error.position().saturating_sub(1), error.position()),
+                "severity": SEVERITY_ERROR,
+                "source": "ND-Lang",
+                "message": error.message(),
+            })
+        })
+        .collect()
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+///Offers the keywords plus every binding visible at the cursor
+fn completions(analysis: &Analysis, params: &Value) -> Vec<Value> {
+    let mut items = Vec::new();
+
+    let mut keywords: Vec<&String> = KEYWORDS
+        .keys()
+        .filter(|keyword| keyword.chars().all(char::is_alphanumeric))
+        .collect();
+    keywords.sort();
+    for keyword in keywords {
+        items.push(json!({ "label": keyword, "kind": COMPLETION_KEYWORD }));
+    }
+
+    // Bindings declared before the cursor, innermost scope winning
+    let tokens = analysis.tokens();
+    let visible_from = cursor(params).map_or(0, |(line, character)| {
+        tokens
+            .iter()
+            .position(|token| {
+                token.line > line || (token.line == line && token_columns(token).0 >= character)
+            })
+            .unwrap_or(tokens.len())
+    });
+    let mut names: Vec<&str> = Vec::new();
+    for binding in analysis.bindings_in_scope(visible_from).into_iter().rev() {
+        if binding.index >= visible_from || names.contains(&binding.name.as_str()) {
+            continue;
+        }
+        names.push(&binding.name);
+        items.push(json!({
+            "label": binding.name,
+            "kind": if binding.is_constant { COMPLETION_CONSTANT } else { COMPLETION_VARIABLE },
+            "detail": analysis.describe(binding).lines().nth(1).unwrap_or_default(),
+        }));
+    }
+    items
+}
+
+///Reads one `Content-Length` framed message, as LSP and DAP clients
+///send them. Returns `None` once the client closes the stream.
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let length = length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header")
+    })?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+///Writes a message with its `Content-Length` header
+pub fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
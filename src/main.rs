@@ -4,53 +4,85 @@
 // TODO: Create interpreter errors
 // BUG: If statement not working
 
+use nd_lang::debugger::cli::{CliDebugger, STOPPED};
+use nd_lang::debugger::dap::DapServer;
+use nd_lang::formatter::formatter::{diff_lines, format_source, FormatConfig, LineChange};
+use nd_lang::linter::linter::{Level, LintConfig, Linter, RULES};
+use nd_lang::lsp;
+use nd_lang::optimizer::optimizer::{OptLevel, Optimizer};
+use nd_lang::parser::parser::Parser;
+use nd_lang::profiler::profiler::Profiler;
+use nd_lang::scanner::scanner::Scanner;
+use nd_lang::stdlib::fs::Capabilities;
+use nd_lang::testing::runner::{discover, TestResult, TestRunner};
+use nd_lang::{Engine, Error, Value};
+use std::cell::RefCell;
 use std::env::args;
 use std::io::{self, stdout, BufRead, Write};
-mod errors {
-    pub(crate) mod error;
-}
-mod interpreter {
-    pub(crate) mod interpreter;
-}
-use interpreter::interpreter::Interpreter;
-use parser::parser::Parser;
-use scanner::scanner::Scanner;
-mod tokens {
-    pub(crate) mod token;
-    pub(crate) mod token_type;
-}
-mod ast {
-    pub(crate) mod expr;
-    pub mod stmt;
-}
+use std::rc::Rc;
 
-mod parser {
-    pub(crate) mod helpers;
-    pub(crate) mod lookups;
-    pub(crate) mod parser;
-}
-mod object {
-    pub(crate) mod object;
-}
-mod scanner {
-    pub(crate) mod scanner;
+/// The command line, a thin layer over the `Engine`
+struct Cedar {
+    engine: Engine,
 }
 
-struct Cedar {}
-
 impl Cedar {
     pub fn new() -> Cedar {
-        Cedar {}
+        Cedar {
+            engine: Engine::new(),
+        }
     }
 
     fn run_file(&mut self, path: &str) -> io::Result<()> {
-        let buf = std::fs::read_to_string(path)?;
-        if self.run(buf, path.to_string()).is_err() {
-            // Ignore: error was already reported
-            std::process::exit(65);
+        match self.engine.run_file(path) {
+            Ok(_) => Ok(()),
+            Err(Error::Io(error)) => Err(error),
+            Err(error) => {
+                error.report();
+                std::process::exit(65);
+            }
+        }
+    }
+
+    /// Runs a file under the profiler, then prints its report. The call
+    /// stacks are also written to `collapsed`, if given, for flame graph
+    /// tools.
+    fn profile_file(&mut self, path: &str, collapsed: Option<&str>) -> io::Result<()> {
+        let profiler = Rc::new(RefCell::new(Profiler::new()));
+        self.engine.set_profiler(profiler.clone());
+        let result = self.engine.run_file(path);
+        profiler.borrow_mut().finish();
+
+        // Programs stopped by an error are profiled up to it
+        eprint!("{}", profiler.borrow().report(PROFILED_LINES));
+        if let Some(collapsed) = collapsed {
+            std::fs::write(collapsed, profiler.borrow().collapsed())?;
+        }
+        match result {
+            Ok(_) => Ok(()),
+            Err(Error::Io(error)) => Err(error),
+            Err(error) => {
+                error.report();
+                std::process::exit(65);
+            }
         }
+    }
 
-        Ok(())
+    /// Runs a file under the command-line debugger, reading commands from
+    /// the terminal
+    fn debug_file(&mut self, path: &str) -> io::Result<()> {
+        println!("Debugging {}. Type \"help\" for the commands.", path);
+        let debugger = CliDebugger::new(path, io::stdin().lock(), io::stdout());
+        self.engine.set_debug_hook(Box::new(debugger));
+        match self.engine.run_file(path) {
+            Ok(_) => Ok(()),
+            Err(Error::Io(error)) => Err(error),
+            Err(Error::Runtime(error)) if error.message() == STOPPED => Ok(()),
+            Err(error) => {
+                error.report();
+                std::process::exit(65);
+            }
+        }
     }
 
     fn run_prompt(&mut self) {
@@ -62,7 +94,11 @@ impl Cedar {
                 if line.is_empty() {
                     break;
                 }
-                let _ = self.run(line, "Prompt".to_string());
+                match self.engine.eval_named(&line, "Prompt") {
+                    Ok(Value::Nil) => {}
+                    Ok(value) => println!("{:?}", value),
+                    Err(error) => error.report(),
+                }
             } else {
                 break;
             }
@@ -71,51 +107,447 @@ impl Cedar {
         }
     }
 
-    fn run(&mut self, source: String, file_name: String) -> Result<(), ()> {
-        let mut scanner = Scanner::new(source.chars().collect(), file_name.to_string());
-        let tokens = scanner.scan_tokens();
+    /// Formats a source file. The file is rewritten in place unless
+    /// `check` is set, in which case it is left untouched. Returns whether
+    /// the file was already formatted.
+    fn format_file(&mut self, path: &str, config: &FormatConfig, check: bool) -> io::Result<bool> {
+        let source = std::fs::read_to_string(path)?;
+        let formatted = match self.format(&source, path.to_string(), config) {
+            Ok(formatted) => formatted,
+            Err(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "the file has syntax errors",
+                ))
+            }
+        };
+        if formatted == source {
+            return Ok(true);
+        }
+
+        if check {
+            println!("Diff in {}:", path);
+            for change in diff_lines(&source, &formatted) {
+                match change {
+                    LineChange::Removed(line, text) => println!("{:>4} - {}", line, text),
+                    LineChange::Added(line, text) => println!("{:>4} + {}", line, text),
+                }
+            }
+        } else {
+            std::fs::write(path, formatted)?;
+        }
+        Ok(false)
+    }
+
+    fn format(
+        &mut self,
+        source: &str,
+        file_name: String,
+        config: &FormatConfig,
+    ) -> Result<String, ()> {
+        format_source(source, file_name, config).map_err(|errors| {
+            errors.iter().for_each(|error| error.report());
+        })
+    }
+}
+
+/// Handles `ND-Lang lint [--allow RULE] [--warn RULE] [--deny RULE]
+/// [--rules] <files>`, failing when a rule set to deny finds something
+fn run_lint(args: &[String]) {
+    let mut config = LintConfig::default();
+    let mut files = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let level = match arg.as_str() {
+            "--allow" => Level::Allow,
+            "--warn" => Level::Warn,
+            "--deny" => Level::Deny,
+            "--rules" => {
+                for rule in RULES.iter() {
+                    println!("{:<24} {:<8} {}", rule.id, rule.level, rule.description);
+                }
+                return;
+            }
+            _ => {
+                files.push(arg.clone());
+                continue;
+            }
+        };
+        match args.next() {
+            Some(rule) if config.set(rule, level) => {}
+            Some(rule) => {
+                println!("Unknown rule \"{}\"; see ND-Lang lint --rules", rule);
+                std::process::exit(64);
+            }
+            None => {
+                println!("Expected a rule after {}", arg);
+                std::process::exit(64);
+            }
+        }
+    }
+
+    if files.is_empty() {
+        println!(
+            "Usage: ND-Lang lint [--allow RULE] [--warn RULE] [--deny RULE] [--rules] <files>"
+        );
+        std::process::exit(64);
+    }
+
+    let (mut warnings, mut errors) = (0, 0);
+    for file in files {
+        let source = match std::fs::read_to_string(&file) {
+            Ok(source) => source,
+            Err(err) => {
+                println!("Could not read {}: {}", file, err);
+                std::process::exit(66);
+            }
+        };
+        let mut parser = Parser::new(Scanner::new(&source, file.clone()));
+        let statements = parser.parse();
+        if !parser.errors().is_empty() {
+            parser.errors().iter().for_each(|error| error.report());
+            std::process::exit(65);
+        }
+
+        let lints = Linter::new(&config).lint(&statements, parser.scanner().comments());
+        for lint in lints {
+            match lint.level {
+                Level::Deny => errors += 1,
+                _ => warnings += 1,
+            }
+            println!("{}:{}", file, lint);
+        }
+    }
+
+    if warnings + errors > 0 {
+        println!("{} warnings, {} errors", warnings, errors);
+    }
+    if errors > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Handles `ND-Lang ast [--optimized] [--opt-level N] <file>`, printing
+/// the statements of a file as the interpreter gets them
+fn run_ast(args: &[String]) {
+    let mut level = OptLevel::None;
+    let mut files = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--optimized" => level = OptLevel::default(),
+            "--opt-level" => level = opt_level(args.next()),
+            _ => files.push(arg.clone()),
+        }
+    }
+    let [file] = files.as_slice() else {
+        println!("Usage: ND-Lang ast [--optimized] [--opt-level N] <file>");
+        std::process::exit(64);
+    };
+
+    let source = match std::fs::read_to_string(file) {
+        Ok(source) => source,
+        Err(err) => {
+            println!("Could not read {}: {}", file, err);
+            std::process::exit(66);
+        }
+    };
+    let mut parser = Parser::new(Scanner::new(&source, file.clone()));
+    let statements = parser.parse();
+    if !parser.errors().is_empty() {
+        parser.errors().iter().for_each(|error| error.report());
+        std::process::exit(65);
+    }
+    for stmt in Optimizer::new(level).optimize(statements) {
+        println!("{}", stmt);
+    }
+}
+
+/// Handles `ND-Lang test [--filter NAME] [--module-path DIR]...
+/// [--allow-read[=DIR]] [--allow-write[=DIR]] [--opt-level N] [paths]`,
+/// running the tests of the `*_test.ndl` files under the paths, or
+/// under the current directory if none are given
+fn run_test(args: &[String]) {
+    let mut runner = TestRunner::new();
+    let mut capabilities = Capabilities::default();
+    let mut paths = Vec::new();
 
-        // Ok(for token in tokens.unwrap().clone() {
-        //     println!("{:?}", token);
-        // })
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--opt-level" => runner.set_opt_level(opt_level(args.next())),
+            "--allow-read" => capabilities.allow_all_reads(),
+            "--allow-write" => capabilities.allow_all_writes(),
+            _ if arg.starts_with("--allow-read=") => {
+                capabilities.allow_read(&arg["--allow-read=".len()..])
+            }
+            _ if arg.starts_with("--allow-write=") => {
+                capabilities.allow_write(&arg["--allow-write=".len()..])
+            }
+            "--filter" | "--module-path" => match args.next() {
+                Some(value) if arg == "--filter" => runner.set_filter(value),
+                Some(value) => runner.add_search_path(value),
+                None => {
+                    println!("Expected a value after {}", arg);
+                    std::process::exit(64);
+                }
+            },
+            _ => paths.push(arg.into()),
+        }
+    }
+    if let Some(module_paths) = std::env::var_os("ND_PATH") {
+        std::env::split_paths(&module_paths).for_each(|path| runner.add_search_path(path));
+    }
+    runner.set_capabilities(capabilities);
+    if paths.is_empty() {
+        paths.push(".".into());
+    }
 
-        // let expr = parse(tokens.unwrap().clone());
+    let files = match discover(&paths) {
+        Ok(files) => files,
+        Err(err) => {
+            println!("Could not look for tests: {}", err);
+            std::process::exit(66);
+        }
+    };
+    let mut results: Vec<TestResult> = Vec::new();
+    for file in files {
+        match runner.run_file(&file) {
+            Ok(tests) => {
+                for test in tests {
+                    let outcome = if test.passed() { "ok" } else { "FAILED" };
+                    println!("test {} ... {}", test.name, outcome);
+                    results.push(test);
+                }
+            }
+            Err(Error::Io(err)) => {
+                println!("Could not read {}: {}", file.display(), err);
+                std::process::exit(66);
+            }
+            Err(error) => {
+                error.report();
+                std::process::exit(65);
+            }
+        }
+    }
 
-        //Stream token into parser
-        // HACK: MAKE THIS MORE EFFICIENT
-        let mut parser_toks = Vec::new();
-        for token in tokens.unwrap().clone() {
-            if (token.ttype != tokens::token_type::TokenType::EOF) {
-                parser_toks.push(token);
+    let failed: Vec<&TestResult> = results.iter().filter(|test| !test.passed()).collect();
+    if !failed.is_empty() {
+        println!("\nfailures:");
+        for test in failed.iter() {
+            println!(
+                "\n---- {} ({}:{}) ----",
+                test.name,
+                test.file.display(),
+                test.line
+            );
+            if let Some(failure) = &test.failure {
+                failure.report();
             }
         }
+    }
+    println!(
+        "\ntest result: {}. {} passed; {} failed",
+        if failed.is_empty() { "ok" } else { "FAILED" },
+        results.len() - failed.len(),
+        failed.len()
+    );
+    if !failed.is_empty() {
+        std::process::exit(1);
+    }
+}
 
-        // let parser = Parser::new(tokens.unwrap().clone());
+/// Reads the level after `--opt-level`
+fn opt_level(arg: Option<&String>) -> OptLevel {
+    match arg
+        .and_then(|level| level.parse().ok())
+        .and_then(OptLevel::from_number)
+    {
+        Some(level) => level,
+        None => {
+            println!("Expected 0, 1 or 2 after --opt-level");
+            std::process::exit(64);
+        }
+    }
+}
+
+/// Handles `ND-Lang fmt [--check] [--indent N] [--width N] <files>`
+fn run_fmt(cedar: &mut Cedar, args: &[String]) {
+    let mut config = FormatConfig::default();
+    let mut check = false;
+    let mut files = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--check" => check = true,
+            "--indent" | "--width" => {
+                let value = args.next().and_then(|value| value.parse::<usize>().ok());
+                match value {
+                    Some(value) if arg == "--indent" => config.indent_width = value,
+                    Some(value) => config.line_width = value,
+                    None => {
+                        println!("Expected a number after {}", arg);
+                        std::process::exit(64);
+                    }
+                }
+            }
+            _ => files.push(arg.clone()),
+        }
+    }
 
-        let mut parser = Parser::new(parser_toks);
-        let result = parser.parse();
+    if files.is_empty() {
+        println!("Usage: ND-Lang fmt [--check] [--indent N] [--width N] <files>");
+        std::process::exit(64);
+    }
 
-        Ok(())
+    let mut unformatted = false;
+    for file in files {
+        match cedar.format_file(&file, &config, check) {
+            Ok(formatted) => unformatted |= !formatted,
+            Err(err) => {
+                println!("Could not format {}: {}", file, err);
+                std::process::exit(66);
+            }
+        }
+    }
 
-        // match parser.parse() {
-        //     Ok(statements) => {
-        //         self.interpreter.interpret(&statements);
-        //     }
-        //     Err(error) => error.report(),
-        // }
-        // Ok(())
+    if check && unformatted {
+        std::process::exit(1);
     }
 }
 
+/// How many of the slowest lines a profile shows
+const PROFILED_LINES: usize = 20;
+
+/// The stack of the thread scripts run on. Expressions and calls grow the
+/// stack as they need, but nested statements and the tools working on the
+/// tree, like the formatter, still recurse on it.
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
 fn main() {
+    // Scripts run on a thread of their own, so that deeply nested code
+    // is handled rather than crashing
+    let runner = std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)
+        .expect("Could not start the interpreter thread");
+    if runner.join().is_err() {
+        std::process::exit(70);
+    }
+}
+
+fn run() {
     let args: Vec<String> = args().collect();
     let mut cedar = Cedar::new();
     //println!("{}", args[1]);
-    match args.len() {
-        1 => cedar.run_prompt(),
-        2 => cedar.run_file(&args[1]).expect("Could not run file"),
+    if args.len() > 1 && args[1] == "fmt" {
+        run_fmt(&mut cedar, &args[2..]);
+        return;
+    }
+    if args.len() > 1 && args[1] == "lint" {
+        run_lint(&args[2..]);
+        return;
+    }
+    if args.len() > 1 && args[1] == "test" {
+        run_test(&args[2..]);
+        return;
+    }
+    if args.len() > 1 && args[1] == "ast" {
+        run_ast(&args[2..]);
+        return;
+    }
+    if args.len() > 1 && args[1] == "lsp" {
+        match lsp::server::Server::new().run() {
+            Ok(code) => std::process::exit(code),
+            Err(err) => {
+                eprintln!("Language server stopped: {}", err);
+                std::process::exit(74);
+            }
+        }
+    }
+    if args.len() > 1 && args[1] == "dap" {
+        let mut server = DapServer::new(io::stdin().lock(), io::stdout());
+        if let Err(err) = server.run() {
+            eprintln!("Debug adapter stopped: {}", err);
+            std::process::exit(74);
+        }
+        return;
+    }
+
+    // Modules are looked up next to the importing file, then in the
+    // directories given with --module-path, then in those listed in ND_PATH
+    let mut files = Vec::new();
+    let mut module_paths = Vec::new();
+    // Scripts may only touch the files the user allows
+    let mut capabilities = Capabilities::default();
+    // `ND-Lang debug file.ndl` takes the same options as running it, and
+    // `ND-Lang run file.ndl` is the same as `ND-Lang file.ndl`
+    let debug = args.get(1).is_some_and(|arg| arg == "debug");
+    let command = debug || args.get(1).is_some_and(|arg| arg == "run");
+    let mut profile = false;
+    let mut collapsed = None;
+    let mut rest = args[1 + usize::from(command)..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--profile" => profile = true,
+            "--opt-level" => cedar.engine.set_opt_level(opt_level(rest.next())),
+            "--collapsed" => match rest.next() {
+                Some(path) => {
+                    profile = true;
+                    collapsed = Some(path.clone());
+                }
+                None => {
+                    println!("Expected a file after --collapsed");
+                    std::process::exit(64);
+                }
+            },
+            "--allow-read" => capabilities.allow_all_reads(),
+            "--allow-write" => capabilities.allow_all_writes(),
+            _ if arg.starts_with("--allow-read=") => {
+                capabilities.allow_read(&arg["--allow-read=".len()..])
+            }
+            _ if arg.starts_with("--allow-write=") => {
+                capabilities.allow_write(&arg["--allow-write=".len()..])
+            }
+            "--max-depth" => match rest.next().and_then(|depth| depth.parse().ok()) {
+                Some(depth) => cedar.engine.set_max_depth(depth),
+                None => {
+                    println!("Expected a number after --max-depth");
+                    std::process::exit(64);
+                }
+            },
+            "--module-path" => match rest.next() {
+                Some(path) => module_paths.push(path.into()),
+                None => {
+                    println!("Expected a directory after --module-path");
+                    std::process::exit(64);
+                }
+            },
+            _ => files.push(arg.clone()),
+        }
+    }
+    if let Some(paths) = std::env::var_os("ND_PATH") {
+        module_paths.extend(std::env::split_paths(&paths));
+    }
+    for path in module_paths {
+        cedar.engine.add_search_path(path);
+    }
+    cedar.engine.set_capabilities(capabilities);
+
+    match files.as_slice() {
+        [] if !debug => cedar.run_prompt(),
+        [file] if debug => cedar.debug_file(file).expect("Could not debug file"),
+        [file] if profile => cedar
+            .profile_file(file, collapsed.as_deref())
+            .expect("Could not run file"),
+        [file] => cedar.run_file(file).expect("Could not run file"),
         _ => {
-            println!("Usage: lox-ast [script]");
+            println!(
+                "Usage: ND-Lang [run] [--module-path DIR]... [--allow-read[=DIR]] [--allow-write[=DIR]] [--max-depth N] [--opt-level N] [--profile] [--collapsed FILE] [script]\n       ND-Lang debug [options] <script>\n       ND-Lang lint [--allow RULE] [--warn RULE] [--deny RULE] [--rules] <files>\n       ND-Lang test [--filter NAME] [options] [paths]\n       ND-Lang fmt [--check] [--indent N] [--width N] <files>\n       ND-Lang ast [--optimized] [--opt-level N] <script>\n       ND-Lang lsp\n       ND-Lang dap"
+            );
             std::process::exit(64);
         }
     }
@@ -1,18 +1,255 @@
 use core::fmt;
+use std::{collections::HashMap, path::PathBuf, rc::Rc};
 
-#[derive(Debug, Clone, PartialEq)]
+use crate::{
+    ast::stmt::{Stmt, Variant},
+    errors::diagnostic::Diagnostic,
+    interpreter::environment::Environment,
+    stdlib::prelude::variant_name,
+    tokens::symbol::Symbol,
+};
+
+#[derive(Clone)]
 pub enum Object {
-    Num(String),
+    Num(f64),
     Str(String),
     Bool(bool),
+    List(Rc<Vec<Object>>),
+    Func(Rc<Function>),
+    Native(Rc<NativeFunction>),
+    Module(Rc<Module>),
+    Enum(Rc<EnumType>),
+    Variant(Rc<VariantValue>),
+    Error(Rc<ErrorValue>),
     Nil,
 }
 
+/// A function value: the parameters and body of a `func` literal along
+/// with the scope it was created in.
+pub struct Function {
+    pub params: Vec<Symbol>,
+    pub body: Rc<Vec<Stmt>>,
+    pub closure: Environment,
+    /// The name of the file the function is written in
+    pub source: Rc<str>,
+}
+
+/// The Rust side of a native function. Errors are messages, which the
+/// interpreter reports at the call.
+pub type NativeFn = Rc<dyn Fn(&[Object]) -> Result<Object, String>>;
+
+/// A function implemented in Rust, like the ones of the standard library.
+pub struct NativeFunction {
+    pub name: String,
+    /// The number of arguments it takes, or `None` if it checks that
+    /// itself
+    pub arity: Option<usize>,
+    pub function: NativeFn,
+}
+
+impl NativeFunction {
+    pub fn new(
+        name: &str,
+        arity: Option<usize>,
+        function: impl Fn(&[Object]) -> Result<Object, String> + 'static,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            arity,
+            function: Rc::new(function),
+        }
+    }
+
+    ///Returns the function with `receiver` as its first argument, which
+    ///is how the functions of the `string` module become string methods
+    pub fn bind(self: &Rc<Self>, receiver: Object) -> NativeFunction {
+        let function = self.clone();
+        NativeFunction::new(
+            &self.name,
+            self.arity.map(|arity| arity.saturating_sub(1)),
+            move |args| {
+                let mut bound = Vec::with_capacity(args.len() + 1);
+                bound.push(receiver.clone());
+                bound.extend_from_slice(args);
+                (function.function)(&bound)
+            },
+        )
+    }
+}
+
+/// An evaluated module: the values it exported, by name.
+pub struct Module {
+    pub path: PathBuf,
+    pub exports: HashMap<Symbol, Object>,
+}
+
+impl Module {
+    ///Returns the file name of the module, as shown to users
+    pub fn name(&self) -> String {
+        module_name(&self.path)
+    }
+}
+
+/// An enum declared with `enum`, which the values of its variants are
+/// made from.
+pub struct EnumType {
+    pub name: Symbol,
+    pub variants: Rc<Vec<Variant>>,
+}
+
+/// A value of one variant of an enum, with the values of its fields.
+pub struct VariantValue {
+    pub enum_type: Rc<EnumType>,
+    /// The position of the variant in the declaration of the enum
+    pub variant: usize,
+    pub values: Vec<Object>,
+}
+
+impl VariantValue {
+    ///Returns the declaration of the value's variant
+    pub fn declaration(&self) -> &Variant {
+        &self.enum_type.variants[self.variant]
+    }
+
+    ///Returns the value of the field called `name`
+    pub fn field(&self, name: Symbol) -> Option<Object> {
+        let position = self
+            .declaration()
+            .fields
+            .iter()
+            .position(|field| *field == name)?;
+        self.values.get(position).cloned()
+    }
+}
+
+/// An error thrown with `throw` or raised by the interpreter, as seen by
+/// a `catch` block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorValue {
+    /// What went wrong, like `TypeError`; `Error` unless given
+    pub kind: String,
+    pub message: String,
+    /// The calls being run when it was thrown, innermost first
+    pub trace: Vec<String>,
+}
+
+impl ErrorValue {
+    pub fn new(kind: &str, message: &str) -> Self {
+        Self {
+            kind: kind.to_string(),
+            message: message.to_string(),
+            trace: Vec::new(),
+        }
+    }
+}
+
+impl From<&Diagnostic> for ErrorValue {
+    fn from(diagnostic: &Diagnostic) -> Self {
+        Self {
+            kind: diagnostic.kind().unwrap_or("RuntimeError").to_string(),
+            message: diagnostic.message().to_string(),
+            trace: diagnostic.trace().to_vec(),
+        }
+    }
+}
+
+///Returns the file name of a module path, as shown to users
+pub fn module_name(path: &std::path::Path) -> String {
+    path.file_name().map_or_else(
+        || path.display().to_string(),
+        |name| name.to_string_lossy().to_string(),
+    )
+}
+
+impl Object {
+    ///Returns whether the value counts as true in a condition. Only
+    ///`false` and `null` are false.
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Object::Bool(false) | Object::Nil)
+    }
+
+    ///Returns the name of the value's type, as used in error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::Num(_) => "number",
+            Object::Str(_) => "string",
+            Object::Bool(_) => "bool",
+            Object::List(_) => "list",
+            Object::Func(_) | Object::Native(_) => "func",
+            Object::Module(_) => "module",
+            Object::Enum(_) => "enum",
+            Object::Variant(_) => "variant",
+            Object::Error(_) => "error",
+            Object::Nil => "null",
+        }
+    }
+}
+
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Object::Num(a), Object::Num(b)) => a == b,
+            (Object::Str(a), Object::Str(b)) => a == b,
+            (Object::Bool(a), Object::Bool(b)) => a == b,
+            (Object::List(a), Object::List(b)) => a == b,
+            // Functions are only equal to themselves
+            (Object::Func(a), Object::Func(b)) => Rc::ptr_eq(a, b),
+            (Object::Native(a), Object::Native(b)) => Rc::ptr_eq(a, b),
+            (Object::Module(a), Object::Module(b)) => Rc::ptr_eq(a, b),
+            (Object::Enum(a), Object::Enum(b)) => Rc::ptr_eq(a, b),
+            // Variants of enums declared apart are different even when
+            // the enums have the same name
+            (Object::Variant(a), Object::Variant(b)) => {
+                Rc::ptr_eq(&a.enum_type, &b.enum_type)
+                    && a.variant == b.variant
+                    && a.values == b.values
+            }
+            (Object::Error(a), Object::Error(b)) => a == b,
+            (Object::Nil, Object::Nil) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Debug for Object {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Object::Str(x) => write!(f, "{:?}", x),
+            _ => write!(f, "{}", self),
+        }
+    }
+}
+
 impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Object::Num(x) => write!(f, "{x}"),
             Object::Str(x) => write!(f, "{x}"),
+            Object::List(items) => {
+                let items: Vec<String> = items.iter().map(|item| format!("{:?}", item)).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+            Object::Func(function) => write!(f, "<func({})>", function.params.len()),
+            Object::Native(function) => write!(f, "<native func {}>", function.name),
+            Object::Module(module) => write!(f, "<module {}>", module.name()),
+            Object::Enum(enum_type) => write!(f, "<enum {}>", enum_type.name),
+            Object::Error(error) => write!(f, "{}: {}", error.kind, error.message),
+            Object::Variant(value) => {
+                write!(
+                    f,
+                    "{}",
+                    variant_name(value.enum_type.name, value.declaration().name)
+                )?;
+                if !value.values.is_empty() {
+                    let values: Vec<String> = value
+                        .values
+                        .iter()
+                        .map(|value| format!("{:?}", value))
+                        .collect();
+                    write!(f, "({})", values.join(", "))?;
+                }
+                Ok(())
+            }
             Object::Nil => write!(f, "Nil"),
             Object::Bool(x) => {
                 if *x {
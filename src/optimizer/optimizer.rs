@@ -0,0 +1,507 @@
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use crate::{
+    ast::{
+        expr::Expr,
+        pattern::MatchArm,
+        stmt::{Catch, Import, Stmt},
+    },
+    interpreter::interpreter::{Interpreter, STACK_GROWTH, STACK_RED_ZONE},
+    object::object::Object,
+    tokens::{symbol::Symbol, token::Token, token_type::TokenType},
+};
+
+/// How much programs are rewritten before they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum OptLevel {
+    /// Run programs as they were parsed
+    None,
+    /// Fold constant expressions and drop operations that leave their
+    /// operand unchanged, like `x * 1`
+    Fold,
+    /// Also replace the names of `const` bindings by their values
+    #[default]
+    Full,
+}
+
+impl OptLevel {
+    ///Returns the level numbered `level`, as given to `--opt-level`
+    pub fn from_number(level: usize) -> Option<Self> {
+        match level {
+            0 => Some(OptLevel::None),
+            1 => Some(OptLevel::Fold),
+            2 => Some(OptLevel::Full),
+            _ => None,
+        }
+    }
+}
+
+/// The names declared in a block or function body.
+struct Scope {
+    /// Each name with its value, for the constants declared so far
+    names: HashMap<Symbol, Option<Expr>>,
+    /// The names declared more than once, which are never replaced
+    redeclared: HashSet<Symbol>,
+    /// Whether the scope is the body of a function
+    function: bool,
+}
+
+/// Rewrites parsed statements into equivalent ones that do less work.
+/// Constant expressions are computed once, here, by the interpreter
+/// itself, so they give the same values they would at run time. Those
+/// that fail, like `1 / 0`, are left for the run to report.
+pub struct Optimizer {
+    level: OptLevel,
+    /// Computes the constant expressions
+    evaluator: Interpreter,
+    /// The scopes around the code being optimized, outermost first
+    scopes: Vec<Scope>,
+}
+
+impl Optimizer {
+    pub fn new(level: OptLevel) -> Self {
+        Self {
+            level,
+            evaluator: Interpreter::new(),
+            scopes: Vec::new(),
+        }
+    }
+
+    ///Optimizes the statements of a program
+    pub fn optimize(&mut self, statements: Vec<Stmt>) -> Vec<Stmt> {
+        if self.level == OptLevel::None {
+            return statements;
+        }
+        self.scope(statements, &[], false)
+    }
+
+    ///Optimizes an expression on its own, outside of any scope
+    pub fn optimize_expression(&mut self, expr: Expr) -> Expr {
+        if self.level == OptLevel::None {
+            return expr;
+        }
+        self.expression(expr)
+    }
+
+    ///Optimizes the statements of a scope, in which `params` are declared
+    ///as well
+    fn scope(&mut self, body: Vec<Stmt>, params: &[Symbol], function: bool) -> Vec<Stmt> {
+        self.enter(&body, params, function);
+        let body = body.into_iter().map(|stmt| self.statement(stmt)).collect();
+        self.scopes.pop();
+        body
+    }
+
+    ///Opens the scope of `body`, declaring the names it declares and
+    ///`params`
+    fn enter(&mut self, body: &[Stmt], params: &[Symbol], function: bool) {
+        let mut scope = Scope {
+            names: HashMap::new(),
+            redeclared: HashSet::new(),
+            function,
+        };
+        let declared = params
+            .iter()
+            .copied()
+            .chain(body.iter().flat_map(declarations));
+        for name in declared {
+            if scope.names.insert(name, None).is_some() {
+                scope.redeclared.insert(name);
+            }
+        }
+        self.scopes.push(scope);
+    }
+
+    fn statement(&mut self, stmt: Stmt) -> Stmt {
+        match stmt {
+            Stmt::ExpressionStmt { expression, line } => Stmt::ExpressionStmt {
+                expression: self.expression(expression),
+                line,
+            },
+            Stmt::BlockStmt {
+                body,
+                line,
+                end_line,
+            } => Stmt::BlockStmt {
+                body: self.scope(body, &[], false),
+                line,
+                end_line,
+            },
+            Stmt::IfStmt {
+                condition,
+                consequent,
+                alternate,
+                line,
+            } => Stmt::IfStmt {
+                condition: self.expression(condition),
+                consequent: Box::new(self.statement(*consequent)),
+                alternate: alternate.map(|alternate| Box::new(self.statement(*alternate))),
+                line,
+            },
+            Stmt::VarDeclarationStmt {
+                identifier,
+                isConstant,
+                assignedValue,
+                explicitType,
+                line,
+            } => {
+                let value = self.expression(assignedValue);
+                if isConstant && self.level == OptLevel::Full && is_literal(&value) {
+                    self.declare_constant(identifier, &value);
+                }
+                Stmt::VarDeclarationStmt {
+                    identifier,
+                    isConstant,
+                    assignedValue: value,
+                    explicitType,
+                    line,
+                }
+            }
+            Stmt::ReturnStmt { value, line } => Stmt::ReturnStmt {
+                value: value.map(|value| self.expression(value)),
+                line,
+            },
+            Stmt::OperatorDeclStmt {
+                operator,
+                fixity,
+                function,
+                line,
+            } => Stmt::OperatorDeclStmt {
+                operator,
+                fixity,
+                function: self.expression(function),
+                line,
+            },
+            Stmt::ExportStmt { declaration, line } => Stmt::ExportStmt {
+                declaration: Box::new(self.statement(*declaration)),
+                line,
+            },
+            Stmt::TryStmt {
+                body,
+                catch,
+                finally,
+                line,
+            } => Stmt::TryStmt {
+                body: Box::new(self.statement(*body)),
+                catch: catch.map(|catch| {
+                    let names: Vec<Symbol> = catch.name.into_iter().collect();
+                    self.enter(&[], &names, false);
+                    let body = Box::new(self.statement(*catch.body));
+                    self.scopes.pop();
+                    Catch {
+                        name: catch.name,
+                        body,
+                    }
+                }),
+                finally: finally.map(|finally| Box::new(self.statement(*finally))),
+                line,
+            },
+            Stmt::ThrowStmt { value, line } => Stmt::ThrowStmt {
+                value: self.expression(value),
+                line,
+            },
+            Stmt::TestStmt { name, body, line } => Stmt::TestStmt {
+                name,
+                body: Box::new(self.statement(*body)),
+                line,
+            },
+            stmt @ (Stmt::ImportStmt { .. } | Stmt::EnumDeclStmt { .. }) => stmt,
+        }
+    }
+
+    fn expression(&mut self, expr: Expr) -> Expr {
+        // Nested expressions recurse through here, as in the interpreter
+        stacker::maybe_grow(STACK_RED_ZONE, STACK_GROWTH, || {
+            self.nested_expression(expr)
+        })
+    }
+
+    fn nested_expression(&mut self, expr: Expr) -> Expr {
+        match expr {
+            Expr::Identifier(name) => self.constant(name).unwrap_or(expr),
+            // The tree already groups the operands
+            Expr::Grouping { group } => self.expression(*group),
+            Expr::Unary { op, right } => {
+                let right = self.expression(*right);
+                let unary = Expr::Unary {
+                    op,
+                    right: Box::new(right),
+                };
+                self.unary(unary)
+            }
+            Expr::BinaryOp { left, op, right } => {
+                let left = self.expression(*left);
+                let right = self.expression(*right);
+                self.binary(left, op, right)
+            }
+            Expr::Assignment {
+                assignee,
+                op,
+                assigned,
+            } => Expr::Assignment {
+                assignee,
+                op,
+                assigned: Box::new(self.expression(*assigned)),
+            },
+            Expr::Function {
+                params,
+                body,
+                line,
+                end_line,
+            } => {
+                let body = self.scope(body.as_ref().clone(), &params, true);
+                Expr::Function {
+                    params,
+                    body: Rc::new(body),
+                    line,
+                    end_line,
+                }
+            }
+            Expr::Call {
+                callee,
+                args,
+                line,
+                position,
+            } => Expr::Call {
+                // Callees keep their names, which stack traces show
+                callee: match *callee {
+                    callee @ (Expr::Identifier(_) | Expr::Member { .. }) => Box::new(callee),
+                    callee => Box::new(self.expression(callee)),
+                },
+                args: args.into_iter().map(|arg| self.expression(arg)).collect(),
+                line,
+                position,
+            },
+            Expr::List(items) => Expr::List(
+                items
+                    .into_iter()
+                    .map(|item| self.expression(item))
+                    .collect(),
+            ),
+            Expr::Index { object, index } => Expr::Index {
+                object: Box::new(self.expression(*object)),
+                index: Box::new(self.expression(*index)),
+            },
+            Expr::Member { object, property } => Expr::Member {
+                object: Box::new(self.expression(*object)),
+                property,
+            },
+            Expr::Propagate { value, line } => Expr::Propagate {
+                value: Box::new(self.expression(*value)),
+                line,
+            },
+            Expr::Match {
+                value,
+                arms,
+                line,
+                end_line,
+            } => Expr::Match {
+                value: Box::new(self.expression(*value)),
+                arms: arms.into_iter().map(|arm| self.arm(arm)).collect(),
+                line,
+                end_line,
+            },
+            Expr::Number(_) | Expr::String(_) | Expr::Bool(_) | Expr::Null => expr,
+        }
+    }
+
+    fn arm(&mut self, arm: MatchArm) -> MatchArm {
+        // The guard and body see the names the pattern binds
+        self.enter(&[], &arm.pattern.bindings(), false);
+        let arm = MatchArm {
+            guard: arm.guard.map(|guard| self.expression(guard)),
+            body: self.expression(arm.body),
+            ..arm
+        };
+        self.scopes.pop();
+        arm
+    }
+
+    fn unary(&mut self, unary: Expr) -> Expr {
+        let Expr::Unary { op, right } = &unary else {
+            return unary;
+        };
+        match op.ttype {
+            TokenType::BANG | TokenType::MINUS if is_literal(right) => self.fold(unary),
+            // `!!b` is `b` when `b` is a bool already
+            TokenType::BANG => match right.as_ref() {
+                Expr::Unary { op, right } if op.ttype == TokenType::BANG && is_bool(right) => {
+                    right.as_ref().clone()
+                }
+                _ => unary,
+            },
+            _ => unary,
+        }
+    }
+
+    fn binary(&mut self, left: Expr, op: Token<'static>, right: Expr) -> Expr {
+        // `&&` and `||` with a known left side are decided by it
+        match op.ttype {
+            TokenType::AND | TokenType::OR if is_literal(&left) => {
+                let truthy = self.evaluate(&left).is_some_and(|left| left.is_truthy());
+                return match (op.ttype == TokenType::AND, truthy) {
+                    (true, true) | (false, false) => right,
+                    _ => left,
+                };
+            }
+            TokenType::OPERATOR | TokenType::AND | TokenType::OR => {
+                return Expr::BinaryOp {
+                    left: Box::new(left),
+                    op,
+                    right: Box::new(right),
+                }
+            }
+            _ => {}
+        }
+        if is_literal(&left) && is_literal(&right) {
+            return self.fold(Expr::BinaryOp {
+                left: Box::new(left),
+                op,
+                right: Box::new(right),
+            });
+        }
+
+        // Operations that give back their operand, as long as it is a
+        // number: `x * 1` is an error for a string `x`. Dropping `+ 0`
+        // leaves a negative zero negative, the one value it would change.
+        let (left_number, right_number) = (is_number(&left), is_number(&right));
+        let keep_left = match op.ttype {
+            TokenType::STAR | TokenType::SLASH => left_number && is_number_literal(&right, 1.0),
+            TokenType::PLUS | TokenType::MINUS => left_number && is_number_literal(&right, 0.0),
+            _ => false,
+        };
+        let keep_right = match op.ttype {
+            TokenType::STAR => is_number_literal(&left, 1.0) && right_number,
+            TokenType::PLUS => is_number_literal(&left, 0.0) && right_number,
+            _ => false,
+        };
+        match (keep_left, keep_right) {
+            (true, _) => left,
+            (_, true) => right,
+            _ => Expr::BinaryOp {
+                left: Box::new(left),
+                op,
+                right: Box::new(right),
+            },
+        }
+    }
+
+    ///Replaces a constant expression by its value, unless computing it
+    ///fails
+    fn fold(&mut self, expr: Expr) -> Expr {
+        self.evaluate(&expr)
+            .and_then(|value| literal(&value))
+            .unwrap_or(expr)
+    }
+
+    fn evaluate(&mut self, expr: &Expr) -> Option<Object> {
+        self.evaluator.evaluate(expr).ok()
+    }
+
+    fn declare_constant(&mut self, name: Symbol, value: &Expr) {
+        if let Some(scope) = self.scopes.last_mut() {
+            if !scope.redeclared.contains(&name) {
+                scope.names.insert(name, Some(value.clone()));
+            }
+        }
+    }
+
+    ///Returns the value of the constant `name` refers to here, if it is
+    ///known
+    fn constant(&self, name: Symbol) -> Option<Expr> {
+        let mut in_function = false;
+        for (depth, scope) in self.scopes.iter().enumerate().rev() {
+            if let Some(value) = scope.names.get(&name) {
+                // Functions can be called from later runs of the prompt,
+                // which may declare the globals again
+                if depth == 0 && in_function {
+                    return None;
+                }
+                return value.clone();
+            }
+            in_function |= scope.function;
+        }
+        None
+    }
+}
+
+impl Default for Optimizer {
+    fn default() -> Self {
+        Self::new(OptLevel::default())
+    }
+}
+
+///Returns the names a statement declares in the scope it is in
+fn declarations(stmt: &Stmt) -> Vec<Symbol> {
+    match stmt {
+        Stmt::VarDeclarationStmt { identifier, .. } => vec![*identifier],
+        Stmt::EnumDeclStmt { name, .. } => vec![*name],
+        Stmt::ImportStmt { import, .. } => match import {
+            Import::Module(alias) => vec![*alias],
+            Import::Names(names) => names.clone(),
+        },
+        Stmt::ExportStmt { declaration, .. } => declarations(declaration),
+        _ => Vec::new(),
+    }
+}
+
+///Returns the literal for a value, if it has one
+fn literal(value: &Object) -> Option<Expr> {
+    match value {
+        Object::Num(n) => Some(Expr::Number(n.to_string())),
+        Object::Str(s) => Some(Expr::String(s.clone())),
+        Object::Bool(b) => Some(Expr::Bool(*b)),
+        Object::Nil => Some(Expr::Null),
+        _ => None,
+    }
+}
+
+fn is_literal(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Number(_) | Expr::String(_) | Expr::Bool(_) | Expr::Null
+    )
+}
+
+fn is_number_literal(expr: &Expr, value: f64) -> bool {
+    matches!(expr, Expr::Number(n) if n.parse::<f64>() == Ok(value))
+}
+
+///Checks whether an expression can only give a number, if it does not
+///fail
+fn is_number(expr: &Expr) -> bool {
+    match expr {
+        Expr::Number(_) => true,
+        Expr::Unary { op, .. } => op.ttype == TokenType::MINUS,
+        Expr::BinaryOp { op, .. } => matches!(
+            op.ttype,
+            TokenType::MINUS
+                | TokenType::STAR
+                | TokenType::SLASH
+                | TokenType::MODULO
+                | TokenType::POW
+        ),
+        _ => false,
+    }
+}
+
+///Checks whether an expression can only give a bool, if it does not fail
+fn is_bool(expr: &Expr) -> bool {
+    match expr {
+        Expr::Bool(_) => true,
+        Expr::Unary { op, .. } => op.ttype == TokenType::BANG,
+        Expr::BinaryOp { op, .. } => matches!(
+            op.ttype,
+            TokenType::EQUALEQUAL
+                | TokenType::BANGEQUAL
+                | TokenType::LESS
+                | TokenType::LESSEQUAL
+                | TokenType::GREATER
+                | TokenType::GREATEREQUAL
+        ),
+        _ => false,
+    }
+}
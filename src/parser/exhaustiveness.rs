@@ -0,0 +1,489 @@
+//! Checks the arms of `match` expressions: that every value is matched by
+//! some arm, and that every arm can match a value the arms before it did
+//! not. This is the usefulness algorithm of Maranget's "Warnings for
+//! pattern matching", run on the patterns' constructors: `true`, `false`,
+//! `null`, number and string constants and ranges, lists of each length
+//! and the variants of enums.
+//!
+//! Values have no static types, so only bools, lists and enums declared
+//! in the same file can be covered without a catch-all arm, and only when
+//! every arm agrees on which of them is being matched. Numbers and
+//! strings always need one.
+
+use core::fmt;
+use std::{cmp::Ordering, collections::HashMap, rc::Rc};
+
+use crate::{
+    ast::{
+        expr::{join, Expr},
+        pattern::{MatchArm, Pattern},
+        stmt::Variant,
+    },
+    errors::diagnostic::Diagnostic,
+    stdlib::prelude::variant_name,
+    tokens::symbol::Symbol,
+};
+
+/// The enums declared in a file, by name
+type Enums = HashMap<Symbol, Rc<Vec<Variant>>>;
+
+/// A number or string in a pattern
+#[derive(Debug, Clone, PartialEq)]
+enum Constant {
+    Num(f64),
+    Str(String),
+}
+
+impl Constant {
+    fn from_expr(expr: &Expr) -> Option<Constant> {
+        match expr {
+            Expr::Number(x) => x.parse().ok().map(Constant::Num),
+            Expr::String(x) => Some(Constant::Str(x.clone())),
+            Expr::Unary { op, right } if op.lexeme == "-" => match Constant::from_expr(right)? {
+                Constant::Num(x) => Some(Constant::Num(-x)),
+                Constant::Str(_) => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn compare(&self, other: &Constant) -> Option<Ordering> {
+        match (self, other) {
+            (Constant::Num(a), Constant::Num(b)) => a.partial_cmp(b),
+            (Constant::Str(a), Constant::Str(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+}
+
+/// What a pattern requires of the outermost shape of a value
+#[derive(Debug, Clone, PartialEq)]
+enum Constructor {
+    Bool(bool),
+    Null,
+    Constant(Constant),
+    Range {
+        start: Constant,
+        end: Constant,
+        inclusive: bool,
+    },
+    /// A list of exactly `len` items, or of `len` or more
+    List {
+        len: usize,
+        more: bool,
+    },
+    /// A variant of an enum, with the variants of the enum if it was
+    /// declared in the file
+    Variant {
+        enum_name: Symbol,
+        variant: Symbol,
+        arity: usize,
+        variants: Option<Rc<Vec<Variant>>>,
+    },
+}
+
+impl Constructor {
+    ///Returns how many sub-patterns the constructor has
+    fn arity(&self) -> usize {
+        match self {
+            Constructor::List { len, .. } => *len,
+            Constructor::Variant { arity, .. } => *arity,
+            _ => 0,
+        }
+    }
+
+    ///Checks whether every value `other` matches is also matched by
+    ///`self`. Constants and ranges that only partly overlap do not cover
+    ///each other, which can hide an unreachable arm but never reports a
+    ///reachable one.
+    fn covers(&self, other: &Constructor) -> bool {
+        match (self, other) {
+            (
+                Constructor::Range {
+                    start,
+                    end,
+                    inclusive,
+                },
+                Constructor::Constant(value),
+            ) => {
+                start.compare(value).is_some_and(Ordering::is_le)
+                    && match value.compare(end) {
+                        Some(Ordering::Less) => true,
+                        Some(Ordering::Equal) => *inclusive,
+                        _ => false,
+                    }
+            }
+            (
+                Constructor::Range {
+                    start,
+                    end,
+                    inclusive,
+                },
+                Constructor::Range {
+                    start: other_start,
+                    end: other_end,
+                    inclusive: other_inclusive,
+                },
+            ) => {
+                start.compare(other_start).is_some_and(Ordering::is_le)
+                    && match other_end.compare(end) {
+                        Some(Ordering::Less) => true,
+                        Some(Ordering::Equal) => *inclusive || !*other_inclusive,
+                        _ => false,
+                    }
+            }
+            _ => self == other,
+        }
+    }
+}
+
+/// One pattern of a row, reduced to what the checker needs
+#[derive(Debug, Clone)]
+enum Pat {
+    Wild,
+    Constructor(Constructor),
+    /// `[a, b]` or, with `rest`, `[a, b, ..]`
+    List {
+        items: Vec<Pat>,
+        rest: bool,
+    },
+    /// `Enum.Variant(a, b)`
+    Variant {
+        constructor: Constructor,
+        fields: Vec<Pat>,
+    },
+}
+
+impl Pat {
+    fn from_pattern(pattern: &Pattern, enums: &Enums) -> Pat {
+        match pattern {
+            Pattern::Wildcard | Pattern::Binding(_) => Pat::Wild,
+            Pattern::Literal(Expr::Bool(x)) => Pat::Constructor(Constructor::Bool(*x)),
+            Pattern::Literal(Expr::Null) => Pat::Constructor(Constructor::Null),
+            Pattern::Literal(expr) => match Constant::from_expr(expr) {
+                Some(constant) => Pat::Constructor(Constructor::Constant(constant)),
+                // The parser only makes literal patterns of constants
+                None => Pat::Wild,
+            },
+            Pattern::Range {
+                start,
+                end,
+                inclusive,
+            } => match (Constant::from_expr(start), Constant::from_expr(end)) {
+                (Some(start), Some(end)) => Pat::Constructor(Constructor::Range {
+                    start,
+                    end,
+                    inclusive: *inclusive,
+                }),
+                _ => Pat::Wild,
+            },
+            Pattern::List { items, rest } => Pat::List {
+                items: items
+                    .iter()
+                    .map(|item| Pat::from_pattern(item, enums))
+                    .collect(),
+                rest: rest.is_some(),
+            },
+            Pattern::Variant {
+                enum_name,
+                variant,
+                fields,
+            } => Pat::Variant {
+                constructor: Constructor::Variant {
+                    enum_name: *enum_name,
+                    variant: *variant,
+                    arity: fields.len(),
+                    variants: enums.get(enum_name).cloned(),
+                },
+                fields: fields
+                    .iter()
+                    .map(|field| Pat::from_pattern(field, enums))
+                    .collect(),
+            },
+        }
+    }
+
+    ///Returns the sub-patterns the pattern has when matched against
+    ///`constructor`, or `None` if it does not match all of its values
+    fn specialize(&self, constructor: &Constructor) -> Option<Vec<Pat>> {
+        match self {
+            Pat::Wild => Some(vec![Pat::Wild; constructor.arity()]),
+            Pat::Constructor(own) => own.covers(constructor).then(Vec::new),
+            Pat::List { items, rest } => match constructor {
+                Constructor::List { len, more } => {
+                    let fits = if *rest {
+                        items.len() <= *len
+                    } else {
+                        items.len() == *len && !more
+                    };
+                    fits.then(|| {
+                        let mut items = items.clone();
+                        items.resize(*len, Pat::Wild);
+                        items
+                    })
+                }
+                _ => None,
+            },
+            Pat::Variant {
+                constructor: own,
+                fields,
+            } => (own == constructor).then(|| fields.clone()),
+        }
+    }
+}
+
+/// A value no arm matches, shown as a pattern
+enum Witness {
+    Wild,
+    Constructor(Constructor),
+    List {
+        items: Vec<Witness>,
+        more: bool,
+    },
+    Variant {
+        constructor: Constructor,
+        fields: Vec<Witness>,
+    },
+}
+
+impl Witness {
+    ///Builds the witness for `constructor` out of the first witnesses of
+    ///`fields`
+    fn apply(constructor: &Constructor, fields: &mut Vec<Witness>) -> Witness {
+        let items: Vec<Witness> = fields.drain(..constructor.arity()).collect();
+        match constructor {
+            Constructor::List { more, .. } => Witness::List { items, more: *more },
+            Constructor::Variant { .. } => Witness::Variant {
+                constructor: constructor.clone(),
+                fields: items,
+            },
+            constructor => Witness::Constructor(constructor.clone()),
+        }
+    }
+}
+
+impl fmt::Display for Witness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Witness::Wild => write!(f, "_"),
+            Witness::Constructor(Constructor::Bool(x)) => write!(f, "{}", x),
+            Witness::Constructor(Constructor::Null) => write!(f, "null"),
+            Witness::Constructor(_) => write!(f, "_"),
+            Witness::List { items, more } => {
+                let mut items: Vec<String> = items.iter().map(Witness::to_string).collect();
+                if *more {
+                    items.push("..".to_string());
+                }
+                write!(f, "[{}]", join(&items))
+            }
+            Witness::Variant {
+                constructor:
+                    Constructor::Variant {
+                        enum_name, variant, ..
+                    },
+                fields,
+            } => {
+                write!(f, "{}", variant_name(*enum_name, *variant))?;
+                if !fields.is_empty() {
+                    write!(f, "({})", join(fields))?;
+                }
+                Ok(())
+            }
+            Witness::Variant { .. } => write!(f, "_"),
+        }
+    }
+}
+
+///Returns the list constructors needed to tell the list patterns of
+///`column` apart: every length up to the longest one, then a single
+///constructor for all the longer lists
+fn list_constructors<'a>(column: impl Iterator<Item = &'a Pat>) -> Vec<Constructor> {
+    let mut longest = 0;
+    for pattern in column {
+        if let Pat::List { items, rest } = pattern {
+            longest = longest.max(items.len() + usize::from(!rest));
+        }
+    }
+    (0..longest)
+        .map(|len| Constructor::List { len, more: false })
+        .chain([Constructor::List {
+            len: longest,
+            more: true,
+        }])
+        .collect()
+}
+
+///Checks whether some constructor pattern at the head of `rows` covers
+///all values of `constructor`
+fn covered(rows: &[Vec<Pat>], constructor: &Constructor) -> bool {
+    rows.iter()
+        .any(|row| !matches!(row[0], Pat::Wild) && row[0].specialize(constructor).is_some())
+}
+
+///Returns the constructors of the column of `rows` if there is a finite
+///set of them, which only bools, lists and declared enums have. Rows of
+///any other kind, apart from `null` and catch-alls, leave the column
+///open.
+fn signature(rows: &[Vec<Pat>]) -> Option<Vec<Constructor>> {
+    let mut bools = false;
+    let mut lists = false;
+    let mut enums = Vec::new();
+    for row in rows {
+        match &row[0] {
+            Pat::Wild | Pat::Constructor(Constructor::Null) => {}
+            Pat::Constructor(Constructor::Bool(_)) => bools = true,
+            Pat::List { .. } => lists = true,
+            Pat::Variant {
+                constructor:
+                    Constructor::Variant {
+                        enum_name,
+                        variants: Some(variants),
+                        ..
+                    },
+                ..
+            } => enums.push((*enum_name, variants)),
+            Pat::Constructor(_) | Pat::Variant { .. } => return None,
+        }
+    }
+    match (bools, lists, enums.as_slice()) {
+        (true, false, []) => Some(vec![Constructor::Bool(true), Constructor::Bool(false)]),
+        (false, true, []) => Some(list_constructors(rows.iter().map(|row| &row[0]))),
+        (false, false, [(enum_name, variants), others @ ..])
+            if others.iter().all(|(other, _)| other == enum_name) =>
+        {
+            Some(
+                variants
+                    .iter()
+                    .map(|variant| Constructor::Variant {
+                        enum_name: *enum_name,
+                        variant: variant.name,
+                        arity: variant.fields.len(),
+                        variants: Some(Rc::clone(variants)),
+                    })
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
+
+///Returns the rows of `rows` that match `constructor`, with their first
+///pattern replaced by its sub-patterns
+fn specialize(rows: &[Vec<Pat>], constructor: &Constructor) -> Vec<Vec<Pat>> {
+    rows.iter()
+        .filter_map(|row| {
+            let mut specialized = row[0].specialize(constructor)?;
+            specialized.extend_from_slice(&row[1..]);
+            Some(specialized)
+        })
+        .collect()
+}
+
+///Returns a value matched by `row` but by none of `rows`, if there is one
+fn useful(rows: &[Vec<Pat>], row: &[Pat]) -> Option<Vec<Witness>> {
+    let Some(head) = row.first() else {
+        return rows.is_empty().then(Vec::new);
+    };
+
+    let constructors = match head {
+        Pat::Constructor(constructor) | Pat::Variant { constructor, .. } => {
+            vec![constructor.clone()]
+        }
+        Pat::List { rest: false, items } => vec![Constructor::List {
+            len: items.len(),
+            more: false,
+        }],
+        // A list pattern with a rest matches every length from its own
+        // up, so it is useful if it is for any of them
+        Pat::List { rest: true, items } => {
+            let column = rows.iter().map(|row| &row[0]).chain([head]);
+            list_constructors(column)
+                .into_iter()
+                .filter(|constructor| constructor.arity() >= items.len())
+                .collect()
+        }
+        Pat::Wild => match signature(rows) {
+            Some(constructors)
+                if constructors
+                    .iter()
+                    .all(|constructor| covered(rows, constructor)) =>
+            {
+                constructors
+            }
+            _ => {
+                // Only the rows that match anything can match the values
+                // none of the constructors in the column cover
+                let default: Vec<Vec<Pat>> = rows
+                    .iter()
+                    .filter(|row| matches!(row[0], Pat::Wild))
+                    .map(|row| row[1..].to_vec())
+                    .collect();
+                let mut witness = useful(&default, &row[1..])?;
+                witness.insert(0, missing(rows));
+                return Some(witness);
+            }
+        },
+    };
+
+    constructors.iter().find_map(|constructor| {
+        let mut specialized_row = head.specialize(constructor)?;
+        specialized_row.extend_from_slice(&row[1..]);
+        let mut fields = useful(&specialize(rows, constructor), &specialized_row)?;
+        let witness = Witness::apply(constructor, &mut fields);
+        fields.insert(0, witness);
+        Some(fields)
+    })
+}
+
+///Returns a value of the column of `rows` that none of them cover, as
+///precisely as it can be named
+fn missing(rows: &[Vec<Pat>]) -> Witness {
+    let constructor = signature(rows)
+        .into_iter()
+        .flatten()
+        .find(|constructor| !covered(rows, constructor));
+    match constructor {
+        Some(constructor) => {
+            let mut fields = (0..constructor.arity()).map(|_| Witness::Wild).collect();
+            Witness::apply(&constructor, &mut fields)
+        }
+        None => Witness::Wild,
+    }
+}
+
+///Checks the arms of the `match` at `line`/`position`, returning a
+///diagnostic for each arm that can never be reached and one if some
+///value matches no arm. Arms with a guard may not match, so they do not
+///cover anything.
+pub fn check(arms: &[MatchArm], enums: &Enums, line: usize, position: usize) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut rows: Vec<Vec<Pat>> = Vec::new();
+
+    for arm in arms {
+        let row = vec![Pat::from_pattern(&arm.pattern, enums)];
+        if useful(&rows, &row).is_none() {
+            diagnostics.push(Diagnostic::new(
+                arm.position,
+                arm.line,
+                format!("Unreachable match arm \"{}\"", arm.pattern),
+                "The arms before it already match every value it does".to_string(),
+            ));
+        }
+        if arm.guard.is_none() {
+            rows.push(row);
+        }
+    }
+
+    if let Some(witness) = useful(&rows, &[Pat::Wild]) {
+        diagnostics.push(Diagnostic::new(
+            position,
+            line,
+            format!(
+                "Non-exhaustive match: \"{}\" is not matched",
+                join(&witness)
+            ),
+            "Add an arm for it, or end with \"_ => ...\" to match everything else".to_string(),
+        ));
+    }
+    diagnostics
+}
@@ -1,10 +1,19 @@
+use std::rc::Rc;
+
 use crate::{
-    ast::{expr::Expr, stmt::Stmt},
-    tokens::token_type::TokenType,
+    ast::{
+        expr::Expr,
+        pattern::{MatchArm, Pattern, Rest},
+        stmt::{Catch, Import, Stmt, Variant},
+    },
+    errors::diagnostic::Diagnostic,
+    stdlib::prelude::builtin_variant,
+    tokens::{symbol::Symbol, token::Token, token_type::TokenType},
 };
 
 use super::{
-    lookups::{BP_TABLE, PREC},
+    exhaustiveness,
+    lookups::{Assoc, Fixity, PREC},
     parser::Parser,
 };
 
@@ -21,13 +30,22 @@ pub fn parse_string(parser: &mut Parser) -> Expr {
     Expr::String(token.lexeme.to_string())
 }
 
+pub fn parse_literal(parser: &mut Parser) -> Expr {
+    let token = parser.advance_and_get_current();
+    match token.ttype {
+        TokenType::TRUE => Expr::Bool(true),
+        TokenType::FALSE => Expr::Bool(false),
+        _ => Expr::Null,
+    }
+}
+
 pub fn parse_assignment_expr(parser: &mut Parser, left: Expr) -> Expr {
     let op = parser.advance_and_get_current();
     let rhs = parser.parse_expr(PREC::DefaultBp);
 
     return Expr::Assignment {
         assignee: Box::new(left),
-        op: op.clone(),
+        op: op.to_static(),
         assigned: Box::new(rhs),
     };
 }
@@ -36,7 +54,8 @@ pub fn parse_identifier(parser: &mut Parser) -> Expr {
     // Implementation
     let token = parser.advance_and_get_current();
     // Implementation
-    Expr::Identifier(token.lexeme.to_string())
+    // Identifiers are interned by the scanner
+    Expr::Identifier(token.symbol.unwrap_or_else(|| Symbol::intern(token.lexeme)))
 }
 
 pub fn parse_grouping_expr(parser: &mut Parser) -> Expr {
@@ -51,9 +70,10 @@ pub fn parse_grouping_expr(parser: &mut Parser) -> Expr {
 pub fn parse_unary(parser: &mut Parser) -> Expr {
     let op = parser.advance_and_get_current();
 
-    let right = parser.parse_expr(PREC::Prefix);
+    let right_bp = parser.operators().prefix_bp(&op);
+    let right = parser.parse_expr_bp(right_bp);
     Expr::Unary {
-        op,
+        op: op.to_static(),
         right: Box::new(right),
     }
 }
@@ -61,30 +81,39 @@ pub fn parse_unary(parser: &mut Parser) -> Expr {
 pub fn parse_binary_expr(parser: &mut Parser, left: Expr) -> Expr {
     let op = parser.advance_and_get_current();
 
-    let right = parser.parse_expr(*unsafe { BP_TABLE.get(&op.ttype).unwrap() });
+    let right_bp = parser
+        .operators()
+        .infix(&op)
+        .map_or(u8::from(PREC::DefaultBp), |operator| operator.right_bp());
+    let right = parser.parse_expr_bp(right_bp);
     Expr::BinaryOp {
         left: Box::new(left),
-        op,
+        op: op.to_static(),
         right: Box::new(right),
     }
 }
 
 pub fn parse_block_stmt(parser: &mut Parser) -> Stmt {
     // parser.expect(TokenType::LEFTBRACE);
-    parser.advance();
+    let line = parser.advance_and_get_current().line;
     let mut body: Vec<Stmt> = Vec::new();
 
     while !parser.is_eof() && parser.at().ttype != TokenType::RIGHTBRACE {
         body.push(parser.parse_stmt());
     }
+    let end_line = parser.at().line;
     parser.expect(TokenType::RIGHTBRACE, ']');
 
-    Stmt::BlockStmt { body }
+    Stmt::BlockStmt {
+        body,
+        line,
+        end_line,
+    }
 }
 
 pub fn parse_if_stmt(parser: &mut Parser) -> Stmt {
-    parser.advance();
-    let condition = parser.parse_expr(PREC::Assignment);
+    let line = parser.advance_and_get_current().line;
+    let condition = parser.parse_expr(PREC::DefaultBp);
     let consequent = parser.parse_stmt();
     let mut alternate = None; // Initialize alternate with None
 
@@ -92,11 +121,8 @@ pub fn parse_if_stmt(parser: &mut Parser) -> Stmt {
         if parser.at().ttype == TokenType::ELSE {
             parser.advance();
 
-            if parser.at().ttype == TokenType::IF {
-                alternate = Some(Box::new(parse_if_stmt(parser))); // Use Some() to wrap the alternate value
-            } else {
-                alternate = Some(Box::new(parse_block_stmt(parser))); // Use Some() to wrap the alternate value
-            }
+            // Either `else if` or `else { ... }`, dispatched by parse_stmt
+            alternate = Some(Box::new(parser.parse_stmt())); // Use Some() to wrap the alternate value
         }
     }
 
@@ -104,6 +130,7 @@ pub fn parse_if_stmt(parser: &mut Parser) -> Stmt {
         condition,
         consequent: Box::new(consequent),
         alternate, // Use the initialized alternate value
+        line,
     }
 }
 
@@ -114,28 +141,788 @@ pub fn parse_var_decl_stmt(parser: &mut Parser) -> Stmt {
 
     let explicit_type = if parser.at().ttype == TokenType::COLON {
         parser.expect(TokenType::COLON, ':');
-        Some(parser.advance_and_get_current())
+        Some(parser.advance_and_get_current().to_static())
     } else {
         None
     };
 
     let assignment_value = if parser.at().ttype != TokenType::SEMICOLON {
         parser.expect(TokenType::EQUAL, '=');
-        Some(parser.parse_expr(PREC::Assignment))
+        Some(parser.parse_expr(PREC::DefaultBp))
     } else {
         None
     };
 
-    parser.expect(TokenType::SEMICOLON, ':');
+    parser.expect(TokenType::SEMICOLON, ';');
 
+    // `let x;` starts out null, but constants could never get a value
     if is_constant && assignment_value.is_none() {
-        panic!("Cannot define constant variable without providing default value.")
+        parser.error(Diagnostic::new(
+            symbol_name.position,
+            symbol_name.line,
+            format!(
+                "Constant \"{}\" is declared without a value",
+                symbol_name.lexeme
+            ),
+            "Constants take their value where they are declared: const x = 1;".to_string(),
+        ));
     }
 
     Stmt::VarDeclarationStmt {
         isConstant: is_constant,
-        identifier: symbol_name.lexeme,
-        assignedValue: assignment_value.unwrap(),
+        identifier: symbol_name
+            .symbol
+            .unwrap_or_else(|| Symbol::intern(symbol_name.lexeme)),
+        assignedValue: assignment_value.unwrap_or(Expr::Null),
         explicitType: explicit_type,
+        line: start_token.line,
+    }
+}
+
+pub fn parse_function_expr(parser: &mut Parser) -> Expr {
+    let line = parser.advance_and_get_current().line;
+    parser.expect(TokenType::LEFTPAREN, '(');
+
+    let mut params = Vec::new();
+    while !parser.is_eof() && parser.at().ttype != TokenType::RIGHTPAREN {
+        let param = parser.advance_and_get_current();
+        match param.symbol {
+            Some(symbol) => params.push(symbol),
+            None => parser.error(Diagnostic::new(
+                param.position,
+                param.line,
+                format!("Expected a parameter name but found \"{}\"", param.lexeme),
+                "".to_string(),
+            )),
+        }
+        if parser.at().ttype != TokenType::RIGHTPAREN {
+            parser.expect(TokenType::COMMA, ',');
+        }
+    }
+    parser.expect(TokenType::RIGHTPAREN, ')');
+
+    if parser.at().ttype != TokenType::LEFTBRACE {
+        let token = parser.at().clone();
+        parser.error(Diagnostic::new(
+            token.position,
+            token.line,
+            format!("Expected a function body but found \"{}\"", token.lexeme),
+            "Function bodies are blocks: func(a) { ret a; }".to_string(),
+        ));
+        return Expr::Function {
+            params,
+            body: Rc::new(Vec::new()),
+            line,
+            end_line: line,
+        };
+    }
+
+    match parse_block_stmt(parser) {
+        Stmt::BlockStmt { body, end_line, .. } => Expr::Function {
+            params,
+            body: Rc::new(body),
+            line,
+            end_line,
+        },
+        _ => unreachable!("parse_block_stmt always returns a block"),
+    }
+}
+
+pub fn parse_call_expr(parser: &mut Parser, callee: Expr) -> Expr {
+    let paren = parser.advance_and_get_current();
+    let (line, position) = (paren.line, paren.position);
+    let mut args = Vec::new();
+
+    while !parser.is_eof() && parser.at().ttype != TokenType::RIGHTPAREN {
+        args.push(parser.parse_expr(PREC::DefaultBp));
+        if parser.at().ttype != TokenType::RIGHTPAREN {
+            parser.expect(TokenType::COMMA, ',');
+        }
+    }
+    parser.expect(TokenType::RIGHTPAREN, ')');
+
+    Expr::Call {
+        callee: Box::new(callee),
+        args,
+        line,
+        position,
+    }
+}
+
+pub fn parse_return_stmt(parser: &mut Parser) -> Stmt {
+    let line = parser.advance_and_get_current().line;
+    let value = if parser.at().ttype != TokenType::SEMICOLON {
+        Some(parser.parse_expr(PREC::DefaultBp))
+    } else {
+        None
+    };
+    parser.expect(TokenType::SEMICOLON, ';');
+
+    Stmt::ReturnStmt { value, line }
+}
+
+///Parses the precedence of an operator declaration, reporting it and
+///falling back to `default` when it is not a valid one
+fn parse_operator_precedence(parser: &mut Parser, default: PREC) -> u8 {
+    let precedence = parser.advance_and_get_current();
+    match precedence.lexeme.parse::<u8>() {
+        Ok(level) if (PREC::Primary.level()..=PREC::Call.level()).contains(&level) => {
+            PREC::level_bp(level)
+        }
+        _ => {
+            parser.error(Diagnostic::new(
+                precedence.position,
+                precedence.line,
+                format!("Invalid operator precedence \"{}\"", precedence.lexeme),
+                format!(
+                    "Use a whole number from {} to {}, e.g. {} like `+` or {} like `*`",
+                    PREC::Primary.level(),
+                    PREC::Call.level(),
+                    PREC::Additive.level(),
+                    PREC::Multiplicative.level()
+                ),
+            ));
+            default.into()
+        }
+    }
+}
+
+///Parses `infix <op> <precedence> [left|right] = <function>;` or
+///`prefix <op> [precedence] = <function>;` and adds the operator to the
+///grammar for the rest of the program
+pub fn parse_operator_decl_stmt(parser: &mut Parser) -> Stmt {
+    let start_token = parser.advance_and_get_current();
+    let operator = parser.advance_and_get_current();
+    if operator.ttype != TokenType::OPERATOR {
+        parser.error(Diagnostic::new(
+            operator.position,
+            operator.line,
+            format!("Cannot declare \"{}\" as an operator", operator.lexeme),
+            "Operators are made of symbols, and built-in ones cannot be redeclared".to_string(),
+        ));
+    }
+
+    let fixity = if start_token.ttype == TokenType::INFIX {
+        let bp = parse_operator_precedence(parser, PREC::Additive);
+
+        let assoc = match parser.at().lexeme {
+            "left" if parser.at().ttype == TokenType::IDENTIFIER => Some(Assoc::Left),
+            "right" if parser.at().ttype == TokenType::IDENTIFIER => Some(Assoc::Right),
+            _ => None,
+        };
+        if assoc.is_some() {
+            parser.advance();
+        }
+        Fixity::Infix {
+            bp,
+            assoc: assoc.unwrap_or(Assoc::Left),
+        }
+    } else if parser.at().ttype == TokenType::EQUAL {
+        Fixity::Prefix {
+            bp: PREC::Prefix.into(),
+        }
+    } else {
+        Fixity::Prefix {
+            bp: parse_operator_precedence(parser, PREC::Prefix),
+        }
+    };
+
+    parser.expect(TokenType::EQUAL, '=');
+    let function = parser.parse_expr(PREC::DefaultBp);
+    parser.expect(TokenType::SEMICOLON, ';');
+
+    let arity = match fixity {
+        Fixity::Infix { .. } => 2,
+        Fixity::Prefix { .. } => 1,
+    };
+    if let Expr::Function { params, line, .. } = &function {
+        if params.len() != arity {
+            parser.error(Diagnostic::new(
+                0,
+                *line,
+                format!(
+                    "The function of operator \"{}\" should take {} parameters, not {}",
+                    operator.lexeme,
+                    arity,
+                    params.len()
+                ),
+                "".to_string(),
+            ));
+        }
+    }
+
+    if operator.ttype == TokenType::OPERATOR {
+        if let Some(symbol) = operator.symbol {
+            parser.operators_mut().register_operator(symbol, fixity);
+        }
+    }
+
+    Stmt::OperatorDeclStmt {
+        operator: operator.to_static(),
+        fixity,
+        function,
+        line: start_token.line,
+    }
+}
+
+pub fn parse_list_expr(parser: &mut Parser) -> Expr {
+    parser.advance();
+    let mut items = Vec::new();
+
+    while !parser.is_eof() && parser.at().ttype != TokenType::RIGHTBRACKET {
+        items.push(parser.parse_expr(PREC::DefaultBp));
+        if parser.at().ttype != TokenType::RIGHTBRACKET {
+            parser.expect(TokenType::COMMA, ',');
+        }
+    }
+    parser.expect(TokenType::RIGHTBRACKET, ']');
+
+    Expr::List(items)
+}
+
+pub fn parse_index_expr(parser: &mut Parser, object: Expr) -> Expr {
+    parser.advance();
+    let index = parser.parse_expr(PREC::DefaultBp);
+    parser.expect(TokenType::RIGHTBRACKET, ']');
+
+    Expr::Index {
+        object: Box::new(object),
+        index: Box::new(index),
+    }
+}
+
+///Parses `value?`, which unwraps an `Ok`/`Some` or returns an
+///`Err`/`None` from the enclosing function
+pub fn parse_propagate_expr(parser: &mut Parser, value: Expr) -> Expr {
+    let line = parser.advance_and_get_current().line;
+    Expr::Propagate {
+        value: Box::new(value),
+        line,
+    }
+}
+
+pub fn parse_member_expr(parser: &mut Parser, object: Expr) -> Expr {
+    parser.advance();
+    let property = parser.advance_and_get_current();
+    let property = match property.symbol {
+        Some(symbol) if property.ttype == TokenType::IDENTIFIER => symbol,
+        _ => {
+            parser.error(Diagnostic::new(
+                property.position,
+                property.line,
+                format!(
+                    "Expected a name after \".\" but found \"{}\"",
+                    property.lexeme
+                ),
+                "".to_string(),
+            ));
+            Symbol::intern(property.lexeme)
+        }
+    };
+
+    Expr::Member {
+        object: Box::new(object),
+        property,
+    }
+}
+
+///Expects the current token to be the identifier `word`, for the words
+///that only mean something in one place, like `as` and `from`
+fn expect_word(parser: &mut Parser, word: &str) {
+    let token = parser.advance_and_get_current();
+    if token.ttype != TokenType::IDENTIFIER || token.lexeme != word {
+        parser.error(Diagnostic::new(
+            token.position,
+            token.line,
+            format!("Expected \"{}\" but found \"{}\"", word, token.lexeme),
+            "".to_string(),
+        ));
+    }
+}
+
+///Parses a name, an identifier
+fn parse_name(parser: &mut Parser) -> Symbol {
+    let token = parser.advance_and_get_current();
+    match token.symbol {
+        Some(symbol) => symbol,
+        None => {
+            parser.error(Diagnostic::new(
+                token.position,
+                token.line,
+                format!("Expected a name but found \"{}\"", token.lexeme),
+                "".to_string(),
+            ));
+            Symbol::intern(token.lexeme)
+        }
+    }
+}
+
+///Parses `import "path" as name;` or `import { a, b } from "path";`
+pub fn parse_import_stmt(parser: &mut Parser) -> Stmt {
+    let line = parser.advance_and_get_current().line;
+
+    let mut names = None;
+    if parser.at().ttype == TokenType::LEFTBRACE {
+        parser.advance();
+        let mut list = Vec::new();
+        while !parser.is_eof() && parser.at().ttype != TokenType::RIGHTBRACE {
+            list.push(parse_name(parser));
+            if parser.at().ttype != TokenType::RIGHTBRACE {
+                parser.expect(TokenType::COMMA, ',');
+            }
+        }
+        parser.expect(TokenType::RIGHTBRACE, '}');
+        expect_word(parser, "from");
+        names = Some(list);
+    }
+
+    let path = parser.advance_and_get_current();
+    if path.ttype != TokenType::STRING {
+        parser.error(Diagnostic::new(
+            path.position,
+            path.line,
+            format!(
+                "Expected the path of a module but found \"{}\"",
+                path.lexeme
+            ),
+            "Module paths are strings: import \"util.ndl\" as util;".to_string(),
+        ));
+    }
+
+    let import = match names {
+        Some(names) => Import::Names(names),
+        None => {
+            expect_word(parser, "as");
+            Import::Module(parse_name(parser))
+        }
+    };
+    parser.expect(TokenType::SEMICOLON, ';');
+
+    Stmt::ImportStmt {
+        path: path.lexeme.to_string(),
+        import,
+        line,
+    }
+}
+
+///Parses `export` followed by the declaration it makes visible to
+///importers
+pub fn parse_export_stmt(parser: &mut Parser) -> Stmt {
+    let token = parser.advance_and_get_current();
+    let declaration = parser.parse_stmt();
+    if !matches!(
+        declaration,
+        Stmt::VarDeclarationStmt { .. } | Stmt::EnumDeclStmt { .. }
+    ) {
+        parser.error(Diagnostic::new(
+            token.position,
+            token.line,
+            "Only declarations can be exported".to_string(),
+            "Export a variable or an enum: export let name = ...;".to_string(),
+        ));
+    }
+
+    Stmt::ExportStmt {
+        declaration: Box::new(declaration),
+        line: token.line,
+    }
+}
+
+///Parses `match value { pattern => result, ... }` and checks that its
+///arms are exhaustive and all reachable
+pub fn parse_match_expr(parser: &mut Parser) -> Expr {
+    let token = parser.advance_and_get_current();
+    let value = parser.parse_expr(PREC::DefaultBp);
+    parser.expect(TokenType::LEFTBRACE, '{');
+
+    let mut arms = Vec::new();
+    while !parser.is_eof() && parser.at().ttype != TokenType::RIGHTBRACE {
+        let start = parser.at().clone();
+        let pattern = parse_pattern(parser);
+        let guard = if parser.at().ttype == TokenType::IF {
+            parser.advance();
+            Some(parser.parse_expr(PREC::DefaultBp))
+        } else {
+            None
+        };
+        if parser.at().ttype == TokenType::FATARROW {
+            parser.advance();
+        } else {
+            let found = parser.at().clone();
+            parser.error(Diagnostic::new(
+                found.position,
+                found.line,
+                format!("Expected \"=>\" but found \"{}\"", found.lexeme),
+                "Match arms are written pattern => result".to_string(),
+            ));
+        }
+        let body = parser.parse_expr(PREC::DefaultBp);
+        arms.push(MatchArm {
+            pattern,
+            guard,
+            body,
+            line: start.line,
+            position: start.position,
+        });
+        if parser.at().ttype != TokenType::RIGHTBRACE {
+            parser.expect(TokenType::COMMA, ',');
+        }
+    }
+    let end_line = parser.at().line;
+    parser.expect(TokenType::RIGHTBRACE, '}');
+
+    for diagnostic in exhaustiveness::check(&arms, parser.enums(), token.line, token.position) {
+        parser.error(diagnostic);
+    }
+
+    Expr::Match {
+        value: Box::new(value),
+        arms,
+        line: token.line,
+        end_line,
+    }
+}
+
+///Parses the pattern of a match arm
+fn parse_pattern(parser: &mut Parser) -> Pattern {
+    let token = parser.at().clone();
+    match token.ttype {
+        TokenType::IDENTIFIER => {
+            parser.advance();
+            let name = token.symbol.unwrap_or_else(|| Symbol::intern(token.lexeme));
+            if token.lexeme == "_" {
+                Pattern::Wildcard
+            } else if parser.at().ttype == TokenType::DOT {
+                parser.advance();
+                let variant = parser.advance_and_get_current();
+                parse_variant_pattern(parser, name, &variant)
+            } else if let Some((enum_type, _)) = builtin_variant(name) {
+                // `Ok(x)`, `Err(e)`, `Some(x)` and `None`
+                parse_variant_pattern(parser, enum_type.name, &token)
+            } else {
+                Pattern::Binding(name)
+            }
+        }
+        TokenType::LEFTBRACKET => {
+            parser.advance();
+            parse_list_pattern(parser, TokenType::RIGHTBRACKET, ']')
+        }
+        // `(a, b)` is a list pattern too, while `(a)` is just `a`
+        TokenType::LEFTPAREN => {
+            parser.advance();
+            match parse_list_pattern(parser, TokenType::RIGHTPAREN, ')') {
+                Pattern::List { mut items, rest } if items.len() == 1 && rest.is_none() => {
+                    items.remove(0)
+                }
+                pattern => pattern,
+            }
+        }
+        _ => {
+            let start = parse_pattern_literal(parser);
+            let inclusive = match parser.at().ttype {
+                TokenType::DOTDOT => false,
+                TokenType::DOTDOTEQUAL => true,
+                _ => return Pattern::Literal(start),
+            };
+            parser.advance();
+            let end = parse_pattern_literal(parser);
+            let valid = match (&start, &end) {
+                (Expr::String(_), Expr::String(_)) => true,
+                (start, end) => is_number(start) && is_number(end),
+            };
+            if !valid {
+                parser.error(Diagnostic::new(
+                    token.position,
+                    token.line,
+                    format!("Invalid range pattern \"{}\"", token.lexeme),
+                    "Ranges go between two numbers or two strings: 1..10, \"a\"..=\"z\""
+                        .to_string(),
+                ));
+            }
+            Pattern::Range {
+                start,
+                end,
+                inclusive,
+            }
+        }
+    }
+}
+
+///Checks whether a literal pattern is a number
+fn is_number(expr: &Expr) -> bool {
+    matches!(expr, Expr::Number(_) | Expr::Unary { .. })
+}
+
+///Parses the constant of a literal or range pattern: a number, possibly
+///negative, a string, `true`, `false` or `null`
+fn parse_pattern_literal(parser: &mut Parser) -> Expr {
+    let token = parser.advance_and_get_current();
+    match token.ttype {
+        TokenType::NUMBER => Expr::Number(token.lexeme.to_string()),
+        TokenType::STRING => Expr::String(token.lexeme.to_string()),
+        TokenType::TRUE => Expr::Bool(true),
+        TokenType::FALSE => Expr::Bool(false),
+        TokenType::NIL => Expr::Null,
+        TokenType::MINUS if parser.at().ttype == TokenType::NUMBER => {
+            let number = parser.advance_and_get_current();
+            Expr::Unary {
+                op: token.to_static(),
+                right: Box::new(Expr::Number(number.lexeme.to_string())),
+            }
+        }
+        _ => {
+            parser.error(Diagnostic::new(
+                token.position,
+                token.line,
+                format!("Expected a pattern but found \"{}\"", token.lexeme),
+                "Patterns are constants, ranges, names, _ or lists of patterns".to_string(),
+            ));
+            Expr::Null
+        }
+    }
+}
+
+///Parses the patterns of a list pattern up to `close`. Only the last one
+///may be a rest pattern, `..` or `..name`.
+fn parse_list_pattern(parser: &mut Parser, close: TokenType, close_char: char) -> Pattern {
+    let mut items = Vec::new();
+    let mut rest = None;
+    while !parser.is_eof() && parser.at().ttype != close {
+        if parser.at().ttype == TokenType::DOTDOT {
+            let token = parser.advance_and_get_current();
+            let binding = match parser.at().ttype {
+                TokenType::IDENTIFIER => {
+                    let name = parser.advance_and_get_current();
+                    name.symbol.filter(|_| name.lexeme != "_")
+                }
+                _ => None,
+            };
+            if rest.is_some() {
+                parser.error(Diagnostic::new(
+                    token.position,
+                    token.line,
+                    "A list pattern can only have one rest pattern".to_string(),
+                    "".to_string(),
+                ));
+            }
+            rest = Some(Rest { binding });
+        } else {
+            let pattern = parse_pattern(parser);
+            if rest.is_some() {
+                let token = parser.at().clone();
+                parser.error(Diagnostic::new(
+                    token.position,
+                    token.line,
+                    "The rest pattern must come last in a list pattern".to_string(),
+                    "Match the first items instead: [first, ..rest]".to_string(),
+                ));
+            }
+            items.push(pattern);
+        }
+        if parser.at().ttype != close {
+            parser.expect(TokenType::COMMA, ',');
+        }
+    }
+    parser.expect(close, close_char);
+
+    Pattern::List { items, rest }
+}
+
+///Parses the fields of `Enum.Variant(patterns)` once the variant name
+///`token` is consumed, checking them against the declaration of the enum
+///if there is one
+fn parse_variant_pattern(parser: &mut Parser, enum_name: Symbol, token: &Token) -> Pattern {
+    let variant = token.symbol.unwrap_or_else(|| Symbol::intern(token.lexeme));
+
+    let mut fields = Vec::new();
+    if parser.at().ttype == TokenType::LEFTPAREN {
+        parser.advance();
+        while !parser.is_eof() && parser.at().ttype != TokenType::RIGHTPAREN {
+            fields.push(parse_pattern(parser));
+            if parser.at().ttype != TokenType::RIGHTPAREN {
+                parser.expect(TokenType::COMMA, ',');
+            }
+        }
+        parser.expect(TokenType::RIGHTPAREN, ')');
+    }
+
+    let declared = parser.enums().get(&enum_name).map(|variants| {
+        variants
+            .iter()
+            .find(|declared| declared.name == variant)
+            .map(|declared| declared.fields.len())
+    });
+    let message = match declared {
+        Some(None) => Some(format!(
+            "Enum \"{}\" has no variant \"{}\"",
+            enum_name, variant
+        )),
+        Some(Some(count)) if count != fields.len() => Some(format!(
+            "\"{}.{}\" has {} field{} but the pattern has {}",
+            enum_name,
+            variant,
+            count,
+            if count == 1 { "" } else { "s" },
+            fields.len()
+        )),
+        _ => None,
+    };
+    if let Some(message) = message {
+        parser.error(Diagnostic::new(
+            token.position,
+            token.line,
+            message,
+            "".to_string(),
+        ));
+    }
+
+    Pattern::Variant {
+        enum_name,
+        variant,
+        fields,
+    }
+}
+
+///Parses `enum Name { Variant(field, ...), ... }`
+pub fn parse_enum_stmt(parser: &mut Parser) -> Stmt {
+    let line = parser.advance_and_get_current().line;
+    let name = parse_name(parser);
+    parser.expect(TokenType::LEFTBRACE, '{');
+
+    let mut variants: Vec<Variant> = Vec::new();
+    while !parser.is_eof() && parser.at().ttype != TokenType::RIGHTBRACE {
+        let token = parser.at().clone();
+        let variant = parse_name(parser);
+        if variants.iter().any(|declared| declared.name == variant) {
+            parser.error(Diagnostic::new(
+                token.position,
+                token.line,
+                format!("Enum \"{}\" already has a variant \"{}\"", name, variant),
+                "".to_string(),
+            ));
+        }
+
+        let mut fields = Vec::new();
+        if parser.at().ttype == TokenType::LEFTPAREN {
+            parser.advance();
+            while !parser.is_eof() && parser.at().ttype != TokenType::RIGHTPAREN {
+                fields.push(parse_name(parser));
+                if parser.at().ttype != TokenType::RIGHTPAREN {
+                    parser.expect(TokenType::COMMA, ',');
+                }
+            }
+            parser.expect(TokenType::RIGHTPAREN, ')');
+        }
+        variants.push(Variant {
+            name: variant,
+            fields,
+            line: token.line,
+        });
+
+        if parser.at().ttype != TokenType::RIGHTBRACE {
+            parser.expect(TokenType::COMMA, ',');
+        }
+    }
+    let end_line = parser.at().line;
+    parser.expect(TokenType::RIGHTBRACE, '}');
+
+    let variants = Rc::new(variants);
+    parser.declare_enum(name, variants.clone());
+    Stmt::EnumDeclStmt {
+        name,
+        variants,
+        line,
+        end_line,
+    }
+}
+
+///Parses the block following `keyword` in a `try` statement
+fn parse_try_block(parser: &mut Parser, keyword: &str) -> Stmt {
+    if parser.at().ttype != TokenType::LEFTBRACE {
+        let token = parser.at().clone();
+        parser.error(Diagnostic::new(
+            token.position,
+            token.line,
+            format!(
+                "Expected a block after \"{}\" but found \"{}\"",
+                keyword, token.lexeme
+            ),
+            "".to_string(),
+        ));
+        return Stmt::BlockStmt {
+            body: Vec::new(),
+            line: token.line,
+            end_line: token.line,
+        };
+    }
+    parse_block_stmt(parser)
+}
+
+///Parses `try { ... } catch (e) { ... } finally { ... }`
+pub fn parse_try_stmt(parser: &mut Parser) -> Stmt {
+    let token = parser.advance_and_get_current();
+    let body = parse_try_block(parser, "try");
+
+    let catch = if parser.at().ttype == TokenType::CATCH {
+        parser.advance();
+        let name = if parser.at().ttype == TokenType::LEFTPAREN {
+            parser.advance();
+            let name = parse_name(parser);
+            parser.expect(TokenType::RIGHTPAREN, ')');
+            Some(name)
+        } else {
+            None
+        };
+        Some(Catch {
+            name,
+            body: Box::new(parse_try_block(parser, "catch")),
+        })
+    } else {
+        None
+    };
+
+    let finally = if parser.at().ttype == TokenType::FINALLY {
+        parser.advance();
+        Some(Box::new(parse_try_block(parser, "finally")))
+    } else {
+        None
+    };
+
+    if catch.is_none() && finally.is_none() {
+        parser.error(Diagnostic::new(
+            token.position,
+            token.line,
+            "A \"try\" needs a \"catch\" or a \"finally\"".to_string(),
+            "Handle the error: try { ... } catch (error) { ... }".to_string(),
+        ));
+    }
+
+    Stmt::TryStmt {
+        body: Box::new(body),
+        catch,
+        finally,
+        line: token.line,
+    }
+}
+
+pub fn parse_throw_stmt(parser: &mut Parser) -> Stmt {
+    let line = parser.advance_and_get_current().line;
+    let value = parser.parse_expr(PREC::DefaultBp);
+    parser.expect(TokenType::SEMICOLON, ';');
+
+    Stmt::ThrowStmt { value, line }
+}
+
+///Parses `test "name" { ... }`. `test` is only a keyword there, so it
+///can still name variables.
+pub fn parse_test_stmt(parser: &mut Parser) -> Stmt {
+    let line = parser.advance_and_get_current().line;
+    let name = parser.advance_and_get_current().lexeme.to_string();
+    let body = parse_try_block(parser, "test");
+
+    Stmt::TestStmt {
+        name,
+        body: Box::new(body),
+        line,
     }
 }
@@ -1,136 +1,356 @@
 use std::collections::HashMap;
 
-use once_cell::sync::Lazy;
-
 use crate::{
     ast::{expr::Expr, stmt::Stmt},
-    tokens::token_type::TokenType,
+    tokens::{symbol::Symbol, token::Token, token_type::TokenType},
 };
 
 use super::{helpers::*, parser::Parser};
 
-#[derive(PartialEq, PartialOrd, Clone, Copy)]
+/// Binding powers, lowest first. Programs declare operators with a
+/// precedence level, and level `n` binds with power `2 * n`. The odd
+/// powers in between hold levels added without renumbering the ones
+/// programs already use, like `&&` between `||` and the comparisons.
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
 pub enum PREC {
     DefaultBp = 0,
-    Primary = 1,
+    Primary = 2,
     // Comma,
-    Assignment = 3,
-    Logical = 4,
-    Relational = 5,
-    Additive = 6,
-    Multiplicative = 7,
-    Power = 8,
-    Unary = 9,
-    Prefix = 10,
-    // Call,
-    // Member,
-    Group = 12,
+    Assignment = 6,
+    Logical = 8,
+    And = 9,
+    Relational = 10,
+    Additive = 12,
+    Multiplicative = 14,
+    Power = 16,
+    Unary = 18,
+    Prefix = 20,
+    Call = 22,
+    Member = 24,
+    Group = 26,
 }
 
-pub static mut BP_TABLE: Lazy<HashMap<TokenType, PREC>> = Lazy::new(|| {
-    let mut map = HashMap::new();
-
-    //Assignment
-    map.insert(TokenType::EQUAL, PREC::Assignment);
-    map.insert(TokenType::PLUSEQUALS, PREC::Assignment);
-    map.insert(TokenType::MINUSEQUALS, PREC::Assignment);
-
-    //Primary
-    map.insert(TokenType::NUMBER, PREC::Primary);
-    map.insert(TokenType::STRING, PREC::Primary);
-    map.insert(TokenType::IDENTIFIER, PREC::Primary);
+impl PREC {
+    ///Returns the level programs declare operators of this precedence with
+    pub fn level(self) -> u8 {
+        self as u8 / 2
+    }
 
-    //Additive
-    map.insert(TokenType::PLUS, PREC::Additive);
-    map.insert(TokenType::MINUS, PREC::Additive);
+    ///Returns the binding power of a declared precedence level
+    pub fn level_bp(level: u8) -> u8 {
+        level * 2
+    }
+}
 
-    //Multiplicative
-    map.insert(TokenType::STAR, PREC::Multiplicative);
-    map.insert(TokenType::SLASH, PREC::Multiplicative);
-    map.insert(TokenType::MODULO, PREC::Multiplicative);
+impl From<PREC> for u8 {
+    fn from(prec: PREC) -> Self {
+        prec as u8
+    }
+}
 
-    //Relational
-    map.insert(TokenType::LESS, PREC::Relational);
-    map.insert(TokenType::GREATER, PREC::Relational);
-    map.insert(TokenType::LESSEQUAL, PREC::Relational);
-    map.insert(TokenType::GREATEREQUAL, PREC::Relational);
+/// Which side an infix operator groups to when it is chained with
+/// itself: `a - b - c` is `(a - b) - c`, `a ^ b ^ c` is `a ^ (b ^ c)`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Assoc {
+    Left,
+    Right,
+}
 
-    //Logical
-    map.insert(TokenType::OR, PREC::Logical);
+/// How a declared operator is used: between two operands or in front of
+/// one, with the given binding power.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Fixity {
+    Infix { bp: u8, assoc: Assoc },
+    Prefix { bp: u8 },
+}
 
-    //Power
-    map.insert(TokenType::POW, PREC::Power);
+impl Fixity {
+    ///Returns the name a declared operator's function is bound to
+    pub fn binding_name(&self, operator: &str) -> Symbol {
+        operator_binding(self.keyword(), operator)
+    }
 
-    //Unary
-    map.insert(TokenType::BANG, PREC::Unary);
+    ///Returns the head of the declaration of `operator`, everything up
+    ///to the `=`
+    pub fn declaration(&self, operator: &str) -> String {
+        match self {
+            Fixity::Infix { bp, assoc } => format!(
+                "infix {} {} {}",
+                operator,
+                bp / 2,
+                if *assoc == Assoc::Left {
+                    "left"
+                } else {
+                    "right"
+                }
+            ),
+            Fixity::Prefix { bp } if *bp == PREC::Prefix as u8 => {
+                format!("prefix {}", operator)
+            }
+            Fixity::Prefix { bp } => format!("prefix {} {}", operator, bp / 2),
+        }
+    }
 
-    //Group
-    map.insert(TokenType::LEFTPAREN, PREC::Group);
+    fn keyword(&self) -> &'static str {
+        match self {
+            Fixity::Infix { .. } => "infix",
+            Fixity::Prefix { .. } => "prefix",
+        }
+    }
+}
 
-    //Default
-    map.insert(TokenType::EOF, PREC::DefaultBp);
-    map
-});
+///Returns the name the function of `infix`/`prefix` operator `operator`
+///is bound to. The names are not valid identifiers, so they cannot clash
+///with one.
+pub fn operator_binding(keyword: &str, operator: &str) -> Symbol {
+    Symbol::intern(&format!("{} {}", keyword, operator))
+}
 
 pub type LedHandler = fn(&mut Parser, Expr) -> Expr;
 pub type NudHandler = fn(&mut Parser) -> Expr;
 pub type StmtHandler = fn(&mut Parser) -> Stmt;
 
-pub fn create_nud_lookups() -> HashMap<TokenType, NudHandler> {
-    let mut map = HashMap::new();
-
-    // Literals & Symbols
-    map.insert(TokenType::NUMBER, parse_num as NudHandler);
-    map.insert(TokenType::STRING, parse_string as NudHandler);
-    map.insert(TokenType::IDENTIFIER, parse_identifier as NudHandler);
+/// An operator that follows an expression, along with how tightly it
+/// binds and which way it groups.
+#[derive(Clone, Copy)]
+pub struct InfixOperator {
+    pub bp: u8,
+    pub assoc: Assoc,
+    pub handler: LedHandler,
+}
 
-    // Unary/Prefix
-    map.insert(TokenType::MINUS, parse_unary as NudHandler);
-    map.insert(TokenType::BANG, parse_unary as NudHandler);
+/// An operator declared with `prefix`, along with how tightly it binds
+/// its operand.
+#[derive(Clone, Copy)]
+pub struct PrefixOperator {
+    pub bp: u8,
+    pub handler: NudHandler,
+}
 
-    // Grouping Expr
-    map.insert(TokenType::LEFTPAREN, parse_grouping_expr as NudHandler);
-    map
+impl InfixOperator {
+    ///Returns the binding power to parse the right-hand side with
+    pub fn right_bp(&self) -> u8 {
+        match self.assoc {
+            Assoc::Left => self.bp,
+            // Let the right-hand side take the same operator again
+            Assoc::Right => self.bp.saturating_sub(1),
+        }
+    }
 }
 
-pub fn create_led_lookups() -> HashMap<TokenType, LedHandler> {
-    let mut map = HashMap::new();
-
-    //Assignment
-    map.insert(TokenType::EQUAL, parse_assignment_expr as LedHandler);
-    map.insert(TokenType::PLUSEQUALS, parse_assignment_expr as LedHandler);
-    map.insert(TokenType::MINUSEQUALS, parse_assignment_expr as LedHandler);
-
-    // Logical
-    map.insert(TokenType::AND, parse_binary_expr as LedHandler);
-    map.insert(TokenType::OR, parse_binary_expr as LedHandler);
-
-    // Relational
-    map.insert(TokenType::GREATER, parse_binary_expr as LedHandler);
-    map.insert(TokenType::LESS, parse_binary_expr as LedHandler);
-    map.insert(TokenType::LESSEQUAL, parse_binary_expr as LedHandler);
-    map.insert(TokenType::GREATEREQUAL, parse_binary_expr as LedHandler);
-
-    //Additive & Multiplicative
-    map.insert(TokenType::PLUS, parse_binary_expr as LedHandler);
-    map.insert(TokenType::MINUS, parse_binary_expr as LedHandler);
-    map.insert(TokenType::STAR, parse_binary_expr as LedHandler);
-    map.insert(TokenType::SLASH, parse_binary_expr as LedHandler);
-    map.insert(TokenType::MODULO, parse_binary_expr as LedHandler);
-    map.insert(TokenType::POW, parse_binary_expr as LedHandler);
-
-    map
+/// The grammar a `Parser` runs on: the handlers for tokens starting a
+/// statement or an expression (nud) and for tokens following one (led),
+/// with the binding power and associativity of each infix operator.
+///
+/// Every parser owns its table, so parsers with different grammars can
+/// coexist, and embedders can register extra operators before parsing.
+/// Operators declared by the program are all OPERATOR tokens, so they
+/// are looked up by their symbol instead of their token type.
+#[derive(Clone, Default)]
+pub struct OperatorTable {
+    nud: HashMap<TokenType, NudHandler>,
+    led: HashMap<TokenType, InfixOperator>,
+    stmt: HashMap<TokenType, StmtHandler>,
+    declared_nud: HashMap<Symbol, PrefixOperator>,
+    declared_led: HashMap<Symbol, InfixOperator>,
 }
 
-pub fn create_stmt_lookups() -> HashMap<TokenType, StmtHandler> {
-    let mut map = HashMap::new();
+impl OperatorTable {
+    ///Creates the table for the ND-Lang grammar
+    pub fn new() -> Self {
+        let mut table = Self::empty();
+
+        // Literals & Symbols
+        table.register_prefix(TokenType::NUMBER, parse_num);
+        table.register_prefix(TokenType::STRING, parse_string);
+        table.register_prefix(TokenType::IDENTIFIER, parse_identifier);
+        table.register_prefix(TokenType::TRUE, parse_literal);
+        table.register_prefix(TokenType::FALSE, parse_literal);
+        table.register_prefix(TokenType::NIL, parse_literal);
+
+        // Unary/Prefix
+        table.register_prefix(TokenType::MINUS, parse_unary);
+        table.register_prefix(TokenType::BANG, parse_unary);
+
+        // Grouping Expr
+        table.register_prefix(TokenType::LEFTPAREN, parse_grouping_expr);
+
+        // Functions
+        table.register_prefix(TokenType::FUNC, parse_function_expr);
+        table.register_infix(
+            TokenType::LEFTPAREN,
+            PREC::Call,
+            Assoc::Left,
+            parse_call_expr,
+        );
+
+        // Lists
+        table.register_prefix(TokenType::LEFTBRACKET, parse_list_expr);
+        table.register_infix(
+            TokenType::LEFTBRACKET,
+            PREC::Call,
+            Assoc::Left,
+            parse_index_expr,
+        );
+
+        // Modules and methods
+        table.register_infix(TokenType::DOT, PREC::Member, Assoc::Left, parse_member_expr);
+
+        // `value?`, for Result and Option
+        table.register_infix(
+            TokenType::QUESTION,
+            PREC::Call,
+            Assoc::Left,
+            parse_propagate_expr,
+        );
+
+        // Pattern matching
+        table.register_prefix(TokenType::MATCH, parse_match_expr);
+
+        //Assignment
+        for ttype in [
+            TokenType::EQUAL,
+            TokenType::PLUSEQUALS,
+            TokenType::MINUSEQUALS,
+        ] {
+            table.register_infix(ttype, PREC::Assignment, Assoc::Right, parse_assignment_expr);
+        }
+
+        // Logical, `&&` binding tighter than `||`
+        table.register_infix(TokenType::AND, PREC::And, Assoc::Left, parse_binary_expr);
+        table.register_infix(TokenType::OR, PREC::Logical, Assoc::Left, parse_binary_expr);
+
+        // Relational
+        for ttype in [
+            TokenType::GREATER,
+            TokenType::LESS,
+            TokenType::LESSEQUAL,
+            TokenType::GREATEREQUAL,
+            TokenType::EQUALEQUAL,
+            TokenType::BANGEQUAL,
+        ] {
+            table.register_infix(ttype, PREC::Relational, Assoc::Left, parse_binary_expr);
+        }
+
+        //Additive & Multiplicative
+        table.register_infix(
+            TokenType::PLUS,
+            PREC::Additive,
+            Assoc::Left,
+            parse_binary_expr,
+        );
+        table.register_infix(
+            TokenType::MINUS,
+            PREC::Additive,
+            Assoc::Left,
+            parse_binary_expr,
+        );
+        for ttype in [TokenType::STAR, TokenType::SLASH, TokenType::MODULO] {
+            table.register_infix(ttype, PREC::Multiplicative, Assoc::Left, parse_binary_expr);
+        }
+
+        //Power
+        table.register_infix(TokenType::POW, PREC::Power, Assoc::Right, parse_binary_expr);
+
+        // Statements
+        table.register_stmt(TokenType::LET, parse_var_decl_stmt);
+        table.register_stmt(TokenType::CONST, parse_var_decl_stmt);
+        table.register_stmt(TokenType::LEFTBRACE, parse_block_stmt);
+        table.register_stmt(TokenType::IF, parse_if_stmt);
+        table.register_stmt(TokenType::RETURN, parse_return_stmt);
+        table.register_stmt(TokenType::INFIX, parse_operator_decl_stmt);
+        table.register_stmt(TokenType::PREFIX, parse_operator_decl_stmt);
+        table.register_stmt(TokenType::IMPORT, parse_import_stmt);
+        table.register_stmt(TokenType::EXPORT, parse_export_stmt);
+        table.register_stmt(TokenType::ENUM, parse_enum_stmt);
+        table.register_stmt(TokenType::TRY, parse_try_stmt);
+        table.register_stmt(TokenType::THROW, parse_throw_stmt);
+
+        table
+    }
+
+    ///Creates a table without any operators or statements
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    ///Registers a token that starts an expression
+    pub fn register_prefix(&mut self, ttype: TokenType, handler: NudHandler) {
+        self.nud.insert(ttype, handler);
+    }
+
+    ///Registers a token that follows an expression
+    pub fn register_infix(
+        &mut self,
+        ttype: TokenType,
+        bp: impl Into<u8>,
+        assoc: Assoc,
+        handler: LedHandler,
+    ) {
+        let bp = bp.into();
+        self.led.insert(ttype, InfixOperator { bp, assoc, handler });
+    }
+
+    ///Registers a token that starts a statement
+    pub fn register_stmt(&mut self, ttype: TokenType, handler: StmtHandler) {
+        self.stmt.insert(ttype, handler);
+    }
+
+    ///Registers an operator declared by the program
+    pub fn register_operator(&mut self, operator: Symbol, fixity: Fixity) {
+        match fixity {
+            Fixity::Infix { bp, assoc } => {
+                let handler = parse_binary_expr;
+                self.declared_led
+                    .insert(operator, InfixOperator { bp, assoc, handler });
+            }
+            Fixity::Prefix { bp } => {
+                let handler = parse_unary;
+                self.declared_nud
+                    .insert(operator, PrefixOperator { bp, handler });
+            }
+        }
+    }
+
+    ///Returns the operators declared by the program
+    pub fn declared_operators(&self) -> impl Iterator<Item = Symbol> + '_ {
+        self.declared_led
+            .keys()
+            .chain(self.declared_nud.keys())
+            .copied()
+    }
+
+    pub fn prefix(&self, token: &Token) -> Option<NudHandler> {
+        match token.symbol {
+            Some(symbol) if token.ttype == TokenType::OPERATOR => self
+                .declared_nud
+                .get(&symbol)
+                .map(|operator| operator.handler),
+            _ => self.nud.get(&token.ttype).copied(),
+        }
+    }
 
-    // Populate the map
-    map.insert(TokenType::LET, parse_var_decl_stmt as StmtHandler);
-    //map.insert(TokenType::CONST, parse_var_decl_stmt as StmtHandler);
+    ///Returns how tightly the prefix operator `token` binds its operand
+    pub fn prefix_bp(&self, token: &Token) -> u8 {
+        match token.symbol {
+            Some(symbol) if token.ttype == TokenType::OPERATOR => self
+                .declared_nud
+                .get(&symbol)
+                .map_or(PREC::Prefix.into(), |operator| operator.bp),
+            _ => PREC::Prefix.into(),
+        }
+    }
 
-    map.insert(TokenType::LEFTBRACE, parse_block_stmt as StmtHandler);
-    map.insert(TokenType::IF, parse_if_stmt as StmtHandler);
+    pub fn infix(&self, token: &Token) -> Option<InfixOperator> {
+        match token.symbol {
+            Some(symbol) if token.ttype == TokenType::OPERATOR => {
+                self.declared_led.get(&symbol).copied()
+            }
+            _ => self.led.get(&token.ttype).copied(),
+        }
+    }
 
-    map
+    pub fn stmt(&self, ttype: &TokenType) -> Option<StmtHandler> {
+        self.stmt.get(ttype).copied()
+    }
 }
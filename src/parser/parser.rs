@@ -1,30 +1,140 @@
-use super::lookups::{
-    create_led_lookups, create_nud_lookups, create_stmt_lookups, LedHandler, NudHandler,
-    StmtHandler, BP_TABLE, PREC,
-};
+use super::helpers::parse_test_stmt;
+use super::lookups::{OperatorTable, StmtHandler, PREC};
+use crate::ast::cst::{CstBuilder, SyntaxKind, SyntaxNode};
 use crate::ast::expr::Expr;
-use crate::ast::stmt::Stmt;
-use crate::errors::error::Error;
+use crate::ast::stmt::{Stmt, Variant};
+use crate::errors::diagnostic::Diagnostic;
+use crate::interpreter::interpreter::{STACK_GROWTH, STACK_RED_ZONE};
+use crate::scanner::scanner::Scanner;
+use crate::stdlib::prelude::builtin_enums;
+use crate::tokens::symbol::Symbol;
 use crate::tokens::token::Token;
 use crate::tokens::token_type::TokenType;
-use std::collections::HashMap;
-
-pub struct Parser {
-    tokens: Vec<Token>,
-    current: usize,
-    nud_lookup: HashMap<TokenType, NudHandler>,
-    led_lookup: HashMap<TokenType, LedHandler>,
-    stmt_lookup: HashMap<TokenType, StmtHandler>,
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+pub struct Parser<'src> {
+    scanner: Scanner<'src>,
+    /// Tokens pulled from the scanner but not consumed yet. The front is
+    /// the current token; the buffer is never empty, since the scanner
+    /// ends with EOF and EOF is never consumed.
+    lookahead: VecDeque<Token<'src>>,
+    operators: OperatorTable,
+    cst: Option<CstBuilder<'src>>,
+    errors: Vec<Diagnostic>,
+    /// The enums declared so far, for checking the patterns that use them
+    enums: HashMap<Symbol, Rc<Vec<Variant>>>,
 }
 
-impl Parser {
-    pub fn new(tokens: Vec<Token>) -> Self {
-        Self {
-            tokens,
-            current: 0,
-            nud_lookup: create_nud_lookups(),
-            led_lookup: create_led_lookups(),
-            stmt_lookup: create_stmt_lookups(),
+impl<'src> Parser<'src> {
+    ///Creates a parser that pulls its tokens from the scanner as it goes
+    pub fn new(scanner: Scanner<'src>) -> Self {
+        Self::with_operators(scanner, OperatorTable::new())
+    }
+
+    ///Creates a parser running on the grammar described by `operators`.
+    ///The scanner is told about the operators declared in it.
+    pub fn with_operators(mut scanner: Scanner<'src>, operators: OperatorTable) -> Self {
+        for operator in operators.declared_operators().filter_map(Symbol::as_str) {
+            scanner.declare_operator(operator);
+        }
+        let mut parser = Self {
+            scanner,
+            lookahead: VecDeque::new(),
+            operators,
+            cst: None,
+            errors: Vec::new(),
+            enums: builtin_enums()
+                .into_iter()
+                .map(|enum_type| (enum_type.name, enum_type.variants.clone()))
+                .collect(),
+        };
+        parser.fill_lookahead(1);
+        parser
+    }
+
+    ///Creates a parser that also records a concrete syntax tree. The
+    ///scanner should be lossless so the tree keeps every character.
+    pub fn new_lossless(scanner: Scanner<'src>) -> Self {
+        let mut parser = Self::new(scanner);
+        parser.cst = Some(CstBuilder::new());
+        parser
+    }
+
+    ///Parses the tokens and returns the concrete syntax tree. Printing the
+    ///tree gives back the source the tokens were scanned from.
+    pub fn parse_cst(&mut self) -> SyntaxNode<'src> {
+        if self.cst.is_none() {
+            self.cst = Some(CstBuilder::new());
+        }
+        self.parse();
+
+        // EOF carries the trivia found after the last statement
+        let mut cst = self.cst.take().unwrap_or_default();
+        cst.push_token(self.at().clone());
+        cst.finish()
+    }
+
+    ///Returns the errors found while scanning and parsing
+    pub fn errors(&self) -> &Vec<Diagnostic> {
+        &self.errors
+    }
+
+    ///Records an error found by a parse handler
+    pub fn error(&mut self, diagnostic: Diagnostic) {
+        self.errors.push(diagnostic);
+    }
+
+    ///Records the variants of an enum declared in the source
+    pub fn declare_enum(&mut self, name: Symbol, variants: Rc<Vec<Variant>>) {
+        self.enums.insert(name, variants);
+    }
+
+    ///Returns the enums declared so far, by name
+    pub fn enums(&self) -> &HashMap<Symbol, Rc<Vec<Variant>>> {
+        &self.enums
+    }
+
+    ///Returns the grammar the parser runs on
+    pub fn operators(&self) -> &OperatorTable {
+        &self.operators
+    }
+
+    ///Returns the grammar the parser runs on, to register operators
+    pub fn operators_mut(&mut self) -> &mut OperatorTable {
+        &mut self.operators
+    }
+
+    ///Returns the scanner the tokens are pulled from
+    pub fn scanner(&self) -> &Scanner<'src> {
+        &self.scanner
+    }
+
+    ///Pulls tokens from the scanner until `count` are buffered. Scanning
+    ///errors are recorded and skipped.
+    fn fill_lookahead(&mut self, count: usize) {
+        while self.lookahead.len() < count {
+            match self.scanner.next() {
+                Some(Ok(token)) => self.lookahead.push_back(token),
+                Some(Err(diagnostic)) => self.errors.push(diagnostic),
+                // The scanner is done once it returned EOF, which stays
+                // buffered, so this only happens for an empty scanner
+                None => self
+                    .lookahead
+                    .push_back(Token::new(TokenType::EOF, "", 0, 0)),
+            }
+        }
+    }
+
+    ///Returns a checkpoint in the syntax tree being recorded, if any
+    fn cst_checkpoint(&self) -> usize {
+        self.cst.as_ref().map_or(0, |cst| cst.checkpoint())
+    }
+
+    ///Groups what was consumed since `checkpoint` into a syntax tree node
+    fn cst_finish_node(&mut self, checkpoint: usize, kind: SyntaxKind) {
+        if let Some(cst) = self.cst.as_mut() {
+            cst.finish_node_at(checkpoint, kind);
         }
     }
 
@@ -41,63 +151,119 @@ impl Parser {
     ///Starts to parse the tokens inside our Parser
     ///
     /// #Example
-    /// ```rust
+    /// ```ignore
     /// let mut parser = Parser::new(tokens),
     /// ```
     pub fn parse_stmt(&mut self) -> Stmt {
-        let stmt_fn = self.stmt_lookup.get(&self.at().ttype);
+        let checkpoint = self.cst_checkpoint();
+        let stmt_fn = match self.at_test() {
+            true => Some(parse_test_stmt as StmtHandler),
+            false => self.operators.stmt(&self.at().ttype),
+        };
 
-        if stmt_fn.is_some() {
-            return stmt_fn.unwrap()(self);
+        let stmt = if let Some(stmt_fn) = stmt_fn {
+            stmt_fn(self)
         } else {
+            let line = self.at().line;
             let expression = self.parse_expr(PREC::DefaultBp);
 
             self.expect(TokenType::SEMICOLON, ';');
 
-            return Stmt::ExpressionStmt { expression };
-        }
+            Stmt::ExpressionStmt { expression, line }
+        };
+        self.cst_finish_node(checkpoint, SyntaxKind::from(&stmt));
+        stmt
     }
 
     ///Starts to parse the tokens inside our Parser
     pub fn parse_expr(&mut self, bp: PREC) -> Expr {
+        self.parse_expr_bp(bp.into())
+    }
+
+    ///Parses an expression, taking infix operators for as long as they
+    ///bind tighter than `bp`
+    pub fn parse_expr_bp(&mut self, bp: u8) -> Expr {
         let token = self.at().clone();
+        let checkpoint = self.cst_checkpoint();
 
-        let mut left = self.nud_lookup.get(&token.ttype).unwrap()(self);
-        while self.token_bp().map_or(false, |&next_bp| next_bp >= bp) {
-            left = self.led_lookup.get(&self.at().ttype).unwrap()(self, left);
+        let nud_fn = match self.operators.prefix(&token) {
+            Some(nud_fn) => nud_fn,
+            None => {
+                self.errors.push(Diagnostic::new(
+                    token.position,
+                    token.line,
+                    format!("Unexpected token \"{}\"", token.lexeme),
+                    "Expected an expression".to_string(),
+                ));
+                match token.ttype {
+                    // Leave the token that closes the statement or block
+                    // alone and stand in for the missing expression. The
+                    // error stops the program from ever running.
+                    TokenType::SEMICOLON | TokenType::RIGHTBRACE | TokenType::EOF => {
+                        return Expr::Identifier(Symbol::intern(token.lexeme));
+                    }
+                    // Otherwise skip the token and try again
+                    _ => {
+                        self.advance();
+                        return self.parse_expr_bp(bp);
+                    }
+                }
+            }
+        };
+        // Nested expressions recurse through the handlers, which would
+        // overflow the stack of the thread long before any sane limit
+        let mut left = stacker::maybe_grow(STACK_RED_ZONE, STACK_GROWTH, || nud_fn(self));
+        self.cst_finish_node(checkpoint, SyntaxKind::from(&left));
+        while let Some(operator) = self.operators.infix(self.at()) {
+            if operator.bp <= bp {
+                break;
+            }
+            left = stacker::maybe_grow(STACK_RED_ZONE, STACK_GROWTH, || {
+                (operator.handler)(self, left)
+            });
+            self.cst_finish_node(checkpoint, SyntaxKind::from(&left));
         }
         left
     }
 
-    ///Get the precedence of the current token
-    pub fn token_bp(&self) -> Option<&PREC> {
-        if self.current + 1 < self.tokens.len() {
-            let token_type = &self.tokens[self.current].ttype;
-            return unsafe { BP_TABLE.get(&token_type) };
+    ///Get the binding power of the current token, if it is an infix
+    ///operator
+    pub fn token_bp(&self) -> Option<u8> {
+        self.operators.infix(self.at()).map(|operator| operator.bp)
+    }
+
+    ///Checks whether a `test "name"` declaration starts here
+    fn at_test(&mut self) -> bool {
+        if self.at().ttype != TokenType::IDENTIFIER || self.at().lexeme != "test" {
+            return false;
         }
-        None
+        self.fill_lookahead(2);
+        self.lookahead[1].ttype == TokenType::STRING
     }
 
     ///Checks if we are at the end of the file
     pub fn is_eof(&self) -> bool {
-        self.current + 1 > self.tokens.len()
+        self.at().ttype == TokenType::EOF
     }
 
-    ///Advances the current position
+    ///Advances the current position. EOF is never consumed.
     pub fn advance(&mut self) {
         if !self.is_eof() {
-            self.current += 1;
+            let token = self.lookahead.pop_front();
+            if let (Some(cst), Some(token)) = (self.cst.as_mut(), token) {
+                cst.push_token(token);
+            }
+            self.fill_lookahead(1);
         }
-        self.current;
     }
 
     ///Returns the current token
-    pub fn at(&self) -> &Token {
-        &self.tokens[self.current]
+    pub fn at(&self) -> &Token<'src> {
+        &self.lookahead[0]
     }
 
     ///Returns the current token and advances
-    pub fn advance_and_get_current(&mut self) -> Token {
+    pub fn advance_and_get_current(&mut self) -> Token<'src> {
         let current = self.at().clone();
         self.advance();
         return current;
@@ -106,7 +272,7 @@ impl Parser {
     ///Expects the current token to be of the given type
     pub fn expect(&mut self, expected_type: TokenType, expected_char: char) {
         if self.at().ttype != expected_type {
-            let error = Error::new(
+            let error = Diagnostic::new(
                 self.at().position,
                 self.at().line,
                 format!(
@@ -117,8 +283,7 @@ impl Parser {
                 "".to_string(),
             );
 
-            error.report();
-            //std::process::exit(65);
+            self.errors.push(error);
         }
         self.advance();
     }
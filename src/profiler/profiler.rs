@@ -0,0 +1,256 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+/// The name the top level of a script is profiled under
+pub const SCRIPT: &str = "<script>";
+
+/// What the profiler measured of a function.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FunctionProfile {
+    pub name: String,
+    pub calls: usize,
+    /// The time spent in the function and in the functions it called.
+    /// Recursive calls are only counted once.
+    pub inclusive: Duration,
+    /// The time spent in the function itself
+    pub exclusive: Duration,
+}
+
+/// What the profiler measured of a line: how many statements started on
+/// it and the time spent running them, leaving out the calls they made.
+/// Lines count from 1, as in editors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineProfile {
+    pub source: Rc<str>,
+    pub line: usize,
+    pub hits: usize,
+    pub time: Duration,
+}
+
+/// A call being profiled.
+struct Call {
+    name: String,
+    started: Instant,
+    /// The time spent in the calls it made
+    children: Duration,
+    /// The line it was called from, which is timed again once it returns
+    caller: Option<(Rc<str>, usize)>,
+}
+
+/// Measures where a running program spends its time. The interpreter
+/// tells it about each statement it starts and each call it makes;
+/// the profiler times the lines and the functions from that.
+pub struct Profiler {
+    functions: HashMap<String, FunctionProfile>,
+    lines: HashMap<(Rc<str>, usize), LineProfile>,
+    /// The exclusive time of each call stack, by the names in it
+    /// outermost first
+    stacks: HashMap<Vec<String>, Duration>,
+    /// The calls being run, outermost first, starting with the script
+    calls: Vec<Call>,
+    /// The line being run and since when
+    line: Option<(Rc<str>, usize, Instant)>,
+}
+
+impl Profiler {
+    ///Starts profiling, timing the script from now on
+    pub fn new() -> Self {
+        Self {
+            functions: HashMap::new(),
+            lines: HashMap::new(),
+            stacks: HashMap::new(),
+            calls: vec![Call {
+                name: SCRIPT.to_string(),
+                started: Instant::now(),
+                children: Duration::ZERO,
+                caller: None,
+            }],
+            line: None,
+        }
+    }
+
+    ///Records that a statement on `line` of `source`, counting from 1,
+    ///starts running
+    pub fn statement(&mut self, source: &Rc<str>, line: usize) {
+        let now = Instant::now();
+        self.leave_line(now);
+        self.line = Some((source.clone(), line, now));
+        self.lines
+            .entry((source.clone(), line))
+            .or_insert_with(|| LineProfile {
+                source: source.clone(),
+                line,
+                hits: 0,
+                time: Duration::ZERO,
+            })
+            .hits += 1;
+    }
+
+    ///Records that the function `name` is called
+    pub fn enter(&mut self, name: &str) {
+        let now = Instant::now();
+        let caller = self.leave_line(now);
+        self.calls.push(Call {
+            name: name.to_string(),
+            started: now,
+            children: Duration::ZERO,
+            caller,
+        });
+    }
+
+    ///Records that the innermost call returned, or stopped on an error
+    pub fn exit(&mut self) {
+        // The script itself ends with `finish`
+        if self.calls.len() > 1 {
+            self.exit_at(Instant::now());
+        }
+    }
+
+    ///Stops timing, ending the calls still running. Nothing is recorded
+    ///after it.
+    pub fn finish(&mut self) {
+        let now = Instant::now();
+        while !self.calls.is_empty() {
+            self.exit_at(now);
+        }
+        self.leave_line(now);
+    }
+
+    fn exit_at(&mut self, now: Instant) {
+        self.leave_line(now);
+        let stack: Vec<String> = self.calls.iter().map(|call| call.name.clone()).collect();
+        let Some(call) = self.calls.pop() else {
+            return;
+        };
+        let inclusive = now.duration_since(call.started);
+        let exclusive = inclusive.saturating_sub(call.children);
+        if let Some(parent) = self.calls.last_mut() {
+            parent.children += inclusive;
+        }
+
+        let recursive = self.calls.iter().any(|outer| outer.name == call.name);
+        let function = self
+            .functions
+            .entry(call.name.clone())
+            .or_insert_with(|| FunctionProfile {
+                name: call.name.clone(),
+                ..Default::default()
+            });
+        function.calls += 1;
+        function.exclusive += exclusive;
+        if !recursive {
+            function.inclusive += inclusive;
+        }
+        *self.stacks.entry(stack).or_default() += exclusive;
+
+        // The caller's line goes on once the call returns
+        if let Some((source, line)) = call.caller {
+            self.line = Some((source, line, now));
+        }
+    }
+
+    ///Adds the time since the current line started to it, returning the
+    ///line
+    fn leave_line(&mut self, now: Instant) -> Option<(Rc<str>, usize)> {
+        let (source, line, started) = self.line.take()?;
+        if let Some(profile) = self.lines.get_mut(&(source.clone(), line)) {
+            profile.time += now.duration_since(started);
+        }
+        Some((source, line))
+    }
+
+    ///Returns the functions called, the script included, most exclusive
+    ///time first
+    pub fn functions(&self) -> Vec<FunctionProfile> {
+        let mut functions: Vec<FunctionProfile> = self.functions.values().cloned().collect();
+        functions.sort_by(|a, b| b.exclusive.cmp(&a.exclusive).then(a.name.cmp(&b.name)));
+        functions
+    }
+
+    ///Returns the lines run, most time first
+    pub fn lines(&self) -> Vec<LineProfile> {
+        let mut lines: Vec<LineProfile> = self.lines.values().cloned().collect();
+        lines.sort_by(|a, b| {
+            b.time
+                .cmp(&a.time)
+                .then(a.source.cmp(&b.source))
+                .then(a.line.cmp(&b.line))
+        });
+        lines
+    }
+
+    ///Returns the time the script ran for
+    pub fn total(&self) -> Duration {
+        self.functions
+            .get(SCRIPT)
+            .map(|script| script.inclusive)
+            .unwrap_or_default()
+    }
+
+    ///Describes the profile as tables of the functions and of the
+    ///`lines` slowest lines
+    pub fn report(&self, lines: usize) -> String {
+        let total = self.total().as_secs_f64().max(f64::EPSILON);
+        let mut report = String::new();
+        let _ = writeln!(report, "Ran for {}", milliseconds(self.total()));
+        let _ = writeln!(
+            report,
+            "\n{:>8} {:>12} {:>12} {:>7}  Function",
+            "Calls", "Inclusive", "Exclusive", "%"
+        );
+        for function in self.functions() {
+            let _ = writeln!(
+                report,
+                "{:>8} {:>12} {:>12} {:>6.1}%  {}",
+                function.calls,
+                milliseconds(function.inclusive),
+                milliseconds(function.exclusive),
+                100.0 * function.exclusive.as_secs_f64() / total,
+                function.name
+            );
+        }
+
+        let _ = writeln!(report, "\n{:>8} {:>12} {:>7}  Line", "Hits", "Time", "%");
+        for line in self.lines().into_iter().take(lines) {
+            let _ = writeln!(
+                report,
+                "{:>8} {:>12} {:>6.1}%  {}:{}",
+                line.hits,
+                milliseconds(line.time),
+                100.0 * line.time.as_secs_f64() / total,
+                line.source,
+                line.line
+            );
+        }
+        report
+    }
+
+    ///Describes the call stacks in the collapsed format flame graph
+    ///tools read: one `outer;inner;innermost microseconds` line per stack
+    pub fn collapsed(&self) -> String {
+        let mut stacks: Vec<(String, u128)> = self
+            .stacks
+            .iter()
+            .map(|(stack, time)| (stack.join(";"), time.as_micros()))
+            .collect();
+        stacks.sort();
+        stacks
+            .into_iter()
+            .map(|(stack, time)| format!("{} {}\n", stack, time))
+            .collect()
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn milliseconds(duration: Duration) -> String {
+    format!("{:.3}ms", duration.as_secs_f64() * 1000.0)
+}
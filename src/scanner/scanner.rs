@@ -1,16 +1,44 @@
 // use crate::tokens::token_type::TokenTy
 
 use crate::{
-    object::object::Object,
+    errors::diagnostic::Diagnostic,
     tokens::{
-        token::{Token, KEYWORDS},
+        symbol::Symbol,
+        token::{Comment, Token, Trivia, TriviaKind, KEYWORDS},
         token_type::TokenType,
     },
 };
 
-pub struct Scanner {
-    source: Vec<char>,
-    tokens: Vec<Token>,
+/// Characters that operators declared with `infix`/`prefix` are made of
+const OPERATOR_CHARS: &str = "+-*/%^!=<>&|~?@$#";
+
+/// Operators with a token type of their own, which cannot be redeclared
+const BUILTIN_OPERATORS: [&str; 20] = [
+    "+", "-", "*", "/", "%", "^", "!", "=", "==", "!=", "<", "<=", ">", ">=", "+=", "-=", "&&",
+    "||", "=>", "?",
+];
+
+/// Turns source code into tokens on demand. The scanner is an iterator:
+/// each call to `next` scans just enough of the source to produce one
+/// token (or a diagnostic for text that is not one), ending with a single
+/// EOF token.
+///
+/// The scanner works on the bytes of the source and never copies it:
+/// lexemes, trivia and comments are all slices of `source`. `position`
+/// and `current` are byte offsets, while `offset` counts characters.
+pub struct Scanner<'src> {
+    source: &'src str,
+    bytes: &'src [u8],
+    scanned: Option<Token<'src>>,
+    comments: Vec<Comment<'src>>,
+    lossless: bool,
+    pending_trivia: Vec<Trivia<'src>>,
+    last_token_line: Option<usize>,
+    /// Operators declared so far, longest first
+    operators: Vec<&'src str>,
+    /// Set after `infix`/`prefix`, when the next symbol is being declared
+    declaring_operator: bool,
+    done: bool,
     position: usize,
     offset: usize,
     line: usize,
@@ -18,11 +46,19 @@ pub struct Scanner {
     current: usize,
 }
 
-impl Scanner {
-    pub fn new(source: Vec<char>, file_name: String) -> Self {
+impl<'src> Scanner<'src> {
+    pub fn new(source: &'src str, file_name: String) -> Self {
         return Self {
             source,
-            tokens: Vec::new(),
+            bytes: source.as_bytes(),
+            scanned: None,
+            comments: Vec::new(),
+            lossless: false,
+            pending_trivia: Vec::new(),
+            last_token_line: None,
+            operators: Vec::new(),
+            declaring_operator: false,
+            done: false,
             position: 0,
             line: 0,
             offset: 0,
@@ -31,31 +67,25 @@ impl Scanner {
         };
     }
 
-    /// Scans the source code and generates a vector of tokens. It
-    /// keeps calling the scan_token function until the end of the
-    /// source code is reached. If an error is encountered while
-    /// scanning a token, it is reported and the function stops scanning.
-    /// At the end, an EOF token is added to the token vector, and the
-    /// function returns a reference to the vector of tokens, wrapped
-    /// in a Result indicating success or an error of type LexerError.
-    pub fn scan_tokens(&mut self) -> Result<&Vec<Token>, ()> {
-        while !self.is_eof() {
-            match self.scan_token() {
-                Ok(_) => {}
-                Err(err) => {
-                    break;
-                }
-            }
-        }
+    /// Creates a scanner in lossless mode: every token carries the
+    /// whitespace, newlines and comments around it as trivia, so the
+    /// token stream can be printed back to the exact source.
+    pub fn new_lossless(source: &'src str, file_name: String) -> Self {
+        let mut scanner = Self::new(source, file_name);
+        scanner.lossless = true;
+        scanner
+    }
 
-        self.tokens.push(Token::new(
-            TokenType::EOF,
-            "".to_string(),
-            Object::Nil,
-            self.line,
-            self.offset,
-        ));
-        Ok(&self.tokens)
+    /// Returns the name of the file being scanned, as shown in stack traces
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    /// Returns the `//` comments scanned so far, in source order.
+    /// Comments never reach the token stream, so tools that need to
+    /// reproduce them (such as the formatter) read them from here.
+    pub fn comments(&self) -> &Vec<Comment<'src>> {
+        &self.comments
     }
 
     /// Returns a boolean indicating whether the parser has reached
@@ -66,7 +96,7 @@ impl Scanner {
     ///  of the source code, it is considered to have reached the end
     /// and returns true, otherwise it returns false.    
     fn is_eof(&mut self) -> bool {
-        return self.position >= self.source.len();
+        return self.position >= self.bytes.len();
     }
 
     /// Returns the current character at the current position in the
@@ -75,22 +105,30 @@ impl Scanner {
     fn at(&mut self) -> char {
         if self.is_eof() {
             return ' ';
-        } else {
-            return self.source[self.position];
+        }
+        match self.bytes[self.position] {
+            byte if byte.is_ascii() => byte as char,
+            // Only characters outside ASCII need decoding
+            _ => self.source[self.position..].chars().next().unwrap_or(' '),
         }
     }
 
-    fn next(&mut self) -> Option<char> {
+    fn advance(&mut self) -> Option<char> {
         if self.is_eof() {
             return None;
         } else {
+            let char = self.at();
             self.offset += 1;
-            let char = self.source[self.position];
-            self.position += 1;
+            self.position += char.len_utf8();
             return Some(char);
         }
     }
 
+    /// Returns the text consumed since the start of the current token
+    fn lexeme(&self) -> &'src str {
+        &self.source[self.current..self.position]
+    }
+
     /// Adds a token of the specified type to the list of tokens.
     ///
     /// Retrieves the value for the token from the input stream using the `next` method,
@@ -102,27 +140,71 @@ impl Scanner {
     /// * `token_type` - The type of token to add.
     /// # Example
     ///
-    /// ```
+    /// ```ignore
     /// self.add_token(TokenType::SLASH);
     /// ```
     fn add_token(&mut self, token_type: TokenType) {
-        let value = self.next();
-        self.tokens.push(Token::new(
+        self.advance();
+        self.push_token(Token::new(
             token_type,
-            value.unwrap().to_string(),
-            Object::Nil,
+            self.lexeme(),
             self.line,
             self.offset,
         ))
     }
 
+    /// Hands a finished token over to `scan_token`.
+    fn push_token(&mut self, token: Token<'src>) {
+        self.scanned = Some(token);
+    }
+
+    /// Records the characters consumed since `current` as trivia when
+    /// running in lossless mode. Consecutive whitespace is merged.
+    fn add_trivia(&mut self, kind: TriviaKind) {
+        if !self.lossless {
+            return;
+        }
+        let text = self.lexeme();
+        match self.pending_trivia.last_mut() {
+            Some(last) if last.kind == TriviaKind::Whitespace && kind == TriviaKind::Whitespace => {
+                // The previous whitespace ends where this one starts
+                let start = self.current - last.text.len();
+                last.text = &self.source[start..self.position];
+            }
+            _ => self.pending_trivia.push(Trivia::new(kind, text)),
+        }
+    }
+
+    /// Consumes the trivia following a token on its line: whitespace and
+    /// a comment, up to and including the newline.
+    fn scan_trailing_trivia(&mut self) -> Vec<Trivia<'src>> {
+        let leading = std::mem::take(&mut self.pending_trivia);
+        while !self.is_eof() {
+            self.current = self.position;
+            match self.at() {
+                ' ' | '\t' | '\r' => {
+                    self.empty_next();
+                    self.add_trivia(TriviaKind::Whitespace);
+                }
+                '/' if self.peek('/') => self.check_for_comments(),
+                '\n' => {
+                    self.next_line();
+                    self.add_trivia(TriviaKind::Newline);
+                    break;
+                }
+                _ => break,
+            }
+        }
+        std::mem::replace(&mut self.pending_trivia, leading)
+    }
+
     /// Returns true if the next character in the source matches the
     /// specified character, false otherwise. If the end of the source
     /// is reached, returns false.
     ///
     /// # Example
     ///
-    /// ```
+    /// ```ignore
     /// self.peek('b');
     /// ```
     ///
@@ -134,9 +216,7 @@ impl Scanner {
     ///
     /// `true` if the next character in the source matches the specified character, `false` otherwise.
     fn peek(&mut self, c: char) -> bool {
-        !self.is_eof()
-            && self.position + 1 < self.source.len()
-            && self.source[self.position + 1] == c
+        self.peak_next() == c
     }
 
     /// Adds a token based on a conditional check on the next character in the source.
@@ -152,7 +232,7 @@ impl Scanner {
     ///
     /// # Example
     ///
-    /// ```
+    /// ```ignore
     /// self.add_conditional_token('=', TokenType::Equal, TokenType::Identifier);
     /// ```
     fn add_conditional_token(
@@ -162,29 +242,10 @@ impl Scanner {
         token_type_false: TokenType,
     ) {
         if self.peek(compare) {
-            let value = format!(
-                "{}{}",
-                self.next().unwrap_or('\0').to_string(),
-                self.next().unwrap_or('\0').to_string()
-            );
-            self.tokens.push(Token::new(
-                token_type_true,
-                value,
-                Object::Nil,
-                self.line,
-                self.offset,
-            ))
+            self.advance();
+            self.add_token(token_type_true);
         } else {
-            match self.next() {
-                Some(ch) => self.tokens.push(Token::new(
-                    token_type_false,
-                    ch.to_string(),
-                    Object::Nil,
-                    self.line,
-                    self.offset,
-                )),
-                None => (),
-            }
+            self.add_token(token_type_false);
         }
     }
 
@@ -194,14 +255,18 @@ impl Scanner {
         self.line += 1;
     }
 
+    /// Consumes a `//` comment up to (but not including) the end of the
+    /// line and records it, or adds a SLASH token otherwise. A comment is
+    /// marked as trailing when a token was already scanned on its line.
     fn check_for_comments(&mut self) {
         if self.peek('/') {
-            self.next();
-            self.next();
-            while !self.peek('\n') && !self.is_eof() {
-                self.next();
+            while !self.is_eof() && self.at() != '\n' {
+                self.advance();
             }
-            self.next_line();
+            let trailing = self.last_token_line == Some(self.line);
+            self.comments
+                .push(Comment::new(self.lexeme().trim_end(), self.line, trailing));
+            self.add_trivia(TriviaKind::Comment);
         } else {
             self.add_token(TokenType::SLASH);
         }
@@ -211,40 +276,38 @@ impl Scanner {
     ///
     /// Increments the `line` counter and resets the `offset` to 0.
     // TODO: Add check for new line
-    fn string(&mut self) -> Result<(), ()> {
-        self.next();
-        while !self.peek('"') && !self.is_eof() {
-            self.next();
-            if self.peek('\n') {
+    fn string(&mut self) -> Result<(), Diagnostic> {
+        self.advance();
+        while !self.is_eof() && self.at() != '"' {
+            if self.at() == '\n' {
                 self.next_line();
+            } else {
+                self.advance();
             }
         }
         if self.is_eof() {
-            // TODO: Add error
+            self.add_trivia(TriviaKind::Skipped);
+            return Err(Diagnostic::new(
+                self.offset,
+                self.line,
+                "Unterminated string".to_string(),
+                "Add a closing '\"' to the string".to_string(),
+            ));
         }
 
-        self.next();
-        self.next();
+        // The closing quote
+        self.advance();
 
-        let value: String = self.source[self.current + 1..self.position - 1]
-            .iter()
-            .collect();
-        self.add_string_token(Object::Str(value.clone()), TokenType::STRING, value);
+        // The lexeme of a string leaves out its quotes
+        let value = &self.source[self.current + 1..self.position - 1];
+        self.add_string_token(TokenType::STRING, value);
         Ok(())
     }
-    /// This function adds a new string token to the tokenizer state with
-    /// the provided object type, token type, and string value. It creates
-    /// a new Token instance and pushes it onto the tokens vector in the
-    /// tokenizer state, updating the line and current position information
-    /// as appropriate.
-    fn add_string_token(&mut self, object_type: Object, tok_type: TokenType, value: String) {
-        self.tokens.push(Token::new(
-            tok_type,
-            value,
-            object_type,
-            self.line,
-            self.offset,
-        ))
+    /// This function adds a new token to the tokenizer state with the
+    /// provided token type and lexeme, updating the line and current
+    /// position information as appropriate.
+    fn add_string_token(&mut self, tok_type: TokenType, value: &'src str) {
+        self.push_token(Token::new(tok_type, value, self.line, self.offset))
     }
 
     /// This function checks if a character is a digit (0-9) and returns a
@@ -257,13 +320,32 @@ impl Scanner {
     /// in the source string.If the end of the file is reached or if the
     /// next character is out of range, it returns the null character '\0'
     fn peak_next(&mut self) -> char {
-        if !self.is_eof() && self.position + 1 != self.source.len() {
-            self.source[self.position + 1]
-        } else {
-            '\0'
+        if self.is_eof() {
+            return '\0';
+        }
+        let next = self.position + self.at().len_utf8();
+        match self.bytes.get(next) {
+            Some(byte) if byte.is_ascii() => *byte as char,
+            Some(_) => self.source[next..].chars().next().unwrap_or('\0'),
+            None => '\0',
         }
     }
 
+    /// Returns the character after the one `peak_next` returns, or '\0'
+    /// past the end of the source
+    fn peak_after_next(&mut self) -> char {
+        if self.is_eof() {
+            return '\0';
+        }
+        let next = self.position + self.at().len_utf8();
+        let mut chars = match self.source.get(next..) {
+            Some(rest) => rest.chars(),
+            None => return '\0',
+        };
+        chars.next();
+        chars.next().unwrap_or('\0')
+    }
+
     /// Extracts a number token from the input and updates the
     /// tokenizer state accordingly. It scans the input for the end of the
     /// number, handling floating-point values if present. If the number
@@ -275,28 +357,25 @@ impl Scanner {
             let next = self.peak_next();
             self.is_digit(next)
         } {
-            self.next();
+            self.advance();
         }
 
-        //Look for floating point
-        if self.peek('.') {
-            self.next();
-            let next = self.peak_next();
-            if self.is_digit(next) {
-                self.next();
-                while {
-                    let next = self.peak_next();
-                    self.is_digit(next)
-                } {
-                    self.next();
-                }
-            } else {
-                //return invalid floating point
+        //Look for floating point. A dot not followed by a digit is left
+        //alone, so `1..5` scans as a range
+        if self.peek('.') && {
+            let after = self.peak_after_next();
+            self.is_digit(after)
+        } {
+            self.advance();
+            while {
+                let next = self.peak_next();
+                self.is_digit(next)
+            } {
+                self.advance();
             }
         }
 
-        self.next();
-        let value: String = self.source[self.current..self.position].iter().collect();
+        self.advance();
         // let number = match value.parse::<i32>() {
         //     Ok(num) => num,
         //     Err(_) => {
@@ -304,11 +383,7 @@ impl Scanner {
         //         //return invalid number error
         //     }
         // };
-        self.add_string_token(
-            Object::Num(value.to_string()),
-            TokenType::NUMBER,
-            self.source[self.current..self.position].iter().collect(),
-        );
+        self.add_string_token(TokenType::NUMBER, self.lexeme());
         Ok(())
     }
 
@@ -334,41 +409,79 @@ impl Scanner {
     /// the position until the next character is not an alphanumeric
     /// character. It then creates a new string from the slice of the
     /// source code between the current and next positions, and looks
-    /// up the corresponding TokenType using the KEYWORDS map. Finally,
-    /// it adds a new Token to the tokens vector using the add_string_token
-    /// helper function, with the appropriate TokenType and value fields.
+    /// up the corresponding TokenType using the KEYWORDS map. Identifiers
+    /// are interned, so the parser can work with their `Symbol`.
     fn identifier(&mut self) {
         while {
             let next = self.peak_next();
 
             self.is_alpha_numeric(next)
         } {
-            self.next();
+            self.advance();
         }
-        self.next();
+        self.advance();
 
-        let text: String = self.source[self.current..self.position].iter().collect();
-        let ttype = KEYWORDS
-            .get(&text)
-            .cloned()
-            .unwrap_or(TokenType::IDENTIFIER);
+        let text = self.lexeme();
+        let ttype = KEYWORDS.get(text).cloned().unwrap_or(TokenType::IDENTIFIER);
 
-        let bool_value = if text == "false" {
-            Some(false)
-        } else {
-            Some(true)
-        };
+        let mut token = Token::new(ttype, text, self.line, self.offset);
+        if token.ttype == TokenType::IDENTIFIER {
+            token.symbol = Some(Symbol::intern(text));
+        }
+        self.declaring_operator = matches!(token.ttype, TokenType::INFIX | TokenType::PREFIX);
+        self.push_token(token);
+    }
 
-        let object = match bool_value {
-            Some(bool_value) => Object::Bool(bool_value),
-            None => Object::Str(text.to_string()),
-        };
+    /// Scans the symbol following `infix`/`prefix` as an OPERATOR token
+    /// and remembers it, so that later uses scan as one token too.
+    /// Built-in operators are left to the regular rules.
+    fn operator_declaration(&mut self) -> bool {
+        let rest = &self.source[self.position..];
+        let length = rest
+            .find(|c| !OPERATOR_CHARS.contains(c))
+            .unwrap_or(rest.len());
+        let operator = &rest[..length];
+        if operator.is_empty() || operator.contains("//") || BUILTIN_OPERATORS.contains(&operator) {
+            return false;
+        }
+
+        self.declare_operator(operator);
+        self.add_operator_token(operator);
+        true
+    }
 
-        self.add_string_token(
-            object,
-            ttype,
-            self.source[self.current..self.position].iter().collect(),
-        );
+    /// Makes the scanner read `operator` as one OPERATOR token, for
+    /// operators declared before scanning started
+    pub fn declare_operator(&mut self, operator: &'src str) {
+        if !self.operators.contains(&operator) {
+            self.operators.push(operator);
+            self.operators
+                .sort_by_key(|operator| std::cmp::Reverse(operator.len()));
+        }
+    }
+
+    /// Scans a declared operator if one starts at the current position
+    fn declared_operator(&mut self) -> bool {
+        let rest = &self.source[self.position..];
+        match self
+            .operators
+            .iter()
+            .find(|operator| rest.starts_with(**operator))
+        {
+            Some(&operator) => {
+                self.add_operator_token(operator);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn add_operator_token(&mut self, operator: &'src str) {
+        self.position += operator.len();
+        self.offset += operator.chars().count();
+        let mut token = Token::new(TokenType::OPERATOR, operator, self.line, self.offset);
+        token.symbol = Some(Symbol::intern(operator));
+        self.push_token(token);
     }
 
     /// Increments the current position and offset of the scanner by 1,
@@ -376,49 +489,125 @@ impl Scanner {
     /// anything else. It is typically used to skip over whitespace or
     /// other non-significant characters.
     fn empty_next(&mut self) {
-        self.position += 1;
+        self.position += self.at().len_utf8();
         self.offset += 1;
     }
-    fn scan_token(&mut self) -> Result<(), ()> {
+    /// Scans the source until the next token, skipping whitespace and
+    /// comments. Returns `None` once the end of the source is reached.
+    fn scan_token(&mut self) -> Option<Result<Token<'src>, Diagnostic>> {
         while !self.is_eof() {
             let c = self.at();
             self.current = self.position;
 
+            if OPERATOR_CHARS.contains(c) && !(c == '/' && self.peek('/')) {
+                let declaring = std::mem::take(&mut self.declaring_operator);
+                if (declaring && self.operator_declaration()) || self.declared_operator() {
+                    return self.scanned.take().map(Ok);
+                }
+            } else if !c.is_whitespace() {
+                self.declaring_operator = false;
+            }
+
             match c {
-                '\n' => self.next_line(),
-                ' ' => self.empty_next(),
+                '\n' => {
+                    self.next_line();
+                    self.add_trivia(TriviaKind::Newline);
+                }
+                ' ' | '\t' | '\r' => {
+                    self.empty_next();
+                    self.add_trivia(TriviaKind::Whitespace);
+                }
                 '(' => self.add_token(TokenType::LEFTPAREN),
                 ')' => self.add_token(TokenType::RIGHTPAREN),
                 '{' => self.add_token(TokenType::LEFTBRACE),
                 '}' => self.add_token(TokenType::RIGHTBRACE),
+                '[' => self.add_token(TokenType::LEFTBRACKET),
+                ']' => self.add_token(TokenType::RIGHTBRACKET),
                 ':' => self.add_token(TokenType::COLON),
+                '?' => self.add_token(TokenType::QUESTION),
                 '+' => self.add_conditional_token('=', TokenType::PLUSEQUALS, TokenType::PLUS),
                 '-' => self.add_conditional_token('=', TokenType::MINUSEQUALS, TokenType::MINUS),
                 '*' => self.add_token(TokenType::STAR),
-                '.' => self.add_token(TokenType::DOT),
+                '.' => {
+                    if self.peek('.') {
+                        self.advance();
+                        self.add_conditional_token('=', TokenType::DOTDOTEQUAL, TokenType::DOTDOT);
+                    } else {
+                        self.add_token(TokenType::DOT);
+                    }
+                }
                 ',' => self.add_token(TokenType::COMMA),
                 '^' => self.add_token(TokenType::POW),
                 '%' => self.add_token(TokenType::MODULO),
                 ';' => self.add_token(TokenType::SEMICOLON),
                 '!' => self.add_conditional_token('=', TokenType::BANGEQUAL, TokenType::BANG),
-                '=' => self.add_conditional_token('=', TokenType::EQUALEQUAL, TokenType::EQUAL),
+                '=' => {
+                    if self.peek('>') {
+                        self.advance();
+                        self.add_token(TokenType::FATARROW);
+                    } else {
+                        self.add_conditional_token('=', TokenType::EQUALEQUAL, TokenType::EQUAL);
+                    }
+                }
                 '<' => self.add_conditional_token('=', TokenType::LESSEQUAL, TokenType::LESS),
                 '>' => self.add_conditional_token('=', TokenType::GREATEREQUAL, TokenType::GREATER),
                 '/' => self.check_for_comments(),
-                '"' => self.string()?,
+                '"' => {
+                    if let Err(diagnostic) = self.string() {
+                        return Some(Err(diagnostic));
+                    }
+                }
                 _ => {
                     if self.is_digit(c) {
                         let _ = self.number();
                     } else if self.is_alpha(c) {
                         self.identifier();
-                    } else if c == '\n' || c == '\r' || c == '\r' {
-                        self.empty_next();
                     } else {
-                        // TODO: Add error
+                        self.empty_next();
+                        self.add_trivia(TriviaKind::Skipped);
+                        return Some(Err(Diagnostic::new(
+                            self.offset,
+                            self.line,
+                            format!("Unexpected character '{}'", c),
+                            "".to_string(),
+                        )));
                     }
                 }
             }
+
+            if let Some(token) = self.scanned.take() {
+                return Some(Ok(token));
+            }
         }
-        Ok(())
+        None
+    }
+}
+
+impl<'src> Iterator for Scanner<'src> {
+    type Item = Result<Token<'src>, Diagnostic>;
+
+    /// Scans and returns the next token. Once the source is exhausted a
+    /// single EOF token is returned, carrying any trivia left at the end
+    /// of the file, after which the iterator is done.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let mut token = match self.scan_token() {
+            Some(Ok(token)) => token,
+            Some(Err(diagnostic)) => return Some(Err(diagnostic)),
+            None => {
+                self.done = true;
+                Token::new(TokenType::EOF, "", self.line, self.offset)
+            }
+        };
+
+        self.last_token_line = Some(token.line);
+        token.leading_trivia = std::mem::take(&mut self.pending_trivia);
+        if self.lossless && token.ttype != TokenType::EOF {
+            token.trailing_trivia = self.scan_trailing_trivia();
+        }
+        Some(Ok(token))
     }
 }
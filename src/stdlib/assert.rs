@@ -0,0 +1,164 @@
+use std::rc::Rc;
+
+use crate::{
+    interpreter::environment::Environment,
+    object::object::{NativeFunction, Object},
+    tokens::symbol::Symbol,
+};
+
+/// The assertions of the prelude. The interpreter runs them itself, as
+/// `assert_throws` calls back into the program and failures are errors
+/// of their own kind.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Assertion {
+    /// `assert(condition)` or `assert(condition, message)`
+    Assert,
+    /// `assert_eq(actual, expected)` or `assert_eq(actual, expected, message)`
+    AssertEq,
+    /// `assert_throws(function)` or `assert_throws(function, kind)`,
+    /// returning the error thrown
+    AssertThrows,
+}
+
+/// The kind of the errors failed assertions raise
+pub const ASSERTION_ERROR: &str = "AssertionError";
+
+impl Assertion {
+    pub fn name(self) -> &'static str {
+        match self {
+            Assertion::Assert => "assert",
+            Assertion::AssertEq => "assert_eq",
+            Assertion::AssertThrows => "assert_throws",
+        }
+    }
+
+    ///Returns the fewest and most arguments the assertion takes
+    pub fn arity(self) -> (usize, usize) {
+        match self {
+            Assertion::Assert | Assertion::AssertThrows => (1, 2),
+            Assertion::AssertEq => (2, 3),
+        }
+    }
+}
+
+thread_local! {
+    /// The function values of the assertions. They are told apart by
+    /// identity, so a program's own `assert` is never taken for one.
+    static ASSERTIONS: [(Assertion, Rc<NativeFunction>); 3] = [
+        Assertion::Assert,
+        Assertion::AssertEq,
+        Assertion::AssertThrows,
+    ]
+    .map(|assertion| {
+        let function = NativeFunction::new(assertion.name(), None, |_| {
+            Err("Assertions are run by the interpreter".to_string())
+        });
+        (assertion, Rc::new(function))
+    });
+}
+
+///Binds the assertions in the prelude
+pub fn define(prelude: &Environment) {
+    ASSERTIONS.with(|assertions| {
+        for (assertion, function) in assertions {
+            prelude.define(
+                Symbol::intern(assertion.name()),
+                Object::Native(function.clone()),
+                true,
+            );
+        }
+    });
+}
+
+///Returns the assertion a native function stands for, if it is one
+pub fn assertion(native: &Rc<NativeFunction>) -> Option<Assertion> {
+    ASSERTIONS.with(|assertions| {
+        assertions
+            .iter()
+            .find(|(_, function)| Rc::ptr_eq(function, native))
+            .map(|(assertion, _)| *assertion)
+    })
+}
+
+///Checks `assert(condition, message)`, returning why it failed
+pub fn check(args: &[Object]) -> Result<(), String> {
+    if args[0].is_truthy() {
+        return Ok(());
+    }
+    Err(with_message("Assertion failed", args.get(1)))
+}
+
+///Checks `assert_eq(actual, expected, message)`, returning why it failed
+pub fn check_eq(args: &[Object]) -> Result<(), String> {
+    let (actual, expected) = (&args[0], &args[1]);
+    if actual == expected {
+        return Ok(());
+    }
+    Err(format!(
+        "{}\n{}",
+        with_message("assert_eq failed", args.get(2)),
+        difference(expected, actual)
+    ))
+}
+
+fn with_message(failure: &str, message: Option<&Object>) -> String {
+    match message {
+        Some(message) => format!("{}: {}", failure, message),
+        None => failure.to_string(),
+    }
+}
+
+///Describes how `actual` differs from `expected`: both values, then the
+///items of lists or the lines of text that differ
+pub fn difference(expected: &Object, actual: &Object) -> String {
+    let mut lines = vec![
+        format!("  expected: {:?}", expected),
+        format!("    actual: {:?}", actual),
+    ];
+    match (expected, actual) {
+        (Object::List(expected), Object::List(actual)) => {
+            for (index, (expected, actual)) in expected.iter().zip(actual.iter()).enumerate() {
+                if expected != actual {
+                    lines.push(format!(
+                        "  at [{}]: expected {:?}, got {:?}",
+                        index, expected, actual
+                    ));
+                }
+            }
+            if expected.len() != actual.len() {
+                lines.push(format!(
+                    "  expected {} items, got {}",
+                    expected.len(),
+                    actual.len()
+                ));
+            }
+        }
+        (Object::Str(expected), Object::Str(actual))
+            if expected.contains('\n') || actual.contains('\n') =>
+        {
+            let (expected, actual): (Vec<&str>, Vec<&str>) =
+                (expected.lines().collect(), actual.lines().collect());
+            for line in 0..expected.len().max(actual.len()) {
+                let (old, new) = (expected.get(line), actual.get(line));
+                if old == new {
+                    continue;
+                }
+                if let Some(old) = old {
+                    lines.push(format!("  - line {}: {}", line + 1, old));
+                }
+                if let Some(new) = new {
+                    lines.push(format!("  + line {}: {}", line + 1, new));
+                }
+            }
+        }
+        (expected, actual) if expected.type_name() != actual.type_name() => {
+            lines.push(format!(
+                "  expected a {}, got a {}",
+                expected.type_name(),
+                actual.type_name()
+            ));
+        }
+        _ => {}
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,188 @@
+use std::{
+    cell::RefCell,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crate::object::object::{Module, Object};
+
+use super::helpers::{string, ModuleBuilder};
+
+/// The directories a script may use, in whole or in part.
+#[derive(Debug, Clone, Default)]
+pub struct Grant {
+    all: bool,
+    directories: Vec<PathBuf>,
+}
+
+impl Grant {
+    fn allows(&self, path: &Path) -> bool {
+        self.all
+            || self
+                .directories
+                .iter()
+                .any(|directory| path.starts_with(directory))
+    }
+}
+
+/// What the `fs` module may touch, and which module files scripts may
+/// import. Nothing is allowed until the host grants it, so untrusted
+/// scripts cannot read or change files.
+#[derive(Debug, Clone, Default)]
+pub struct Capabilities {
+    read: Grant,
+    write: Grant,
+}
+
+impl Capabilities {
+    ///Allows reading everything
+    pub fn allow_all_reads(&mut self) {
+        self.read.all = true;
+    }
+
+    ///Allows writing everywhere
+    pub fn allow_all_writes(&mut self) {
+        self.write.all = true;
+    }
+
+    ///Allows reading the files in `directory` and below
+    pub fn allow_read(&mut self, directory: impl AsRef<Path>) {
+        self.read.directories.push(absolute(directory.as_ref()));
+    }
+
+    ///Allows creating, changing and removing files in `directory` and
+    ///below
+    pub fn allow_write(&mut self, directory: impl AsRef<Path>) {
+        self.write.directories.push(absolute(directory.as_ref()));
+    }
+
+    pub fn can_read(&self, path: &Path) -> bool {
+        self.read.allows(&absolute(path))
+    }
+
+    pub fn can_write(&self, path: &Path) -> bool {
+        self.write.allows(&absolute(path))
+    }
+}
+
+///Returns the path with links, `.` and `..` resolved. The file itself may
+///not exist yet, as when it is about to be written.
+fn absolute(path: &Path) -> PathBuf {
+    if let Ok(path) = path.canonicalize() {
+        return path;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => {
+            let parent = if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            };
+            absolute(parent).join(name)
+        }
+        _ => std::env::current_dir()
+            .map(|directory| directory.join(path))
+            .unwrap_or_else(|_| path.to_path_buf()),
+    }
+}
+
+type Policy = Rc<RefCell<Capabilities>>;
+
+///Returns the first argument, a path the script must be allowed to read
+fn readable<'a>(policy: &Policy, function: &str, args: &'a [Object]) -> Result<&'a str, String> {
+    let path = string(function, args, 0)?;
+    if !policy.borrow().can_read(Path::new(path)) {
+        return Err(format!(
+            "{} is not allowed to read {:?} (allow it with --allow-read)",
+            function, path
+        ));
+    }
+    Ok(path)
+}
+
+///Returns the first argument, a path the script must be allowed to
+///write
+fn writable<'a>(policy: &Policy, function: &str, args: &'a [Object]) -> Result<&'a str, String> {
+    let path = string(function, args, 0)?;
+    if !policy.borrow().can_write(Path::new(path)) {
+        return Err(format!(
+            "{} is not allowed to write {:?} (allow it with --allow-write)",
+            function, path
+        ));
+    }
+    Ok(path)
+}
+
+fn io_error(function: &str, path: &str, error: std::io::Error) -> String {
+    format!("{} failed on {:?}: {}", function, path, error)
+}
+
+///Creates the `fs` module, whose functions check `policy` before touching
+///any file
+pub fn module(policy: Policy) -> Module {
+    let (read, write, append, exists, list, remove) = (
+        policy.clone(),
+        policy.clone(),
+        policy.clone(),
+        policy.clone(),
+        policy.clone(),
+        policy,
+    );
+    ModuleBuilder::new("fs")
+        .function("read_file", Some(1), move |args| {
+            let path = readable(&read, "fs.read_file", args)?;
+            fs::read_to_string(path)
+                .map(Object::Str)
+                .map_err(|error| io_error("fs.read_file", path, error))
+        })
+        .function("write_file", Some(2), move |args| {
+            let path = writable(&write, "fs.write_file", args)?;
+            let contents = string("fs.write_file", args, 1)?;
+            fs::write(path, contents)
+                .map(|_| Object::Nil)
+                .map_err(|error| io_error("fs.write_file", path, error))
+        })
+        .function("append_file", Some(2), move |args| {
+            let path = writable(&append, "fs.append_file", args)?;
+            let contents = string("fs.append_file", args, 1)?;
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| file.write_all(contents.as_bytes()))
+                .map(|_| Object::Nil)
+                .map_err(|error| io_error("fs.append_file", path, error))
+        })
+        .function("exists", Some(1), move |args| {
+            let path = readable(&exists, "fs.exists", args)?;
+            Ok(Object::Bool(Path::new(path).exists()))
+        })
+        .function("list_dir", Some(1), move |args| {
+            let path = readable(&list, "fs.list_dir", args)?;
+            let mut names = fs::read_dir(path)
+                .and_then(|entries| {
+                    entries
+                        .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+                        .collect::<std::io::Result<Vec<String>>>()
+                })
+                .map_err(|error| io_error("fs.list_dir", path, error))?;
+            names.sort();
+            Ok(Object::List(Rc::new(
+                names.into_iter().map(Object::Str).collect(),
+            )))
+        })
+        .function("remove", Some(1), move |args| {
+            let path = writable(&remove, "fs.remove", args)?;
+            let removed = if Path::new(path).is_dir() {
+                fs::remove_dir_all(path)
+            } else {
+                fs::remove_file(path)
+            };
+            removed
+                .map(|_| Object::Nil)
+                .map_err(|error| io_error("fs.remove", path, error))
+        })
+        .build()
+}
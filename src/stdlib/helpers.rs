@@ -0,0 +1,131 @@
+use std::{collections::HashMap, path::PathBuf, rc::Rc};
+
+use crate::{
+    object::object::{Module, NativeFunction, Object},
+    tokens::symbol::Symbol,
+};
+
+/// Builds a built-in module out of native functions and constants.
+pub struct ModuleBuilder {
+    name: &'static str,
+    exports: HashMap<Symbol, Object>,
+}
+
+impl ModuleBuilder {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            exports: HashMap::new(),
+        }
+    }
+
+    ///Exports a native function, named `<module>.<name>` in messages
+    pub fn function(
+        mut self,
+        name: &str,
+        arity: Option<usize>,
+        function: impl Fn(&[Object]) -> Result<Object, String> + 'static,
+    ) -> Self {
+        let qualified = format!("{}.{}", self.name, name);
+        let function = NativeFunction::new(&qualified, arity, function);
+        self.exports
+            .insert(Symbol::intern(name), Object::Native(Rc::new(function)));
+        self
+    }
+
+    ///Exports a value
+    pub fn constant(mut self, name: &str, value: Object) -> Self {
+        self.exports.insert(Symbol::intern(name), value);
+        self
+    }
+
+    pub fn build(self) -> Module {
+        Module {
+            path: PathBuf::from(self.name),
+            exports: self.exports,
+        }
+    }
+}
+
+///Returns argument `index` of a native function, which must be a number
+pub fn number(function: &str, args: &[Object], index: usize) -> Result<f64, String> {
+    match args.get(index) {
+        Some(Object::Num(n)) => Ok(*n),
+        Some(value) => Err(format!(
+            "{} expects a number as argument {}, not {}",
+            function,
+            index + 1,
+            value.type_name()
+        )),
+        None => Err(format!(
+            "{} expects at least {} arguments",
+            function,
+            index + 1
+        )),
+    }
+}
+
+///Returns argument `index` of a native function, which must be a whole
+///number
+pub fn integer(function: &str, args: &[Object], index: usize) -> Result<i64, String> {
+    let n = number(function, args, index)?;
+    if n.fract() != 0.0 || !n.is_finite() {
+        return Err(format!(
+            "{} expects a whole number as argument {}, not {}",
+            function,
+            index + 1,
+            n
+        ));
+    }
+    Ok(n as i64)
+}
+
+///Returns argument `index` of a native function, which must be a string
+pub fn string<'a>(function: &str, args: &'a [Object], index: usize) -> Result<&'a str, String> {
+    match args.get(index) {
+        Some(Object::Str(s)) => Ok(s),
+        Some(value) => Err(format!(
+            "{} expects a string as argument {}, not {}",
+            function,
+            index + 1,
+            value.type_name()
+        )),
+        None => Err(format!(
+            "{} expects at least {} arguments",
+            function,
+            index + 1
+        )),
+    }
+}
+
+///Returns argument `index` of a native function, which must be a list
+pub fn list<'a>(function: &str, args: &'a [Object], index: usize) -> Result<&'a [Object], String> {
+    match args.get(index) {
+        Some(Object::List(items)) => Ok(items),
+        Some(value) => Err(format!(
+            "{} expects a list as argument {}, not {}",
+            function,
+            index + 1,
+            value.type_name()
+        )),
+        None => Err(format!(
+            "{} expects at least {} arguments",
+            function,
+            index + 1
+        )),
+    }
+}
+
+///Returns argument `index` of a native function, which must be a whole
+///number of at least 0
+pub fn count(function: &str, args: &[Object], index: usize) -> Result<usize, String> {
+    let n = integer(function, args, index)?;
+    usize::try_from(n).map_err(|_| {
+        format!(
+            "{} expects a number of at least 0 as argument {}, not {}",
+            function,
+            index + 1,
+            n
+        )
+    })
+}
@@ -0,0 +1,58 @@
+use std::{
+    cell::RefCell,
+    io::{self, BufRead, Write},
+};
+
+use crate::object::object::{Module, Object};
+
+use super::helpers::ModuleBuilder;
+
+thread_local! {
+    /// Where `io.print` writes instead of stdout, when a debugger speaks
+    /// its protocol there
+    static OUTPUT: RefCell<Option<Box<dyn Write>>> = RefCell::new(None);
+}
+
+///Sends what scripts print on this thread to `output`, or back to stdout
+///when it is `None`
+pub fn redirect_output(output: Option<Box<dyn Write>>) {
+    OUTPUT.with(|current| *current.borrow_mut() = output);
+}
+
+fn print(args: &[Object], end: &str) -> Result<Object, String> {
+    let line: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    let text = format!("{}{}", line.join(" "), end);
+    OUTPUT
+        .with(|output| match output.borrow_mut().as_mut() {
+            Some(output) => output.write_all(text.as_bytes()),
+            None => {
+                let mut stdout = io::stdout();
+                stdout
+                    .write_all(text.as_bytes())
+                    .and_then(|_| stdout.flush())
+            }
+        })
+        .map_err(|error| format!("io.print cannot write to the output: {}", error))?;
+    Ok(Object::Nil)
+}
+
+///Creates the `io` module, for the standard input and output
+pub fn module() -> Module {
+    ModuleBuilder::new("io")
+        .function("print", None, |args| print(args, ""))
+        .function("println", None, |args| print(args, "\n"))
+        .function("read_line", Some(0), |_| {
+            let mut line = String::new();
+            match io::stdin().lock().read_line(&mut line) {
+                // The end of the input
+                Ok(0) => Ok(Object::Nil),
+                Ok(_) => {
+                    let end = line.trim_end_matches(['\n', '\r']).len();
+                    line.truncate(end);
+                    Ok(Object::Str(line))
+                }
+                Err(error) => Err(format!("io.read_line cannot read the input: {}", error)),
+            }
+        })
+        .build()
+}
@@ -0,0 +1,116 @@
+use std::f64::consts;
+
+use crate::object::object::{Module, Object};
+
+use super::helpers::{integer, number, ModuleBuilder};
+
+type MathFn = fn(f64) -> f64;
+
+///Exports a function of one number
+fn unary(builder: ModuleBuilder, name: &'static str, function: MathFn) -> ModuleBuilder {
+    let qualified = format!("math.{}", name);
+    builder.function(name, Some(1), move |args| {
+        Ok(Object::Num(function(number(&qualified, args, 0)?)))
+    })
+}
+
+///Exports `min` or `max`, which take one or more numbers
+fn fold(
+    builder: ModuleBuilder,
+    name: &'static str,
+    function: fn(f64, f64) -> f64,
+) -> ModuleBuilder {
+    let qualified = format!("math.{}", name);
+    builder.function(name, None, move |args| {
+        if args.is_empty() {
+            return Err(format!("{} expects at least 1 argument", qualified));
+        }
+        let mut result = number(&qualified, args, 0)?;
+        for index in 1..args.len() {
+            result = function(result, number(&qualified, args, index)?);
+        }
+        Ok(Object::Num(result))
+    })
+}
+
+fn gcd(mut a: i64, mut b: i64) -> i64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.abs()
+}
+
+///Creates the `math` module
+pub fn module() -> Module {
+    let mut builder = ModuleBuilder::new("math")
+        .constant("pi", Object::Num(consts::PI))
+        .constant("e", Object::Num(consts::E))
+        .constant("tau", Object::Num(consts::TAU))
+        .constant("inf", Object::Num(f64::INFINITY));
+
+    let functions: [(&'static str, MathFn); 18] = [
+        ("sqrt", f64::sqrt),
+        ("cbrt", f64::cbrt),
+        ("abs", f64::abs),
+        ("floor", f64::floor),
+        ("ceil", f64::ceil),
+        ("round", f64::round),
+        ("trunc", f64::trunc),
+        ("sign", |n| if n == 0.0 { 0.0 } else { n.signum() }),
+        ("sin", f64::sin),
+        ("cos", f64::cos),
+        ("tan", f64::tan),
+        ("asin", f64::asin),
+        ("acos", f64::acos),
+        ("atan", f64::atan),
+        ("exp", f64::exp),
+        ("ln", f64::ln),
+        ("log10", f64::log10),
+        ("log2", f64::log2),
+    ];
+    for (name, function) in functions {
+        builder = unary(builder, name, function);
+    }
+    builder = fold(builder, "min", f64::min);
+    builder = fold(builder, "max", f64::max);
+
+    builder
+        .function("pow", Some(2), |args| {
+            let base = number("math.pow", args, 0)?;
+            Ok(Object::Num(base.powf(number("math.pow", args, 1)?)))
+        })
+        .function("atan2", Some(2), |args| {
+            let y = number("math.atan2", args, 0)?;
+            Ok(Object::Num(y.atan2(number("math.atan2", args, 1)?)))
+        })
+        .function("log", Some(2), |args| {
+            let n = number("math.log", args, 0)?;
+            Ok(Object::Num(n.log(number("math.log", args, 1)?)))
+        })
+        .function("clamp", Some(3), |args| {
+            let n = number("math.clamp", args, 0)?;
+            let min = number("math.clamp", args, 1)?;
+            let max = number("math.clamp", args, 2)?;
+            if min > max {
+                return Err(format!(
+                    "math.clamp expects its minimum ({}) to be at most its maximum ({})",
+                    min, max
+                ));
+            }
+            Ok(Object::Num(n.clamp(min, max)))
+        })
+        .function("gcd", Some(2), |args| {
+            let a = integer("math.gcd", args, 0)?;
+            let b = integer("math.gcd", args, 1)?;
+            Ok(Object::Num(gcd(a, b) as f64))
+        })
+        .function("lcm", Some(2), |args| {
+            let a = integer("math.lcm", args, 0)?;
+            let b = integer("math.lcm", args, 1)?;
+            if a == 0 || b == 0 {
+                return Ok(Object::Num(0.0));
+            }
+            Ok(Object::Num((a / gcd(a, b) * b).abs() as f64))
+        })
+        .build()
+}
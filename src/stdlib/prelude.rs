@@ -0,0 +1,137 @@
+use std::rc::Rc;
+
+use crate::{
+    ast::stmt::Variant,
+    interpreter::environment::Environment,
+    object::object::{EnumType, ErrorValue, NativeFunction, Object, VariantValue},
+    tokens::symbol::Symbol,
+};
+
+use super::{assert, helpers::string};
+
+///Binds a native function in the prelude
+fn function(
+    prelude: &Environment,
+    name: &str,
+    arity: Option<usize>,
+    function: impl Fn(&[Object]) -> Result<Object, String> + 'static,
+) {
+    let function = NativeFunction::new(name, arity, function);
+    prelude.define(
+        Symbol::intern(name),
+        Object::Native(Rc::new(function)),
+        true,
+    );
+}
+
+thread_local! {
+    /// `Result { Ok(value), Err(error) }` and `Option { Some(value), None }`.
+    /// They are made once per thread, so values built by one module match
+    /// the patterns of another.
+    static BUILTIN_ENUMS: [Rc<EnumType>; 2] = [
+        builtin_enum("Result", &[("Ok", Some("value")), ("Err", Some("error"))]),
+        builtin_enum("Option", &[("Some", Some("value")), ("None", None)]),
+    ];
+}
+
+fn builtin_enum(name: &str, variants: &[(&str, Option<&str>)]) -> Rc<EnumType> {
+    let variants = variants
+        .iter()
+        .map(|(variant, field)| Variant {
+            name: Symbol::intern(variant),
+            fields: field.iter().map(|field| Symbol::intern(field)).collect(),
+            line: 0,
+        })
+        .collect();
+    Rc::new(EnumType {
+        name: Symbol::intern(name),
+        variants: Rc::new(variants),
+    })
+}
+
+///Returns the enums every program can use, `Result` and `Option`
+pub fn builtin_enums() -> [Rc<EnumType>; 2] {
+    BUILTIN_ENUMS.with(Clone::clone)
+}
+
+///Returns the built-in enum with a variant called `name`, and the
+///position of the variant, so `Ok(x)` can stand for `Result.Ok(x)`
+pub fn builtin_variant(name: Symbol) -> Option<(Rc<EnumType>, usize)> {
+    builtin_enums().into_iter().find_map(|enum_type| {
+        let position = enum_type
+            .variants
+            .iter()
+            .position(|variant| variant.name == name)?;
+        Some((enum_type, position))
+    })
+}
+
+///Returns how variant `variant` of enum `enum_name` is written: on its
+///own for the built-in enums, as in `Ok`, or else as `Shape.Circle`
+pub fn variant_name(enum_name: Symbol, variant: Symbol) -> String {
+    match builtin_variant(variant) {
+        Some((enum_type, _)) if enum_type.name == enum_name => variant.to_string(),
+        _ => format!("{}.{}", enum_name, variant),
+    }
+}
+
+///Checks whether an enum is `Result` or `Option`. Their first variant,
+///`Ok` or `Some`, holds a value and the second one does not.
+pub fn is_builtin(enum_type: &Rc<EnumType>) -> bool {
+    builtin_enums()
+        .iter()
+        .any(|builtin| Rc::ptr_eq(builtin, enum_type))
+}
+
+fn variant_value(enum_type: &Rc<EnumType>, variant: usize, values: Vec<Object>) -> Object {
+    Object::Variant(Rc::new(VariantValue {
+        enum_type: enum_type.clone(),
+        variant,
+        values,
+    }))
+}
+
+///Creates the scope around the globals of every program, holding the
+///names that can be used without importing them
+pub fn prelude() -> Environment {
+    let prelude = Environment::new();
+
+    // `Error(message)` or `Error(message, kind)`, to `throw`
+    function(&prelude, "Error", None, |args| {
+        if args.is_empty() || args.len() > 2 {
+            return Err(format!(
+                "Error expects 1 or 2 arguments but got {}",
+                args.len()
+            ));
+        }
+        let message = string("Error", args, 0)?;
+        let kind = match args.get(1) {
+            Some(_) => string("Error", args, 1)?,
+            None => "Error",
+        };
+        Ok(Object::Error(Rc::new(ErrorValue::new(kind, message))))
+    });
+
+    // `Result` and `Option`, with their variants usable on their own
+    for enum_type in builtin_enums() {
+        for (position, variant) in enum_type.variants.iter().enumerate() {
+            let value = if variant.fields.is_empty() {
+                variant_value(&enum_type, position, Vec::new())
+            } else {
+                let shared = enum_type.clone();
+                let constructor =
+                    NativeFunction::new(&variant.name.to_string(), Some(1), move |args| {
+                        Ok(variant_value(&shared, position, args.to_vec()))
+                    });
+                Object::Native(Rc::new(constructor))
+            };
+            prelude.define(variant.name, value, true);
+        }
+        prelude.define(enum_type.name, Object::Enum(enum_type), true);
+    }
+
+    // `assert`, `assert_eq` and `assert_throws`, run by the interpreter
+    assert::define(&prelude);
+
+    prelude
+}
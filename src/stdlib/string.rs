@@ -0,0 +1,127 @@
+use std::rc::Rc;
+
+use crate::object::object::{Module, Object};
+
+use super::helpers::{count, list, string, ModuleBuilder};
+
+// Strings are sequences of Unicode characters: lengths and positions
+// count characters, never bytes, so no string is ever cut in the middle
+// of a character.
+
+fn text(value: String) -> Object {
+    Object::Str(value)
+}
+
+fn strings(items: impl Iterator<Item = String>) -> Object {
+    Object::List(Rc::new(items.map(Object::Str).collect()))
+}
+
+///Creates the `string` module. Its functions are also the methods of
+///strings: `"abc".upper()` is `string.upper("abc")`.
+pub fn module() -> Module {
+    ModuleBuilder::new("string")
+        .function("len", Some(1), |args| match &args[0] {
+            Object::List(items) => Ok(Object::Num(items.len() as f64)),
+            _ => {
+                let s = string("string.len", args, 0)?;
+                Ok(Object::Num(s.chars().count() as f64))
+            }
+        })
+        .function("upper", Some(1), |args| {
+            Ok(text(string("string.upper", args, 0)?.to_uppercase()))
+        })
+        .function("lower", Some(1), |args| {
+            Ok(text(string("string.lower", args, 0)?.to_lowercase()))
+        })
+        .function("trim", Some(1), |args| {
+            Ok(text(string("string.trim", args, 0)?.trim().to_string()))
+        })
+        .function("split", Some(2), |args| {
+            let s = string("string.split", args, 0)?;
+            let separator = string("string.split", args, 1)?;
+            if separator.is_empty() {
+                return Ok(strings(s.chars().map(String::from)));
+            }
+            Ok(strings(s.split(separator).map(String::from)))
+        })
+        .function("join", Some(2), |args| {
+            let separator = string("string.join", args, 0)?;
+            let items: Vec<String> = list("string.join", args, 1)?
+                .iter()
+                .map(|item| item.to_string())
+                .collect();
+            Ok(text(items.join(separator)))
+        })
+        .function("replace", Some(3), |args| {
+            let s = string("string.replace", args, 0)?;
+            let from = string("string.replace", args, 1)?;
+            let to = string("string.replace", args, 2)?;
+            if from.is_empty() {
+                return Err("string.replace cannot replace an empty string".to_string());
+            }
+            Ok(text(s.replace(from, to)))
+        })
+        .function("contains", Some(2), |args| {
+            let s = string("string.contains", args, 0)?;
+            Ok(Object::Bool(s.contains(string(
+                "string.contains",
+                args,
+                1,
+            )?)))
+        })
+        .function("starts_with", Some(2), |args| {
+            let s = string("string.starts_with", args, 0)?;
+            Ok(Object::Bool(s.starts_with(string(
+                "string.starts_with",
+                args,
+                1,
+            )?)))
+        })
+        .function("ends_with", Some(2), |args| {
+            let s = string("string.ends_with", args, 0)?;
+            Ok(Object::Bool(s.ends_with(string(
+                "string.ends_with",
+                args,
+                1,
+            )?)))
+        })
+        .function("find", Some(2), |args| {
+            let s = string("string.find", args, 0)?;
+            let needle = string("string.find", args, 1)?;
+            // The byte offset of the match, counted in characters
+            Ok(match s.find(needle) {
+                Some(offset) => Object::Num(s[..offset].chars().count() as f64),
+                None => Object::Nil,
+            })
+        })
+        .function("substring", Some(3), |args| {
+            let s = string("string.substring", args, 0)?;
+            let start = count("string.substring", args, 1)?;
+            let end = count("string.substring", args, 2)?;
+            let length = s.chars().count();
+            if start > end || end > length {
+                return Err(format!(
+                    "string.substring expects 0 <= start <= end <= {}, not {} and {}",
+                    length, start, end
+                ));
+            }
+            Ok(text(s.chars().skip(start).take(end - start).collect()))
+        })
+        .function("repeat", Some(2), |args| {
+            let s = string("string.repeat", args, 0)?;
+            Ok(text(s.repeat(count("string.repeat", args, 1)?)))
+        })
+        .function("chars", Some(1), |args| {
+            let s = string("string.chars", args, 0)?;
+            Ok(strings(s.chars().map(String::from)))
+        })
+        .function("to_num", Some(1), |args| {
+            let s = string("string.to_num", args, 0)?;
+            match s.trim().parse::<f64>() {
+                Ok(n) => Ok(Object::Num(n)),
+                Err(_) => Err(format!("string.to_num cannot read {:?} as a number", s)),
+            }
+        })
+        .function("to_str", Some(1), |args| Ok(text(args[0].to_string())))
+        .build()
+}
@@ -0,0 +1,152 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    ast::stmt::Stmt,
+    engine::engine::Error,
+    errors::diagnostic::Diagnostic,
+    interpreter::interpreter::Interpreter,
+    object::object::module_name,
+    optimizer::optimizer::{OptLevel, Optimizer},
+    parser::parser::Parser,
+    scanner::scanner::Scanner,
+    stdlib::fs::Capabilities,
+};
+
+/// The ending of the files `ND-Lang test` looks for tests in
+pub const TEST_SUFFIX: &str = "_test.ndl";
+
+/// How a `test` block went.
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    pub name: String,
+    pub file: PathBuf,
+    /// The line the test starts on, counting from 1
+    pub line: usize,
+    /// The error that stopped the test, or `None` if it passed
+    pub failure: Option<Diagnostic>,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Runs the `test` blocks of ND-Lang files. Each test gets an interpreter
+/// of its own, which runs the rest of the file first, so tests cannot
+/// see each other's changes.
+#[derive(Default)]
+pub struct TestRunner {
+    /// Only the tests with this in their name are run
+    filter: Option<String>,
+    capabilities: Capabilities,
+    search_paths: Vec<PathBuf>,
+    opt_level: OptLevel,
+}
+
+impl TestRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///Runs only the tests whose name contains `filter`
+    pub fn set_filter(&mut self, filter: &str) {
+        self.filter = Some(filter.to_string());
+    }
+
+    ///Sets what tests may do with files. Nothing is allowed by default.
+    pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+        self.capabilities = capabilities;
+    }
+
+    ///Adds a directory to look for imported modules in
+    pub fn add_search_path(&mut self, path: impl Into<PathBuf>) {
+        self.search_paths.push(path.into());
+    }
+
+    pub fn set_opt_level(&mut self, level: OptLevel) {
+        self.opt_level = level;
+    }
+
+    ///Runs the tests of a file that match the filter, in the order they
+    ///are written
+    pub fn run_file(&self, path: &Path) -> Result<Vec<TestResult>, Error> {
+        let source = std::fs::read_to_string(path)?;
+        let mut parser = Parser::new(Scanner::new(&source, path.display().to_string()));
+        let statements = parser.parse();
+        if !parser.errors().is_empty() {
+            return Err(Error::Syntax(parser.errors().clone()));
+        }
+        let statements = Optimizer::new(self.opt_level).optimize(statements);
+
+        let mut results = Vec::new();
+        for stmt in statements.iter() {
+            let Stmt::TestStmt { name, body, line } = stmt else {
+                continue;
+            };
+            if self
+                .filter
+                .as_ref()
+                .is_some_and(|filter| !name.contains(filter.as_str()))
+            {
+                continue;
+            }
+            let failure = self.run_test(path, &statements, body).err();
+            results.push(TestResult {
+                name: name.clone(),
+                file: path.to_path_buf(),
+                line: line + 1,
+                failure,
+            });
+        }
+        Ok(results)
+    }
+
+    fn run_test(&self, path: &Path, statements: &[Stmt], body: &Stmt) -> Result<(), Diagnostic> {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_file(path);
+        interpreter.set_source_name(&path.display().to_string());
+        interpreter.set_capabilities(self.capabilities.clone());
+        interpreter.set_opt_level(self.opt_level);
+        for search_path in self.search_paths.iter() {
+            interpreter.add_search_path(search_path.clone());
+        }
+        // The test blocks are skipped, leaving the file's declarations
+        interpreter.interpret(statements)?;
+        interpreter.interpret(std::slice::from_ref(body))?;
+        Ok(())
+    }
+}
+
+///Returns the test files among `paths`: files given directly, and the
+///files ending in `_test.ndl` found under the directories, in name order
+pub fn discover(paths: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            discover_in(path, &mut files)?;
+        } else {
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
+fn discover_in(directory: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(directory)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<_>>()?;
+    entries.sort();
+    for entry in entries {
+        let name = module_name(&entry);
+        if entry.is_dir() && !name.starts_with('.') {
+            discover_in(&entry, files)?;
+        } else if name.ends_with(TEST_SUFFIX) {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,204 @@
+use core::fmt;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+/// An interned string. Identifiers are interned once by the scanner, so
+/// comparing or hashing names is a matter of comparing two integers.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u64);
+
+/// Set on the symbols of a `SymbolScope`, which also carry the id of
+/// their scope above the low 32 bits. Global and scoped symbols never
+/// share an id, and neither do the symbols of two scopes.
+const SCOPED: u64 = 1 << 63;
+
+/// The id the next `SymbolScope` is given
+static NEXT_SCOPE: AtomicU32 = AtomicU32::new(0);
+
+/// The symbol table backing every `Symbol`. Interned strings live for the
+/// rest of the program, which lets `Symbol::as_str` hand out `'static`
+/// references.
+struct Interner {
+    symbols: HashMap<&'static str, Symbol>,
+    strings: Vec<&'static str>,
+}
+
+static INTERNER: Lazy<Mutex<Interner>> = Lazy::new(|| {
+    Mutex::new(Interner {
+        symbols: HashMap::new(),
+        strings: Vec::new(),
+    })
+});
+
+/// The strings of a `SymbolScope`, freed along with it
+struct ScopeTable {
+    id: u32,
+    symbols: HashMap<Rc<str>, Symbol>,
+    strings: Vec<Rc<str>>,
+}
+
+thread_local! {
+    /// The strings of the global table this thread has seen. The table
+    /// only grows, so `as_str` takes the lock only for new symbols.
+    static SEEN: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+    /// The scope entered on this thread, if any
+    static CURRENT: RefCell<Option<Rc<RefCell<ScopeTable>>>> = const { RefCell::new(None) };
+}
+
+/// A symbol table that is dropped with its owner instead of living for
+/// the rest of the program. While a scope is entered, strings that are
+/// not interned yet go to the scope, which keeps long running tools such
+/// as the language server from growing with every edit.
+///
+/// The symbols of a scope only mean something while it is entered.
+/// Outside of it they compare unequal to every string and display as
+/// `<unknown symbol>`.
+#[derive(Clone)]
+pub struct SymbolScope(Rc<RefCell<ScopeTable>>);
+
+impl SymbolScope {
+    pub fn new() -> Self {
+        // Ids only need to differ between scopes alive at the same time
+        let id = NEXT_SCOPE.fetch_add(1, Ordering::Relaxed) & !(1 << 31);
+        Self(Rc::new(RefCell::new(ScopeTable {
+            id,
+            symbols: HashMap::new(),
+            strings: Vec::new(),
+        })))
+    }
+
+    ///Runs `f` with this scope entered
+    pub fn enter<R>(&self, f: impl FnOnce() -> R) -> R {
+        // Leaves the scope even when `f` panics
+        struct Leave(Option<Rc<RefCell<ScopeTable>>>);
+        impl Drop for Leave {
+            fn drop(&mut self) {
+                let previous = self.0.take();
+                CURRENT.with(|current| *current.borrow_mut() = previous);
+            }
+        }
+
+        let previous = CURRENT.with(|current| current.replace(Some(self.0.clone())));
+        let _leave = Leave(previous);
+        f()
+    }
+}
+
+impl Default for SymbolScope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Symbol {
+    ///Returns the symbol for `text`, adding it to the table if needed. In
+    ///a `SymbolScope`, new strings are added to the scope.
+    pub fn intern(text: &str) -> Symbol {
+        let scope = CURRENT.with(|current| current.borrow().clone());
+        let Some(scope) = scope else {
+            return Self::intern_global(text).0;
+        };
+
+        let mut table = scope.borrow_mut();
+        if let Some(symbol) = table.symbols.get(text) {
+            return *symbol;
+        }
+        if let Some(symbol) = INTERNER.lock().unwrap().symbols.get(text) {
+            return *symbol;
+        }
+
+        let text: Rc<str> = Rc::from(text);
+        let symbol = Symbol(SCOPED | (table.id as u64) << 32 | table.strings.len() as u64);
+        table.strings.push(text.clone());
+        table.symbols.insert(text, symbol);
+        symbol
+    }
+
+    fn intern_global(text: &str) -> (Symbol, &'static str) {
+        let mut interner = INTERNER.lock().unwrap();
+        if let Some((text, symbol)) = interner.symbols.get_key_value(text) {
+            return (*symbol, *text);
+        }
+
+        let text: &'static str = Box::leak(text.to_string().into_boxed_str());
+        let symbol = Symbol(interner.strings.len() as u64);
+        interner.strings.push(text);
+        interner.symbols.insert(text, symbol);
+        (symbol, text)
+    }
+
+    ///Returns a string that lives for the rest of the program, adding it
+    ///to the global table even inside a `SymbolScope`. Only meant for
+    ///the few lexemes kept in the AST, such as operators and type names.
+    pub(crate) fn static_str(text: &str) -> &'static str {
+        Self::intern_global(text).1
+    }
+
+    ///Returns the string the symbol was interned from, if it is in the
+    ///global table. The strings of a `SymbolScope` are freed with it, so
+    ///scoped symbols have none; compare or format them instead.
+    pub fn as_str(self) -> Option<&'static str> {
+        if self.0 & SCOPED != 0 {
+            return None;
+        }
+
+        let index = self.0 as usize;
+        SEEN.with(|seen| {
+            let mut seen = seen.borrow_mut();
+            if index >= seen.len() {
+                let interner = INTERNER.lock().unwrap();
+                let start = seen.len();
+                seen.extend_from_slice(&interner.strings[start..]);
+            }
+            seen.get(index).copied()
+        })
+    }
+
+    ///Runs `f` on the string of the symbol, if it is global or comes
+    ///from the entered scope
+    fn with_str<R>(self, f: impl FnOnce(&str) -> R) -> Option<R> {
+        if self.0 & SCOPED == 0 {
+            return self.as_str().map(f);
+        }
+
+        let scope = CURRENT.with(|current| current.borrow().clone())?;
+        let table = scope.borrow();
+        if (self.0 & !SCOPED) >> 32 != table.id as u64 {
+            return None;
+        }
+        let text = table.strings.get(self.0 as u32 as usize)?.clone();
+        drop(table);
+        Some(f(&text))
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.with_str(|text| text == other).unwrap_or(false)
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.with_str(|text| write!(f, "{}", text))
+            .unwrap_or_else(|| write!(f, "<unknown symbol>"))
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.with_str(|text| write!(f, "Symbol({:?})", text))
+            .unwrap_or_else(|| write!(f, "Symbol(<unknown>)"))
+    }
+}
@@ -1,58 +1,124 @@
-use crate::object::object::*;
+use crate::tokens::symbol::Symbol;
 use crate::tokens::token_type::*;
 
 use core::fmt;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
 
+/// A token scanned from the source. The lexeme is a slice of the source
+/// buffer rather than a copy, so tokens borrow from the text they were
+/// scanned from. Tokens kept in the AST are detached with `to_static`.
 #[derive(Debug, Clone, PartialEq)]
-pub struct Token {
+pub struct Token<'src> {
     pub ttype: TokenType,
-    pub lexeme: String,
-    pub literal: Object,
+    pub lexeme: &'src str,
+    /// The interned name of an IDENTIFIER token
+    pub symbol: Option<Symbol>,
     pub line: usize,
     pub position: usize,
+    pub leading_trivia: Vec<Trivia<'src>>,
+    pub trailing_trivia: Vec<Trivia<'src>>,
 }
 
-impl Token {
-    pub fn new(
-        ttype: TokenType,
-        lexeme: String,
-        literal: Object,
-        line: usize,
-        position: usize,
-    ) -> Self {
+impl<'src> Token<'src> {
+    pub fn new(ttype: TokenType, lexeme: &'src str, line: usize, position: usize) -> Self {
         Token {
             ttype,
             lexeme,
-            literal,
+            symbol: None,
             line,
             position,
+            leading_trivia: Vec::new(),
+            trailing_trivia: Vec::new(),
+        }
+    }
+
+    /// Returns a copy of the token that no longer borrows from the source.
+    /// The lexeme is interned and the trivia is dropped.
+    pub fn to_static(&self) -> Token<'static> {
+        Token {
+            ttype: self.ttype.clone(),
+            lexeme: Symbol::static_str(self.lexeme),
+            symbol: self.symbol,
+            line: self.line,
+            position: self.position,
+            leading_trivia: Vec::new(),
+            trailing_trivia: Vec::new(),
+        }
+    }
+
+    /// Returns the token as it was written in the source. String lexemes
+    /// are stored without their quotes, so those are put back.
+    pub fn source_text(&self) -> String {
+        match self.ttype {
+            TokenType::STRING => format!("\"{}\"", self.lexeme),
+            _ => self.lexeme.to_string(),
+        }
+    }
+
+    /// Returns the token with its leading and trailing trivia, exactly as
+    /// it appeared in the source. Only meaningful for lossless scans.
+    pub fn full_text(&self) -> String {
+        let mut text = String::new();
+        for trivia in &self.leading_trivia {
+            text.push_str(trivia.text);
+        }
+        text.push_str(&self.source_text());
+        for trivia in &self.trailing_trivia {
+            text.push_str(trivia.text);
+        }
+        text
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriviaKind {
+    Whitespace,
+    Newline,
+    Comment,
+    /// Text the scanner could not turn into a token
+    Skipped,
+}
+
+/// Source text that carries no meaning for the parser, attached to the
+/// neighbouring token by a lossless scan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia<'src> {
+    pub kind: TriviaKind,
+    pub text: &'src str,
+}
+
+impl<'src> Trivia<'src> {
+    pub fn new(kind: TriviaKind, text: &'src str) -> Self {
+        Trivia { kind, text }
+    }
+}
+
+/// A `//` comment kept aside by the scanner. `trailing` is set when the
+/// comment follows code on the same line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment<'src> {
+    pub text: &'src str,
+    pub line: usize,
+    pub trailing: bool,
+}
+
+impl<'src> Comment<'src> {
+    pub fn new(text: &'src str, line: usize, trailing: bool) -> Self {
+        Comment {
+            text,
+            line,
+            trailing,
         }
     }
 }
 
-impl fmt::Display for Token {
+impl fmt::Display for Token<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(
             f,
-            "{:?} {} {} {} {} \n\n",
-            self.ttype,
-            self.lexeme,
-            match &self.literal {
-                Object::Num(x) => x.to_string(),
-                Object::Str(x) => format!("\"{}\"", x),
-                Object::Nil => "Nil".to_string(),
-                Object::Bool(x) => {
-                    if *x == true {
-                        "True".to_string()
-                    } else {
-                        "False".to_string()
-                    }
-                }
-            },
-            self.line,
-            self.position
+            "{:?} {} {} {} \n\n",
+            self.ttype, self.lexeme, self.line, self.position
         )
     }
 }
@@ -77,6 +143,16 @@ pub static KEYWORDS: Lazy<HashMap<String, TokenType>> = Lazy::new(|| {
     map.insert(String::from("let"), TokenType::LET);
     map.insert(String::from("const"), TokenType::CONST);
     map.insert(String::from("i32"), TokenType::I32);
+    map.insert(String::from("infix"), TokenType::INFIX);
+    map.insert(String::from("prefix"), TokenType::PREFIX);
+    map.insert(String::from("import"), TokenType::IMPORT);
+    map.insert(String::from("export"), TokenType::EXPORT);
+    map.insert(String::from("match"), TokenType::MATCH);
+    map.insert(String::from("enum"), TokenType::ENUM);
+    map.insert(String::from("try"), TokenType::TRY);
+    map.insert(String::from("catch"), TokenType::CATCH);
+    map.insert(String::from("finally"), TokenType::FINALLY);
+    map.insert(String::from("throw"), TokenType::THROW);
 
     map
 });
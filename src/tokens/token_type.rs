@@ -6,6 +6,8 @@ pub enum TokenType {
     RIGHTPAREN,
     LEFTBRACE,
     RIGHTBRACE,
+    LEFTBRACKET,
+    RIGHTBRACKET,
     COMMA,
     DOT,
     MINUS,
@@ -27,6 +29,10 @@ pub enum TokenType {
     LESSEQUAL,
     PLUSEQUALS,
     MINUSEQUALS,
+    FATARROW,
+    DOTDOT,
+    DOTDOTEQUAL,
+    QUESTION,
 
     // Literals.
     IDENTIFIER,
@@ -54,5 +60,18 @@ pub enum TokenType {
     HAVE,
     VAR,
     WHILE,
+    INFIX,
+    PREFIX,
+    IMPORT,
+    EXPORT,
+    MATCH,
+    ENUM,
+    TRY,
+    CATCH,
+    FINALLY,
+    THROW,
+
+    // Operators declared by the program with `infix`/`prefix`
+    OPERATOR,
     EOF,
 }
@@ -0,0 +1,85 @@
+//! Helpers shared by the integration tests. Each test crate uses only
+//! some of them.
+#![allow(dead_code)]
+
+use std::{
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use nd_lang::{
+    errors::diagnostic::Diagnostic, interpreter::interpreter::Interpreter, object::object::Object,
+    parser::parser::Parser, scanner::scanner::Scanner, stdlib::fs::Capabilities,
+};
+
+///Parses and runs `source` in a fresh interpreter, returning the value of
+///its last statement or its first syntax or runtime error
+pub fn interpret(source: &str, capabilities: Capabilities) -> Result<Object, Diagnostic> {
+    let mut parser = Parser::new(Scanner::new(source, "test".to_string()));
+    let statements = parser.parse();
+    if let Some(error) = parser.errors().first() {
+        return Err(error.clone());
+    }
+    let mut interpreter = Interpreter::new();
+    interpreter.set_capabilities(capabilities);
+    interpreter.interpret(&statements)
+}
+
+///Runs `source` with the given capabilities, keeping only the message
+///of an error
+pub fn run_with(source: &str, capabilities: Capabilities) -> Result<Object, String> {
+    interpret(source, capabilities).map_err(|error| error.message().to_string())
+}
+
+///Runs `source` without access to any file
+pub fn run(source: &str) -> Result<Object, String> {
+    run_with(source, Capabilities::default())
+}
+
+///Returns the syntax errors of `source`
+pub fn syntax_errors(source: &str) -> Vec<Diagnostic> {
+    let mut parser = Parser::new(Scanner::new(source, "test".to_string()));
+    parser.parse();
+    parser.errors().clone()
+}
+
+/// A directory of files written for one test, removed when dropped
+pub struct Project {
+    root: PathBuf,
+}
+
+impl Project {
+    ///Writes the files, given as paths relative to the project and their
+    ///contents, into a directory no other test uses
+    pub fn new(files: &[(&str, &str)]) -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let root = std::env::temp_dir().join(format!(
+            "nd_lang_{}_{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        for (path, source) in files {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, source).unwrap();
+        }
+        Self { root }
+    }
+}
+
+impl Deref for Project {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl Drop for Project {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
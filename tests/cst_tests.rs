@@ -0,0 +1,99 @@
+use nd_lang::{
+    ast::cst::{SyntaxElement, SyntaxKind, SyntaxNode},
+    parser::parser::Parser,
+    scanner::scanner::Scanner,
+    tokens::token::TriviaKind,
+};
+
+fn cst(source: &str) -> SyntaxNode<'_> {
+    let mut parser = Parser::new_lossless(Scanner::new_lossless(source, "test".to_string()));
+    let tree = parser.parse_cst();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+    tree
+}
+
+fn kinds(node: &SyntaxNode) -> Vec<SyntaxKind> {
+    node.children
+        .iter()
+        .filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node.kind),
+            SyntaxElement::Token(_) => None,
+        })
+        .collect()
+}
+
+#[test]
+fn printing_the_tree_gives_back_the_source() {
+    let sources = [
+        "",
+        "let x = 1;",
+        "let empty = \"\" + \"\";",
+        "  // only a comment\n\n",
+        "let  x\t=  1 ;   // one\r\n\r\n\r\nlet e = \"multi\nline ü\"; // é\n",
+        "infix <+> 6 left = func(a, b) {\n  ret a+b*2; // doubled\n};\n1 <+> 2;",
+        "if (x>1) { y = [1,2 , 3][0]; }\nelse   { y = f( a )?; }\n// trailing\n",
+        "enum Shape { Circle(r), Rect(w, h) }\nlet area = match s {\n  Shape.Circle(r) => r, _ => 0,\n};",
+        "try { throw Error(\"x\"); } catch (e) { e.message; } finally {}\ntest \"t\" { assert(true); }",
+    ];
+    for source in sources {
+        assert_eq!(cst(source).to_string(), source);
+    }
+}
+
+#[test]
+fn nodes_group_the_tokens_of_each_construct() {
+    let tree = cst("let x = 1 + 2;\nf(x);");
+    assert_eq!(tree.kind, SyntaxKind::Program);
+    assert_eq!(
+        kinds(&tree),
+        [SyntaxKind::VarDeclarationStmt, SyntaxKind::ExpressionStmt]
+    );
+
+    let SyntaxElement::Node(declaration) = &tree.children[0] else {
+        panic!("expected a node");
+    };
+    assert_eq!(kinds(declaration), [SyntaxKind::BinaryOp]);
+    let lexemes: Vec<&str> = declaration
+        .tokens()
+        .iter()
+        .map(|token| token.lexeme)
+        .collect();
+    assert_eq!(lexemes, ["let", "x", "=", "1", "+", "2", ";"]);
+}
+
+#[test]
+fn tokens_carry_the_trivia_around_them() {
+    let tree = cst("  x; // note\ny;");
+    let tokens = tree.tokens();
+    let x = tokens[0];
+    assert_eq!(x.lexeme, "x");
+    assert_eq!(x.leading_trivia[0].kind, TriviaKind::Whitespace);
+    assert_eq!(x.leading_trivia[0].text, "  ");
+
+    // A comment on the line of a token trails it, up to the newline
+    let semicolon = tokens[1];
+    let trailing: Vec<(TriviaKind, &str)> = semicolon
+        .trailing_trivia
+        .iter()
+        .map(|trivia| (trivia.kind, trivia.text))
+        .collect();
+    assert_eq!(
+        trailing,
+        [
+            (TriviaKind::Whitespace, " "),
+            (TriviaKind::Comment, "// note"),
+            (TriviaKind::Newline, "\n")
+        ]
+    );
+    assert!(tokens[2].leading_trivia.is_empty());
+}
+
+#[test]
+fn sources_with_errors_still_print_back() {
+    for source in ["let x = ;\nx;", "let s = \"open", "a # b;", "f(1, ;"] {
+        let mut parser = Parser::new_lossless(Scanner::new_lossless(source, "test".to_string()));
+        let tree = parser.parse_cst();
+        assert!(!parser.errors().is_empty());
+        assert_eq!(tree.to_string(), source);
+    }
+}
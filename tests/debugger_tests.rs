@@ -0,0 +1,210 @@
+mod common;
+
+use std::{
+    cell::RefCell,
+    io::{Cursor, Write},
+    rc::Rc,
+};
+
+use common::Project;
+use nd_lang::{
+    debugger::{
+        cli::{parse_expression, CliDebugger, STOPPED},
+        dap::DapServer,
+        debugger::{Debugger, PauseReason, Step},
+    },
+    lsp::server::{read_message, write_message},
+    Engine, Error, Value,
+};
+use serde_json::json;
+
+/// Output shared with the debugger, which the engine owns
+#[derive(Clone, Default)]
+struct Output(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Output {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(bytes)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn stepping_follows_calls() {
+    let mut debugger = Debugger::new();
+    assert_eq!(
+        debugger.should_pause("main.ndl", 1, 0),
+        Some(PauseReason::Step)
+    );
+
+    debugger.resume(Step::Over { depth: 0 });
+    assert_eq!(debugger.should_pause("main.ndl", 5, 1), None);
+    assert_eq!(
+        debugger.should_pause("main.ndl", 2, 0),
+        Some(PauseReason::Step)
+    );
+
+    debugger.resume(Step::Out { depth: 1 });
+    assert_eq!(debugger.should_pause("main.ndl", 6, 1), None);
+    assert_eq!(
+        debugger.should_pause("main.ndl", 3, 0),
+        Some(PauseReason::Step)
+    );
+
+    debugger.add_breakpoint("src/util.ndl", 4);
+    debugger.resume(Step::Continue);
+    assert_eq!(debugger.should_pause("main.ndl", 4, 0), None);
+    assert_eq!(
+        debugger.should_pause("util", 4, 2),
+        Some(PauseReason::Breakpoint)
+    );
+}
+
+#[test]
+fn quitting_stops_the_program_inside_try() {
+    let source = "let cleaned = false;
+try {
+    let inside = true;
+} catch (e) {
+    cleaned = \"caught\";
+} finally {
+    cleaned = true;
+}
+let after = true;";
+    let commands = "break 3\ncontinue\nquit\n";
+    let debugger = CliDebugger::new("main.ndl", commands.as_bytes(), Output::default());
+
+    let mut engine = Engine::new();
+    engine.set_debug_hook(Box::new(debugger));
+    match engine.eval_named(source, "main.ndl") {
+        Err(Error::Runtime(error)) => assert_eq!(error.message(), STOPPED),
+        other => panic!("expected the debugger to stop the program, got {:?}", other),
+    }
+    // `catch` never sees the stop, but `finally` still runs
+    assert_eq!(engine.get_global("cleaned"), Some(Value::Bool(true)));
+    assert_eq!(engine.get_global("after"), None);
+}
+
+#[test]
+fn cli_debugger_inspects_the_paused_frame() {
+    let source = "let square = func(x) {
+    let y = x * x;
+    ret y;
+};
+let total = square(3);
+total + 1;";
+    let commands = "break 2\nwatch x * 10\ncontinue\nlocals\nprint y\nbacktrace\ncontinue\n";
+    let output = Output::default();
+    let debugger = CliDebugger::new("main.ndl", commands.as_bytes(), output.clone());
+
+    let mut engine = Engine::new();
+    engine.set_debug_hook(Box::new(debugger));
+    assert_eq!(
+        engine.eval_named(source, "main.ndl").unwrap(),
+        Value::Num(10.0)
+    );
+
+    let output = String::from_utf8(output.0.borrow().clone()).unwrap();
+    let lines: Vec<&str> = output.split("(ndb) ").collect();
+    assert_eq!(
+        lines,
+        [
+            "Paused at main.ndl:1\n",
+            "Breakpoint set at main.ndl:2\n",
+            "Watch 1: x * 10\n",
+            "Paused at main.ndl:2 (breakpoint)\n  1: x * 10 = 30\n",
+            "x = 3\n",
+            "[2::0] NameError: Undefined variable \"y\"\n",
+            "#0 square (main.ndl:2)\n#1 <script> (main.ndl:5)\n",
+            "",
+        ]
+    );
+}
+
+#[test]
+fn dap_server_stops_at_breakpoints() {
+    let project = Project::new(&[(
+        "main.ndl",
+        "import { println } from \"io\";
+let square = func(x) {
+    let y = x * x;
+    ret y;
+};
+println(square(3));",
+    )]);
+    let path = project.join("main.ndl");
+    let program = path.to_str().unwrap();
+
+    let requests = [
+        json!({ "command": "initialize", "arguments": {} }),
+        json!({ "command": "launch", "arguments": { "program": program } }),
+        json!({ "command": "setBreakpoints", "arguments": {
+            "source": { "path": program }, "breakpoints": [{ "line": 3 }]
+        } }),
+        json!({ "command": "configurationDone" }),
+        json!({ "command": "stackTrace", "arguments": { "threadId": 1 } }),
+        json!({ "command": "scopes", "arguments": { "frameId": 0 } }),
+        json!({ "command": "variables", "arguments": { "variablesReference": 1 } }),
+        json!({ "command": "evaluate", "arguments": { "expression": "x * 10", "frameId": 0 } }),
+        json!({ "command": "continue", "arguments": { "threadId": 1 } }),
+        json!({ "command": "disconnect" }),
+    ];
+    let mut input = Vec::new();
+    for (seq, mut request) in requests.into_iter().enumerate() {
+        request["seq"] = json!(seq + 1);
+        request["type"] = json!("request");
+        write_message(&mut input, &request).unwrap();
+    }
+    let output = Output::default();
+    DapServer::new(Cursor::new(input), output.clone())
+        .run()
+        .unwrap();
+
+    let output = output.0.borrow().clone();
+    let mut reader = output.as_slice();
+    let mut messages = Vec::new();
+    while let Some(message) = read_message(&mut reader).unwrap() {
+        messages.push(match message["type"].as_str() {
+            Some("event") => format!("event {} {}", message["event"], message["body"]),
+            _ => format!("{} {}", message["command"], message["body"]),
+        });
+    }
+    let stack = format!(
+        "\"stackTrace\" {{\"stackFrames\":[\
+        {{\"column\":1,\"id\":0,\"line\":3,\"name\":\"square\",\"source\":{{\"path\":{0:?}}}}},\
+        {{\"column\":15,\"id\":1,\"line\":6,\"name\":\"<script>\",\"source\":{{\"path\":{0:?}}}}}],\
+        \"totalFrames\":2}}",
+        program
+    );
+    assert_eq!(
+        messages,
+        [
+            "\"initialize\" {\"supportsConfigurationDoneRequest\":true,\"supportsEvaluateForHovers\":true}",
+            "event \"initialized\" {}",
+            "\"launch\" {}",
+            "\"setBreakpoints\" {\"breakpoints\":[{\"line\":3,\"verified\":true}]}",
+            "\"configurationDone\" {}",
+            "event \"stopped\" {\"allThreadsStopped\":true,\"reason\":\"breakpoint\",\"threadId\":1}",
+            &stack,
+            "\"scopes\" {\"scopes\":[{\"expensive\":false,\"name\":\"Locals\",\"variablesReference\":1},{\"expensive\":false,\"name\":\"Globals\",\"variablesReference\":2}]}",
+            "\"variables\" {\"variables\":[{\"name\":\"x\",\"type\":\"number\",\"value\":\"3\",\"variablesReference\":0}]}",
+            "\"evaluate\" {\"result\":\"30\",\"type\":\"number\",\"variablesReference\":0}",
+            "\"continue\" {\"allThreadsContinued\":true}",
+            "event \"output\" {\"category\":\"stdout\",\"output\":\"9\\n\"}",
+            "event \"exited\" {\"exitCode\":0}",
+            "event \"terminated\" {}",
+            "\"disconnect\" {}",
+        ]
+    );
+}
+
+#[test]
+fn malformed_expressions_are_errors() {
+    for text in ["", "(", "1 +", "[1,", "{", "func(", ")", "x.", "a ? b"] {
+        assert!(parse_expression(text).is_err(), "{:?} parsed", text);
+    }
+    assert!(parse_expression("x * 10").is_ok());
+}
@@ -0,0 +1,160 @@
+mod common;
+
+use common::Project;
+use nd_lang::{Engine, Error, Handle, Value};
+
+#[test]
+fn globals_persist_between_evals() {
+    let mut engine = Engine::new();
+    engine.set_global("base", Value::Num(10.0));
+    engine.eval("let add = func(n) { ret base + n; };").unwrap();
+    engine.eval("let total = add(5);").unwrap();
+
+    assert_eq!(engine.get_global("total"), Some(Value::Num(15.0)));
+    assert_eq!(engine.eval("total * 2;").unwrap(), Value::Num(30.0));
+    assert_eq!(engine.get_global("missing"), None);
+}
+
+#[test]
+fn errors_say_whether_the_program_ran() {
+    let mut engine = Engine::new();
+    match engine.eval("let = 5;") {
+        Err(Error::Syntax(diagnostics)) => assert!(!diagnostics.is_empty()),
+        other => panic!("expected a syntax error, got {:?}", other),
+    }
+    match engine.eval("missing + 1;") {
+        Err(Error::Runtime(diagnostic)) => {
+            assert_eq!(diagnostic.message(), "Undefined variable \"missing\"")
+        }
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+    assert!(matches!(
+        engine.run_file("does/not/exist.ndl"),
+        Err(Error::Io(_))
+    ));
+}
+
+#[test]
+fn files_import_relative_to_themselves() {
+    let root = Project::new(&[
+        ("lib.ndl", "export let answer = 42;"),
+        ("main.ndl", "import { answer } from \"lib\";\nanswer;"),
+    ]);
+
+    let mut engine = Engine::new();
+    assert_eq!(
+        engine.run_file(root.join("main.ndl")).unwrap(),
+        Value::Num(42.0)
+    );
+}
+
+#[test]
+fn rust_functions_can_be_called_from_scripts() {
+    let mut engine = Engine::new();
+    engine.register_fn("fetch_user", |id: i64| format!("user-{}", id));
+    engine.register_fn("lookup", |key: String| -> Result<i64, String> {
+        match key.as_str() {
+            "a" => Ok(1),
+            _ => Err(format!("no entry for {:?}", key)),
+        }
+    });
+    engine.register_fn("first", |items: Vec<String>| items.into_iter().next());
+    engine.register_variadic_fn("sum", |numbers: Vec<f64>| numbers.iter().sum::<f64>());
+    engine.register_fn("or_zero", |number: Option<f64>| number.unwrap_or(0.0));
+
+    let run = |engine: &mut Engine, source: &str| match engine.eval(source) {
+        Ok(value) => Ok(value),
+        Err(error) => Err(error.diagnostics()[0].message().to_string()),
+    };
+    assert_eq!(
+        run(&mut engine, "fetch_user(7) + lookup(\"a\");"),
+        Ok(Value::Str("user-71".to_string()))
+    );
+    assert_eq!(run(&mut engine, "sum(1, 2, 3.5);"), Ok(Value::Num(6.5)));
+    assert_eq!(run(&mut engine, "first([]);"), Ok(Value::Nil));
+    assert_eq!(
+        run(
+            &mut engine,
+            "or_zero(Some(2)) * 10 + or_zero(None) + or_zero(null) + or_zero(3) * 100;"
+        ),
+        Ok(Value::Num(320.0))
+    );
+    assert_eq!(
+        run(&mut engine, "or_zero(Some(\"2\"));"),
+        Err("or_zero expects a number as argument 1, not variant".to_string())
+    );
+    assert_eq!(
+        run(&mut engine, "lookup(\"b\");"),
+        Err("no entry for \"b\"".to_string())
+    );
+    assert_eq!(
+        run(&mut engine, "fetch_user(1.5);"),
+        Err("fetch_user expects a whole number as argument 1, not 1.5".to_string())
+    );
+    assert_eq!(
+        run(&mut engine, "sum(1, \"2\");"),
+        Err("sum expects a number as argument 2, not string".to_string())
+    );
+    assert_eq!(
+        run(&mut engine, "fetch_user();"),
+        Err("fetch_user expects 1 arguments but got 0".to_string())
+    );
+}
+
+#[test]
+fn rust_can_call_back_into_scripts() {
+    let mut engine = Engine::new();
+    engine
+        .eval(
+            "
+            export let greeting = \"hi\";
+            export let greet = func(name) { ret greeting + \" \" + name; };
+            let counter = func() {
+                let count = 0;
+                ret func() { count += 1; ret count; };
+            };
+            ",
+        )
+        .unwrap();
+
+    let greeting: String = engine.call_fn("greet", ("ann",)).unwrap();
+    assert_eq!(greeting, "hi ann");
+
+    // The handle keeps its closure after the global is rebound
+    let next: Handle = engine.call_fn("counter", ()).unwrap();
+    assert!(next.is_callable());
+    engine.eval("counter = null;").unwrap();
+    let _: f64 = engine.call_handle(&next, ()).unwrap();
+    let count: i64 = engine.call_handle(&next, ()).unwrap();
+    assert_eq!(count, 2);
+
+    let exports: Vec<String> = engine.exports().into_iter().map(|(name, _)| name).collect();
+    assert_eq!(exports, ["greeting", "greet"]);
+    let globals: Vec<String> = engine.globals().into_iter().map(|(name, _)| name).collect();
+    assert_eq!(globals, ["counter", "greet", "greeting"]);
+
+    assert!(matches!(
+        engine.call_fn::<f64>("greet", ("ann",)),
+        Err(Error::Conversion(_))
+    ));
+    assert!(matches!(
+        engine.call_fn::<Value>("missing", ()),
+        Err(Error::Runtime(_))
+    ));
+}
+
+#[test]
+fn operators_outlive_an_eval_only_when_it_parses() {
+    let mut engine = Engine::new();
+    engine
+        .eval("infix <+> 6 = func(a, b) { ret a + b * 2; };")
+        .unwrap();
+    assert_eq!(engine.eval("1 <+> 2;").unwrap(), Value::Num(5.0));
+
+    assert!(matches!(
+        engine.eval("infix <*> 7 = func(a, b) { ret a * b; };\nlet x = ;"),
+        Err(Error::Syntax(_))
+    ));
+    assert!(matches!(engine.eval("1 <*> 2;"), Err(Error::Syntax(_))));
+    assert_eq!(engine.eval("3 <+> 1;").unwrap(), Value::Num(5.0));
+}
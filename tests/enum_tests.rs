@@ -0,0 +1,77 @@
+mod common;
+
+use common::{run, syntax_errors};
+use nd_lang::object::object::Object;
+
+fn errors(source: &str) -> Vec<String> {
+    syntax_errors(source)
+        .iter()
+        .map(|error| error.message().to_string())
+        .collect()
+}
+
+#[test]
+fn variants_are_values() {
+    let source = r#"
+        enum Shape { Circle(r), Rect(w, h), Empty }
+        let circle = Shape.Circle(2);
+        [circle, Shape.Rect("a", [1]), Shape.Empty, circle.r,
+         circle == Shape.Circle(2), circle == Shape.Circle(3), Shape.Empty == Shape.Empty];
+    "#;
+    assert_eq!(
+        run(source).map(|value| value.to_string()),
+        Ok(
+            r#"[Shape.Circle(2), Shape.Rect("a", [1]), Shape.Empty, 2, true, false, true]"#
+                .to_string()
+        )
+    );
+
+    // Enums with the same name declared apart are different types
+    let source = "
+        enum A { X }
+        let first = A.X;
+        enum A { X }
+        first == A.X;
+    ";
+    assert_eq!(run(source), Ok(Object::Bool(false)));
+    assert_eq!(
+        run("enum A { X(v) } A.X(1).w;"),
+        Err("\"A.X\" has no field \"w\"".to_string())
+    );
+}
+
+#[test]
+fn matches_on_variants() {
+    let source = "
+        enum Shape { Circle(r), Rect(w, h), Empty }
+        let area = func(shape) {
+            ret match shape {
+                Shape.Circle(r) => 3 * r * r,
+                Shape.Rect(w, h) if w == h => -1,
+                Shape.Rect(w, h) => w * h,
+                Shape.Empty => 0,
+            };
+        };
+        [area(Shape.Circle(2)), area(Shape.Rect(2, 3)), area(Shape.Rect(2, 2)), area(Shape.Empty)];
+    ";
+    assert_eq!(
+        run(source).map(|value| value.to_string()),
+        Ok("[12, 6, -1, 0]".to_string())
+    );
+
+    let source = "
+        enum State { Idle, Running(pid), Done(code) }
+        match state { State.Idle => 0, State.Running(_) => 1 };
+        match state { State.Idle => 0, State.Running(_) => 1, State.Done(_) => 2, State.Idle => 3 };
+        match state { State.Stopped => 0, State.Done(a, b) => 1, _ => 2 };
+    ";
+    assert_eq!(
+        errors(source),
+        [
+            "Non-exhaustive match: \"State.Done(_)\" is not matched",
+            "Unreachable match arm \"State.Idle\"",
+            "Enum \"State\" has no variant \"Stopped\"",
+            "\"State.Done\" has 1 field but the pattern has 2",
+        ]
+    );
+}
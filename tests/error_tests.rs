@@ -0,0 +1,168 @@
+mod common;
+
+use common::interpret;
+use nd_lang::{
+    errors::diagnostic::Diagnostic, object::object::Object, stdlib::fs::Capabilities, Engine, Error,
+};
+
+fn run(source: &str) -> Result<Object, Diagnostic> {
+    interpret(source, Capabilities::default())
+}
+
+fn value(source: &str) -> String {
+    match run(source) {
+        Ok(value) => value.to_string(),
+        Err(error) => panic!("{}", error),
+    }
+}
+
+#[test]
+fn interpreter_errors_can_be_caught() {
+    let source = r#"
+        let caught = [];
+        let catch_kind = func(f) {
+            try { f(); } catch (e) { ret e.kind; }
+            ret "nothing";
+        };
+        [
+            catch_kind(func() { ret 1 + null; }),
+            catch_kind(func() { ret 1 / 0; }),
+            catch_kind(func() { ret 5 % 0; }),
+            catch_kind(func() { ret missing; }),
+            catch_kind(func() { ret [1][3]; }),
+            catch_kind(func() { ret 3(); }),
+            catch_kind(func() { ret "abc".upper(1); }),
+            catch_kind(func() { ret 1; }),
+        ];
+    "#;
+    assert_eq!(
+        value(source),
+        r#"["TypeError", "ZeroDivisionError", "ZeroDivisionError", "NameError", "IndexError", "TypeError", "TypeError", "nothing"]"#
+    );
+}
+
+#[test]
+fn finally_always_runs() {
+    let source = r#"
+        let log = "log: ";
+        let note = func(entry) { log = log + entry + "; "; };
+        let check = func(n) {
+            if (n < 0) { throw Error("negative", "ValueError"); }
+            ret n;
+        };
+        let attempt = func(n) {
+            try {
+                ret check(n);
+            } catch (e) {
+                note(e.kind + ": " + e.message);
+                ret -1;
+            } finally {
+                note("done " + n);
+            }
+        };
+        try {
+            try { throw "inner"; } finally { note("cleanup"); }
+        } catch (e) {
+            note(e);
+        }
+        [attempt(2), attempt(-2), log];
+    "#;
+    assert_eq!(
+        value(source),
+        "[2, -1, \"log: cleanup; Error: inner; done 2; ValueError: negative; done -2; \"]"
+    );
+}
+
+#[test]
+fn errors_carry_a_stack_trace() {
+    let source = "
+        let inner = func() {
+            throw Error(\"deep\", \"Custom\");
+        };
+        let outer = func() {
+            inner();
+        };
+        outer();
+    ";
+    let error = run(source).unwrap_err();
+    assert_eq!(error.kind(), Some("Custom"));
+    assert_eq!(error.message(), "deep");
+    assert_eq!(
+        error.trace(),
+        [
+            "at inner (<eval>:3)",
+            "at outer (<eval>:6:18)",
+            "at <script> (<eval>:8:14)"
+        ]
+    );
+
+    // A caught error thrown again keeps where it was first thrown
+    let source = "
+        let first = null;
+        try { throw Error(\"x\"); } catch (e) { first = e; }
+        let again = null;
+        try { throw first; } catch (e) { again = e; }
+        [again == first, again.trace];
+    ";
+    assert_eq!(value(source), r#"[true, ["at <script> (<eval>:3)"]]"#);
+}
+
+#[test]
+fn deep_recursion_is_a_clean_error() {
+    // The default limit holds even on the small stacks of test threads
+    let mut engine = Engine::new();
+    let source = "let down = func(n) { ret down(n + 1); };\ndown(0);";
+    let error = match engine.eval_named(source, "deep.ndl") {
+        Err(Error::Runtime(error)) => error,
+        other => panic!("expected a runtime error, got {:?}", other),
+    };
+    assert_eq!(error.kind(), Some("RecursionError"));
+    assert_eq!(
+        error.message(),
+        "Stack overflow: more than 1000 nested calls"
+    );
+    assert_eq!(
+        error.trace(),
+        [
+            "at down (deep.ndl:1:30)",
+            "... repeated 999 more times",
+            "at <script> (deep.ndl:2:5)"
+        ]
+    );
+
+    // Recursion within the limit still works
+    let source =
+        "let count = func(n) { if (n == 0) { ret 0; } ret 1 + count(n - 1); };\ncount(900);";
+    assert_eq!(engine.eval(source).unwrap(), Object::Num(900.0));
+}
+
+#[test]
+fn deeply_nested_expressions_do_not_overflow_the_stack() {
+    // Test threads have small stacks, and used to abort on these
+    let mut engine = Engine::new();
+    let depth = 5000;
+    let source = format!("{}1{};", "(".repeat(depth), ")".repeat(depth));
+    assert_eq!(engine.eval(&source).unwrap(), Object::Num(1.0));
+    let source = format!(
+        "let id = func(x) {{ ret x; }};\n{}2{};",
+        "id(".repeat(depth),
+        ")".repeat(depth)
+    );
+    assert_eq!(engine.eval(&source).unwrap(), Object::Num(2.0));
+}
+
+#[test]
+fn errors_point_at_the_call_they_come_from() {
+    let error = run("let f = func(a) { ret a; };\nlet x = 1 + f();").unwrap_err();
+    assert_eq!(error.message(), "Expected 1 arguments but got 0");
+    assert_eq!((error.line(), error.position()), (1, 14));
+    assert_eq!(
+        error.trace(),
+        ["at f (<eval>:2:14)", "at <script> (<eval>:2:14)"]
+    );
+    // The header shows the same line as the innermost call
+    assert_eq!(
+        error.to_string(),
+        "[2::14] TypeError: Expected 1 arguments but got 0"
+    );
+}
@@ -0,0 +1,97 @@
+use nd_lang::formatter::formatter::{diff_lines, format_source, FormatConfig, LineChange};
+
+fn format_with(source: &str, config: &FormatConfig) -> String {
+    let formatted = format_source(source, "test.ndl".to_string(), config).unwrap();
+    // Formatting again changes nothing
+    assert_eq!(
+        format_source(&formatted, "test.ndl".to_string(), config).unwrap(),
+        formatted
+    );
+    formatted
+}
+
+fn format(source: &str) -> String {
+    format_with(source, &FormatConfig::default())
+}
+
+#[test]
+fn statements_are_laid_out_with_blank_lines_kept() {
+    let source =
+        "let  x=1;\n\n\nif x>0 { x = x-1; } else { x = 2; }\nlet f = func(a) { ret a*2; };";
+    assert_eq!(
+        format(source),
+        "let x = 1;\n\nif x > 0 {\n    x = x - 1;\n} else {\n    x = 2;\n}\nlet f = func(a) {\n    ret a * 2;\n};\n"
+    );
+}
+
+#[test]
+fn trailing_comments_stay_with_their_statement() {
+    let source = "if x { y = 1; } else { y = 2; } // pick y\n\
+                  let z = match x { true => 1, false => 2 }; // arms\n\
+                  let w = 3; // three";
+    assert_eq!(
+        format(source),
+        "if x {\n    y = 1;\n} else {\n    y = 2;\n} // pick y\n\
+         let z = match x {\n    true => 1,\n    false => 2,\n}; // arms\n\
+         let w = 3; // three\n"
+    );
+
+    let source = "// header\nlet a = 1;\n\n// about b\nlet b = [a, // first\n    2];\n";
+    assert_eq!(
+        format(source),
+        "// header\nlet a = 1;\n\n// about b\nlet b = [a, 2]; // first\n"
+    );
+}
+
+#[test]
+fn calls_and_lists_wrap_at_the_line_width() {
+    let config = FormatConfig {
+        line_width: 28,
+        ..FormatConfig::default()
+    };
+    assert_eq!(
+        format_with("draw(first_value, second_value);", &config),
+        "draw(\n    first_value,\n    second_value\n);\n"
+    );
+    assert_eq!(
+        format_with("let items = [alpha, beta, gamma, delta];", &config),
+        "let items = [\n    alpha,\n    beta,\n    gamma,\n    delta\n];\n"
+    );
+    assert_eq!(
+        format_with("f(g(alpha_value, beta_value), 1);", &config),
+        "f(\n    g(\n        alpha_value,\n        beta_value\n    ),\n    1\n);\n"
+    );
+    // What fits stays on one line
+    assert_eq!(
+        format_with("draw(a, [1, 2]);", &config),
+        "draw(a, [1, 2]);\n"
+    );
+}
+
+#[test]
+fn long_binary_expressions_break_before_operators() {
+    let config = FormatConfig {
+        line_width: 24,
+        indent_width: 2,
+    };
+    assert_eq!(
+        format_with("let total = first + second + third;", &config),
+        "let total = first\n  + second\n  + third;\n"
+    );
+}
+
+#[test]
+fn check_diffs_line_up_around_added_and_removed_lines() {
+    let old = "let a = 1;\nlet b = 2;\nlet  c = 3;\nlet e = 5;\n";
+    let new = "let a = 1;\n\nlet b = 2;\nlet c = 3;\nlet e = 5;\nlet f = 6;\n";
+    assert_eq!(
+        diff_lines(old, new),
+        [
+            LineChange::Added(2, ""),
+            LineChange::Removed(3, "let  c = 3;"),
+            LineChange::Added(4, "let c = 3;"),
+            LineChange::Added(6, "let f = 6;"),
+        ]
+    );
+    assert_eq!(diff_lines(new, new), []);
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use common::run;
+use nd_lang::object::object::Object;
+
+#[test]
+fn functions_close_over_their_scope() {
+    let source = "
+        let counter = func() {
+            let count = 0;
+            ret func() { count += 1; ret count; };
+        };
+        let next = counter();
+        next();
+        next();
+    ";
+    assert_eq!(run(source), Ok(Object::Num(2.0)));
+}
+
+#[test]
+fn declared_infix_operators_use_their_precedence() {
+    let source = "
+        infix |> 2 left = func(value, f) { ret f(value); };
+        infix ** 8 right = func(a, b) { ret a ^ b; };
+        let double = func(x) { ret x * 2; };
+        1 + 2 ** 3 ** 2 |> double |> double;
+    ";
+    assert_eq!(run(source), Ok(Object::Num(2052.0)));
+}
+
+#[test]
+fn declared_prefix_operators() {
+    let source = "
+        prefix ~ = func(a) { ret !a; };
+        ~false;
+    ";
+    assert_eq!(run(source), Ok(Object::Bool(true)));
+}
+
+#[test]
+fn declared_prefix_operators_take_an_optional_precedence() {
+    // Binding looser than `+`, `~~` negates the whole sum
+    let source = "
+        prefix ~~ 5 = func(a) { ret -a; };
+        ~~ 1 + 2;
+    ";
+    assert_eq!(run(source), Ok(Object::Num(-3.0)));
+    let source = "
+        prefix ~~ = func(a) { ret -a; };
+        ~~ 1 + 2;
+    ";
+    assert_eq!(run(source), Ok(Object::Num(1.0)));
+}
+
+#[test]
+fn builtin_operators_cannot_be_redeclared() {
+    let source = "infix + 6 = func(a, b) { ret a; };";
+    assert_eq!(
+        run(source),
+        Err("Cannot declare \"+\" as an operator".to_string())
+    );
+}
+
+#[test]
+fn and_is_evaluated_before_or() {
+    assert_eq!(run("true || false && false;"), Ok(Object::Bool(true)));
+}
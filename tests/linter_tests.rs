@@ -0,0 +1,118 @@
+use nd_lang::{
+    linter::linter::{Level, Lint, LintConfig, Linter},
+    parser::parser::Parser,
+    scanner::scanner::Scanner,
+};
+
+fn lint_with(source: &str, config: &LintConfig) -> Vec<Lint> {
+    let mut parser = Parser::new(Scanner::new(source, "test".to_string()));
+    let statements = parser.parse();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+    Linter::new(config).lint(&statements, parser.scanner().comments())
+}
+
+///Returns the rules that found something, with their lines
+fn lint(source: &str) -> Vec<(usize, &'static str)> {
+    lint_with(source, &LintConfig::default())
+        .into_iter()
+        .map(|lint| (lint.line, lint.rule))
+        .collect()
+}
+
+#[test]
+fn finds_unused_and_never_reassigned_variables() {
+    let source = "
+const unused = 1;
+const _skipped = 2;
+let count = 0;
+count += 1;
+let fact = func(n) { ret n * fact(n - 1); };
+const used = fact(count);
+export const shared = used;
+";
+    assert_eq!(lint(source), [(2, "unused-variable"), (6, "prefer-const")]);
+}
+
+#[test]
+fn finds_shadowed_bindings() {
+    let source = "
+const x = 1;
+const f = func(x) { ret x; };
+{ const x = 2; f(x); }
+f(x);
+";
+    assert_eq!(
+        lint(source),
+        [(3, "shadowed-binding"), (4, "shadowed-binding")]
+    );
+}
+
+#[test]
+fn finds_unreachable_code_and_empty_blocks() {
+    let source = "
+const f = func(n) {
+    if (n) { ret 1; } else { throw \"no\"; }
+    f(1);
+    f(2);
+};
+f(0);
+{}
+{
+    // Filled in later
+}
+";
+    assert_eq!(lint(source), [(4, "unreachable-code"), (8, "empty-block")]);
+}
+
+#[test]
+fn finds_suspicious_conditions_and_assignments() {
+    let source = "
+let x = 1;
+x = (x);
+if x = 2 { x; }
+if (x = 3) { x; }
+if ((x = 4)) { x; }
+if (1 + 1 == 2) { x; }
+if (x > 1) { x; }
+";
+    let lints = lint_with(source, &LintConfig::default());
+    let found: Vec<(usize, &str, Level)> = lints
+        .iter()
+        .map(|lint| (lint.line, lint.rule, lint.level))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (3, "self-assignment", Level::Deny),
+            (4, "assignment-in-condition", Level::Warn),
+            (7, "constant-condition", Level::Warn),
+        ]
+    );
+    assert_eq!(lints[2].message, "The condition is always true");
+}
+
+#[test]
+fn rules_can_be_configured_and_suppressed() {
+    let source = "
+// lint-allow-file shadowed-binding
+const a = 1;
+{ const a = 2; a; }
+a;
+const b = 1; // lint-allow
+// lint-allow: unused-variable, empty-block
+const c = 1;
+const d = 1;
+";
+    assert_eq!(lint(source), [(9, "unused-variable")]);
+
+    let mut config = LintConfig::default();
+    assert!(config.set("unused-variable", Level::Allow));
+    assert!(config.set("empty-block", Level::Deny));
+    assert!(!config.set("no-such-rule", Level::Deny));
+    let lints = lint_with("const a = 1;\n{}\n", &config);
+    assert_eq!(lints.len(), 1);
+    assert_eq!(
+        lints[0].to_string(),
+        "2: error[empty-block] This block is empty; remove it or say why in a comment"
+    );
+}
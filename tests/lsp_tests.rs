@@ -0,0 +1,139 @@
+use std::io::Cursor;
+
+use nd_lang::lsp::server::{read_message, write_message, Server};
+use serde_json::{json, Value};
+
+const URI: &str = "file:///main.ndl";
+
+fn open(server: &mut Server, text: &str) -> Value {
+    let mut replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": { "textDocument": { "uri": URI, "text": text } },
+    }));
+    assert_eq!(replies.len(), 1);
+    replies.remove(0)
+}
+
+fn request(server: &mut Server, method: &str, line: usize, character: usize) -> Value {
+    let mut replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "id": 7,
+        "method": method,
+        "params": {
+            "textDocument": { "uri": URI },
+            "position": { "line": line, "character": character },
+        },
+    }));
+    assert_eq!(replies[0]["id"], 7);
+    replies.remove(0)["result"].take()
+}
+
+#[test]
+fn every_edit_publishes_diagnostics() {
+    let mut server = Server::new();
+    let published = open(&mut server, "let x = ;");
+    assert_eq!(published["method"], "textDocument/publishDiagnostics");
+    let diagnostics = published["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["range"]["start"]["line"], 0);
+
+    let fixed = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didChange",
+        "params": {
+            "textDocument": { "uri": URI },
+            "contentChanges": [{ "text": "let x = 1;" }],
+        },
+    }));
+    assert_eq!(fixed[0]["params"]["diagnostics"], json!([]));
+}
+
+#[test]
+fn hover_and_definition_find_the_binding() {
+    let mut server = Server::new();
+    open(&mut server, "const limit = 10;\nlet n = limit + 1;");
+
+    let definition = request(&mut server, "textDocument/definition", 1, 9);
+    assert_eq!(definition["uri"], URI);
+    assert_eq!(
+        definition["range"],
+        json!({ "start": { "line": 0, "character": 6 }, "end": { "line": 0, "character": 11 } })
+    );
+
+    let hover = request(&mut server, "textDocument/hover", 1, 9);
+    let text = hover["contents"]["value"].as_str().unwrap();
+    assert!(text.contains("const limit"), "{}", text);
+    assert!(text.contains("10"), "{}", text);
+
+    // Nothing is bound at a keyword
+    assert_eq!(
+        request(&mut server, "textDocument/hover", 1, 1),
+        Value::Null
+    );
+    // Nor just past the end of a name
+    assert_eq!(
+        request(&mut server, "textDocument/hover", 1, 13),
+        Value::Null
+    );
+    assert_eq!(
+        request(&mut server, "textDocument/definition", 0, 11),
+        Value::Null
+    );
+}
+
+#[test]
+fn symbols_and_completions_list_the_bindings() {
+    let mut server = Server::new();
+    open(
+        &mut server,
+        "let a = 1;\nconst b = 2;\nlet f = func() {\n  let inner = 3;\n};\n",
+    );
+
+    let symbols = request(&mut server, "textDocument/documentSymbol", 0, 0);
+    let names: Vec<&str> = symbols
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|symbol| symbol["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["a", "b", "f", "inner"]);
+
+    let completions = request(&mut server, "textDocument/completion", 5, 0);
+    let labels: Vec<&str> = completions
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap())
+        .collect();
+    assert!(labels.contains(&"let") && labels.contains(&"b"));
+    // `inner` is out of scope after its block
+    assert!(!labels.contains(&"inner"));
+}
+
+#[test]
+fn unknown_methods_and_closed_documents_are_errors() {
+    let mut server = Server::default();
+    let reply = server.handle(&json!({ "jsonrpc": "2.0", "id": 1, "method": "no/such" }));
+    assert_eq!(reply[0]["error"]["code"], -32601);
+
+    let reply = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "textDocument/hover",
+        "params": { "textDocument": { "uri": URI }, "position": { "line": 0, "character": 0 } },
+    }));
+    assert_eq!(reply[0]["error"]["code"], -32600);
+}
+
+#[test]
+fn messages_are_framed_with_content_length() {
+    let message = json!({ "jsonrpc": "2.0", "id": 1, "method": "shutdown" });
+    let mut framed = Vec::new();
+    write_message(&mut framed, &message).unwrap();
+    assert!(framed.starts_with(b"Content-Length: "));
+
+    let mut reader = Cursor::new(framed);
+    assert_eq!(read_message(&mut reader).unwrap(), Some(message));
+    assert_eq!(read_message(&mut reader).unwrap(), None);
+}
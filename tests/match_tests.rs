@@ -0,0 +1,69 @@
+mod common;
+
+use common::{run, syntax_errors};
+
+fn diagnostics(source: &str) -> Vec<(usize, String)> {
+    syntax_errors(source)
+        .iter()
+        .map(|error| (error.line(), error.message().to_string()))
+        .collect()
+}
+
+#[test]
+fn arms_are_tried_in_order() {
+    let source = r#"
+        let describe = func(x) {
+            ret match x {
+                0 => "zero",
+                -9..0 => "negative digit",
+                1..=9 => "digit",
+                [] => "empty",
+                [first, ..rest] => first + rest,
+                (a, b) => "unreachable",
+                n if n > 100 => "big",
+                _ => "other",
+            };
+        };
+        [describe(0), describe(-3), describe(9), describe(500), describe([]),
+         describe(["a", 1, 2]), describe(50)];
+    "#;
+    let expected = r#"["zero", "negative digit", "digit", "big", "empty", "a[1, 2]", "other"]"#;
+    assert_eq!(
+        diagnostics(source),
+        [(8, "Unreachable match arm \"[a, b]\"".to_string())]
+    );
+    let source = source.replace("(a, b) => \"unreachable\",", "");
+    assert_eq!(
+        run(&source).map(|value| value.to_string()),
+        Ok(expected.to_string())
+    );
+}
+
+#[test]
+fn matches_must_be_exhaustive() {
+    let source = "
+        match flag { true => 1 };
+        match flag { true => 1, false if ready => 0 };
+        match items { [] => 0, [only] => 1 };
+        match n { 1..10 => 0 };
+        match flag { true => 1, false => 0 };
+        match items { [] => 0, [_, ..] => 1 };
+    ";
+    let message = |witness: &str| format!("Non-exhaustive match: \"{}\" is not matched", witness);
+    assert_eq!(
+        diagnostics(source),
+        [
+            (1, message("false")),
+            (2, message("false")),
+            (3, message("[_, _, ..]")),
+            (4, message("_")),
+        ]
+    );
+}
+
+#[test]
+fn unmatched_values_are_runtime_errors() {
+    // Arms for both bools cover a bool, which the value is not
+    let source = "match [1, 2] { true => 1, false => 0 };";
+    assert_eq!(run(source), Err("No match arm matches [1, 2]".to_string()));
+}
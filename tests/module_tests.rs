@@ -0,0 +1,104 @@
+mod common;
+
+use std::path::Path;
+
+use common::Project;
+use nd_lang::{
+    interpreter::interpreter::Interpreter, object::object::Object, parser::parser::Parser,
+    scanner::scanner::Scanner, stdlib::fs::Capabilities,
+};
+
+fn run(root: &Path, source: &str) -> Result<Object, String> {
+    run_with(root, source, Capabilities::default())
+}
+
+fn run_with(root: &Path, source: &str, capabilities: Capabilities) -> Result<Object, String> {
+    let mut parser = Parser::new(Scanner::new(source, "main.ndl".to_string()));
+    let statements = parser.parse();
+    if let Some(error) = parser.errors().first() {
+        return Err(error.message().to_string());
+    }
+    let mut interpreter = Interpreter::new();
+    interpreter.set_file(&root.join("main.ndl"));
+    interpreter.add_search_path(root.join("lib"));
+    interpreter.set_capabilities(capabilities);
+    interpreter
+        .interpret(&statements)
+        .map_err(|error| error.message().to_string())
+}
+
+#[test]
+fn imports_resolve_next_to_the_file_then_in_search_paths() {
+    let root = Project::new(&[
+        ("util.ndl", "export let double = func(x) { ret x * 2; };"),
+        (
+            "lib/shapes.ndl",
+            "import \"../util\" as u;\nexport let area = func(w, h) { ret u.double(w * h); };",
+        ),
+    ]);
+    let source = "
+        import \"util.ndl\" as util;
+        import { area } from \"shapes\";
+        util.double(1) + area(2, 3);
+    ";
+    assert_eq!(run(&root, source), Ok(Object::Num(14.0)));
+}
+
+#[test]
+fn modules_are_evaluated_once() {
+    let root = Project::new(&[
+        ("counter.ndl", "export let made = func() { ret 0; };"),
+        (
+            "other.ndl",
+            "import \"counter\" as c;\nexport let made = c.made;",
+        ),
+    ]);
+    let source = "
+        import \"counter\" as c;
+        import { made } from \"other\";
+        c.made == made;
+    ";
+    assert_eq!(run(&root, source), Ok(Object::Bool(true)));
+}
+
+#[test]
+fn only_exports_can_be_imported() {
+    let root = Project::new(&[("util.ndl", "let hidden = 1;")]);
+    assert_eq!(
+        run(&root, "import { hidden } from \"util\";"),
+        Err("Module \"util.ndl\" does not export \"hidden\"".to_string())
+    );
+}
+
+#[test]
+fn import_cycles_are_reported() {
+    let root = Project::new(&[
+        ("main.ndl", "import \"a\" as a;"),
+        ("a.ndl", "import \"b\" as b;"),
+        ("b.ndl", "import \"main\" as main;"),
+    ]);
+    assert_eq!(
+        run(&root, "import \"a\" as a;"),
+        Err("Circular import: main.ndl -> a.ndl -> b.ndl -> main.ndl".to_string())
+    );
+}
+
+#[test]
+fn imports_from_elsewhere_need_permission_to_read_the_module() {
+    let root = Project::new(&[("lib/broken.ndl", "let token = secret_value ?? 1;")]);
+    let elsewhere = Project::new(&[("secret.ndl", "export let key = 1;")]);
+    let source = format!("import \"{}\" as s;", elsewhere.join("secret").display());
+    assert_eq!(
+        run(&root, &source),
+        Err("Not allowed to import \"secret.ndl\"".to_string())
+    );
+
+    let mut capabilities = Capabilities::default();
+    capabilities.allow_read(&*elsewhere);
+    assert_eq!(run_with(&root, &source, capabilities), Ok(Object::Nil));
+    // Syntax errors do not quote the module's source
+    assert_eq!(
+        run(&root, "import \"broken\" as b;"),
+        Err("Module \"broken.ndl\" has a syntax error on line 1".to_string())
+    );
+}
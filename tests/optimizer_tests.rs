@@ -0,0 +1,118 @@
+use nd_lang::{
+    ast::{expr::Expr, stmt::Stmt},
+    optimizer::optimizer::{OptLevel, Optimizer},
+    parser::parser::Parser,
+    scanner::scanner::Scanner,
+    Engine, Value,
+};
+
+fn optimize_at(source: &str, level: OptLevel) -> Vec<Stmt> {
+    let mut parser = Parser::new(Scanner::new(source, "test".to_string()));
+    let statements = parser.parse();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+    Optimizer::new(level).optimize(statements)
+}
+
+fn optimize(source: &str) -> Vec<String> {
+    optimize_at(source, OptLevel::Full)
+        .iter()
+        .map(|stmt| match stmt {
+            Stmt::ExpressionStmt { expression, .. } => expression.to_string(),
+            stmt => stmt.to_string(),
+        })
+        .collect()
+}
+
+///Returns the statements of the body of the function `stmt` declares
+fn body(stmt: &Stmt) -> Vec<String> {
+    match stmt {
+        Stmt::VarDeclarationStmt {
+            assignedValue: Expr::Function { body, .. },
+            ..
+        } => body.iter().map(Stmt::to_string).collect(),
+        stmt => panic!("{} does not declare a function", stmt),
+    }
+}
+
+#[test]
+fn folds_constant_expressions() {
+    assert_eq!(optimize("5 + 5 * 6 * 7 ^ 5 * 6;"), ["3025265"]);
+    assert_eq!(optimize("-(2 - 3) * 4;"), ["4"]);
+    assert_eq!(optimize("!(1 < 2) || \"yes\";"), ["\"yes\""]);
+    assert_eq!(optimize("\"n\" + 1 * 2;"), ["\"n2\""]);
+    assert_eq!(optimize("a + 2 * 3;"), ["(a + 6)"]);
+}
+
+#[test]
+fn keeps_expressions_that_fail() {
+    assert_eq!(optimize("1 / 0;"), ["(1 / 0)"]);
+    assert_eq!(optimize("\"a\" * 2;"), ["(\"a\" * 2)"]);
+
+    let mut engine = Engine::new();
+    let error = engine.eval("let x = 2 + 1 % 0;").unwrap_err();
+    assert!(error.to_string().contains("Division by zero"), "{}", error);
+}
+
+#[test]
+fn applies_identities_to_numbers_only() {
+    assert_eq!(optimize("(a - 1) * 1;"), ["(a - 1)"]);
+    assert_eq!(optimize("0 + a * b;"), ["(a * b)"]);
+    assert_eq!(optimize("!!(a < b);"), ["(a < b)"]);
+    // `a` could be a string, or a value whose truthiness `!!` turns
+    // into a bool
+    assert_eq!(optimize("a * 1;"), ["(a * 1)"]);
+    assert_eq!(optimize("a + 0;"), ["(a + 0)"]);
+    assert_eq!(optimize("!!a;"), ["(!(!a))"]);
+}
+
+#[test]
+fn propagates_constants_in_scope() {
+    let statements = optimize("{ const k = 2 * 3; k + 1; let f = func(x) { ret x + k; }; }");
+    assert_eq!(statements[0].lines().nth(2), Some("    7"));
+
+    let statements = optimize_at(
+        "{ const k = 6; let f = func(x) { ret x + k; }; let g = func(k) { ret k; }; }",
+        OptLevel::Full,
+    );
+    let Stmt::BlockStmt { body: block, .. } = &statements[0] else {
+        panic!("expected a block");
+    };
+    assert_eq!(body(&block[1]), ["ret (x + 6)"]);
+    assert_eq!(body(&block[2]), ["ret k"]);
+}
+
+#[test]
+fn leaves_constants_that_may_change() {
+    // Declared twice in the same scope
+    assert_eq!(
+        optimize("const k = 1; k; let k = 2; k;"),
+        ["let const k = 1", "k", "let k = 2", "k"]
+    );
+    // Globals read from functions, which later runs may declare again
+    let statements = optimize_at("const k = 1; let f = func() { ret k; };", OptLevel::Full);
+    assert_eq!(body(&statements[1]), ["ret k"]);
+    // Constants are only propagated at the highest level
+    let statements = optimize_at("const k = 1; k;", OptLevel::Fold);
+    assert_eq!(statements[1].to_string(), "k");
+}
+
+#[test]
+fn optimized_programs_give_the_same_results() {
+    let source = "
+        const base = 10;
+        let scale = func(x) { const factor = 2 * 1; ret (x - 0) * factor + base; };
+        let total = 0;
+        { const base = 1; total += scale(base); }
+        total + scale(3) * 1;
+    ";
+    for level in [OptLevel::None, OptLevel::Fold, OptLevel::Full] {
+        let mut engine = Engine::new();
+        engine.set_opt_level(level);
+        assert_eq!(
+            engine.eval(source).unwrap(),
+            Value::Num(28.0),
+            "{:?}",
+            level
+        );
+    }
+}
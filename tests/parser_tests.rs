@@ -1 +1,119 @@
+use nd_lang::{
+    ast::{expr::Expr, stmt::Stmt},
+    parser::{
+        lookups::{Assoc, OperatorTable, PREC},
+        parser::Parser,
+    },
+    scanner::scanner::Scanner,
+    tokens::token_type::TokenType,
+};
 
+fn parse_with(source: &str, operators: OperatorTable) -> Vec<String> {
+    let mut parser = Parser::with_operators(Scanner::new(source, "test".to_string()), operators);
+    let statements = parser.parse();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+    statements
+        .iter()
+        .map(|stmt| match stmt {
+            Stmt::ExpressionStmt { expression, .. } => expression.to_string(),
+            stmt => stmt.to_string(),
+        })
+        .collect()
+}
+
+fn parse(source: &str) -> Vec<String> {
+    parse_with(source, OperatorTable::new())
+}
+
+#[test]
+fn binary_operators_group_by_associativity() {
+    assert_eq!(parse("a - b - c;"), ["((a - b) - c)"]);
+    assert_eq!(parse("a ^ b ^ c;"), ["(a ^ (b ^ c))"]);
+    assert_eq!(parse("a + b * c - d;"), ["((a + (b * c)) - d)"]);
+    assert_eq!(
+        parse("x = y = 1 + 2;"),
+        ["Assigned x = Assigned y = (1 + 2)"]
+    );
+}
+
+#[test]
+fn and_binds_tighter_than_or() {
+    assert_eq!(parse("a || b && c;"), ["(a || (b && c))"]);
+    assert_eq!(parse("a && b || c && d;"), ["((a && b) || (c && d))"]);
+    assert_eq!(parse("a == b && c < d;"), ["((a == b) && (c < d))"]);
+}
+
+#[test]
+fn declared_precedence_levels_keep_their_numbers() {
+    // 6 is the level of `+` and 4 the level of `||`, below `&&`
+    let parsed = parse(
+        "infix <+> 6 left = func(a, b) { ret a; };
+        infix <|> 4 left = func(a, b) { ret a; };
+        a <+> b + c;
+        a + b <+> c * d;
+        a <|> b && c;",
+    );
+    assert!(
+        parsed[0].starts_with("infix <+> 6 left = "),
+        "{}",
+        parsed[0]
+    );
+    assert_eq!(
+        parsed[2..],
+        [
+            "((a <+> b) + c)",
+            "((a + b) <+> (c * d))",
+            "(a <|> (b && c))"
+        ]
+    );
+}
+
+#[test]
+fn tokens_without_led_end_the_expression() {
+    let mut parser = Parser::new(Scanner::new("5 5;", "test".to_string()));
+    // Used to panic looking up a led handler for NUMBER
+    parser.parse();
+    assert!(!parser.errors().is_empty());
+}
+
+#[test]
+fn parsers_own_their_operator_tables() {
+    // `%` groups to the right and binds looser than `+` in this grammar
+    let mut custom = OperatorTable::new();
+    custom.register_infix(
+        TokenType::MODULO,
+        PREC::Logical,
+        Assoc::Right,
+        |parser, left| {
+            let op = parser.advance_and_get_current().to_static();
+            let right = parser.parse_expr_bp(u8::from(PREC::Logical) - 1);
+            Expr::BinaryOp {
+                left: Box::new(left),
+                op,
+                right: Box::new(right),
+            }
+        },
+    );
+
+    let source = "a % b % c + d;";
+    assert_eq!(parse_with(source, custom), ["(a % (b % (c + d)))"]);
+    assert_eq!(parse(source), ["(((a % b) % c) + d)"]);
+}
+
+#[test]
+fn test_blocks_need_a_name() {
+    assert_eq!(
+        parse("test \"adds\" { assert(1 + 1 == 2); }"),
+        ["test \"adds\" Block Stmt: {\n    assert(((1 + 1) == 2))\n}"]
+    );
+    // `test` is only a keyword before a string
+    assert_eq!(parse("let test = 1; test + 1;")[1], "(test + 1)");
+}
+
+#[test]
+fn prefix_declarations_keep_their_precedence() {
+    let parsed = parse("prefix ~~ 5 = func(a) { ret a; };\n~~ a + b;");
+    assert!(parsed[0].starts_with("prefix ~~ 5 = "), "{}", parsed[0]);
+    assert_eq!(parsed[1], "(~~(a + b))");
+    assert!(parse("prefix ~~ = func(a) { ret a; };")[0].starts_with("prefix ~~ = "));
+}
@@ -0,0 +1,57 @@
+use std::{cell::RefCell, rc::Rc};
+
+use nd_lang::{profiler::profiler::Profiler, Engine};
+
+fn profile(source: &str) -> Profiler {
+    let profiler = Rc::new(RefCell::new(Profiler::new()));
+    let mut engine = Engine::new();
+    engine.set_profiler(profiler.clone());
+    let _ = engine.eval_named(source, "main.ndl");
+    let mut profiler = profiler.borrow_mut();
+    profiler.finish();
+    std::mem::take(&mut *profiler)
+}
+
+#[test]
+fn counts_calls_and_lines() {
+    let profiler = profile(
+        "let fib = func(n) {\n    if (n < 2) { ret n; }\n    ret fib(n - 1) + fib(n - 2);\n};\nlet x = fib(5);\n",
+    );
+
+    let functions = profiler.functions();
+    let fib = functions.iter().find(|f| f.name == "fib").unwrap();
+    assert_eq!(fib.calls, 15);
+    assert!(fib.exclusive <= fib.inclusive);
+    let script = functions.iter().find(|f| f.name == "<script>").unwrap();
+    assert_eq!(script.calls, 1);
+    assert!(fib.inclusive <= script.inclusive);
+
+    let lines = profiler.lines();
+    let hits = |line| lines.iter().find(|l| l.line == line).map(|l| l.hits);
+    assert_eq!(hits(1), Some(1));
+    assert_eq!(hits(3), Some(7));
+    assert_eq!(hits(5), Some(1));
+}
+
+#[test]
+fn calls_stopped_by_errors_are_ended() {
+    let profiler = profile("let fail = func() { ret 1 / nothing; };\nfail();\n");
+    let fail = profiler.functions().into_iter().find(|f| f.name == "fail");
+    assert_eq!(fail.map(|f| f.calls), Some(1));
+}
+
+#[test]
+fn collapses_call_stacks() {
+    let profiler =
+        profile("let inner = func() { ret 1; };\nlet outer = func() { ret inner(); };\nouter();\nouter();\n");
+    let collapsed = profiler.collapsed();
+    let stacks: Vec<&str> = collapsed
+        .lines()
+        .map(|line| line.rsplit_once(' ').unwrap().0)
+        .collect();
+    assert_eq!(
+        stacks,
+        ["<script>", "<script>;outer", "<script>;outer;inner"]
+    );
+    assert!(profiler.report(10).contains("outer"));
+}
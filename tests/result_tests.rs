@@ -0,0 +1,91 @@
+mod common;
+
+use common::run;
+use nd_lang::{lsp::analysis::Analysis, object::object::Object};
+
+#[test]
+fn question_mark_returns_errors_early() {
+    let source = r#"
+        let half = func(x) {
+            if (x % 2 == 0) { ret Ok(x / 2); }
+            ret Err("odd");
+        };
+        let quarter = func(x) { let h = half(x)?; ret Ok(half(h)?); };
+        match quarter(12) { Ok(v) => v, Err(e) => e } + " " + match quarter(6) { Ok(v) => v, Err(e) => e };
+    "#;
+    assert_eq!(run(source).unwrap().to_string(), "3 odd");
+
+    let source = "let first = func(xs) { ret match xs { [] => None, [x, ..] => Some(x) }; };
+        let second = func(xs) { let x = first(xs)?; ret Some(x + 1); };
+        second([]);";
+    assert_eq!(run(source).unwrap().to_string(), "None");
+    assert!(run("5?;")
+        .unwrap_err()
+        .contains("needs a Result or an Option"));
+}
+
+#[test]
+fn helper_methods() {
+    assert_eq!(run("Ok(1).is_ok();").unwrap(), Object::Bool(true));
+    assert_eq!(run("Err(1).is_ok();").unwrap(), Object::Bool(false));
+    assert_eq!(run("None.is_none();").unwrap(), Object::Bool(true));
+    assert_eq!(run("Err(1).unwrap_or(7);").unwrap(), Object::Num(7.0));
+    assert_eq!(run("Some(2).unwrap();").unwrap(), Object::Num(2.0));
+    assert_eq!(
+        run("Some(2).map(func(x) { ret x * 10; });")
+            .unwrap()
+            .to_string(),
+        "Some(20)"
+    );
+    assert_eq!(
+        run("Err(2).map(func(x) { ret x * 10; });")
+            .unwrap()
+            .to_string(),
+        "Err(2)"
+    );
+    assert!(run("None.unwrap();")
+        .unwrap_err()
+        .contains("unwrap on None"));
+    assert!(run("None.is_ok();").unwrap_err().contains("no method"));
+}
+
+#[test]
+fn matches_on_results_must_be_exhaustive() {
+    let error = run("let f = func(r) { ret match r { Ok(v) => v }; };").unwrap_err();
+    assert_eq!(error, "Non-exhaustive match: \"Err(_)\" is not matched");
+}
+
+#[test]
+fn annotations_are_checked() {
+    let analysis = Analysis::new(
+        "let a: Result = Ok(1);\nlet b: Option = 5;\nlet c: Option = None;",
+        "test".to_string(),
+    );
+    let errors: Vec<&str> = analysis
+        .errors
+        .iter()
+        .map(|error| error.message())
+        .collect();
+    assert_eq!(
+        errors,
+        ["\"b\" is declared as Option but its value is a number"]
+    );
+}
+
+#[test]
+fn annotations_are_enforced_when_binding() {
+    assert_eq!(
+        run("let a: Result = 5;"),
+        Err("\"a\" is declared as Result but its value is a number".to_string())
+    );
+    assert_eq!(
+        run("let b: Option = Ok(1);"),
+        Err("\"b\" is declared as Option but its value is a Result".to_string())
+    );
+    assert_eq!(
+        run(
+            "let c: Option = None; let d: Result = Err(\"e\"); let e: number = \"x\"; c.is_none();"
+        ),
+        Ok(Object::Bool(true))
+    );
+}
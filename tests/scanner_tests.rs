@@ -0,0 +1,122 @@
+use nd_lang::{
+    parser::parser::Parser,
+    scanner::scanner::Scanner,
+    tokens::{
+        symbol::{Symbol, SymbolScope},
+        token_type::TokenType,
+    },
+};
+
+fn types(source: &str) -> Vec<TokenType> {
+    Scanner::new(source, "test".to_string())
+        .map(|token| token.unwrap().ttype)
+        .collect()
+}
+
+#[test]
+fn the_stream_ends_with_a_single_eof() {
+    assert_eq!(types(""), [TokenType::EOF]);
+    assert_eq!(
+        types("let x = 1;"),
+        [
+            TokenType::LET,
+            TokenType::IDENTIFIER,
+            TokenType::EQUAL,
+            TokenType::NUMBER,
+            TokenType::SEMICOLON,
+            TokenType::EOF
+        ]
+    );
+
+    let mut scanner = Scanner::new("x", "test".to_string());
+    assert_eq!(scanner.next().unwrap().unwrap().lexeme, "x");
+    assert_eq!(scanner.next().unwrap().unwrap().ttype, TokenType::EOF);
+    assert!(scanner.next().is_none());
+    assert!(scanner.next().is_none());
+}
+
+#[test]
+fn strings_can_be_empty() {
+    let lexemes = |source: &str| -> Vec<String> {
+        Scanner::new(source, "test".to_string())
+            .map(|token| token.unwrap().lexeme.to_string())
+            .collect()
+    };
+    assert_eq!(lexemes("\"\";"), ["", ";", ""]);
+    assert_eq!(lexemes("\"\" + \"a\""), ["", "+", "a", ""]);
+    assert_eq!(lexemes("\"two\nlines\""), ["two\nlines", ""]);
+
+    let mut scanner = Scanner::new("\"", "test".to_string());
+    let error = scanner.next().unwrap().unwrap_err();
+    assert_eq!(error.message(), "Unterminated string");
+}
+
+#[test]
+fn tokens_are_scanned_on_demand() {
+    // Nothing past the first token is looked at until it is asked for
+    let mut scanner = Scanner::new("a # b", "test".to_string());
+    assert_eq!(scanner.next().unwrap().unwrap().lexeme, "a");
+    assert!(scanner.comments().is_empty());
+
+    let error = scanner.next().unwrap().unwrap_err();
+    assert_eq!(error.message(), "Unexpected character '#'");
+    // Scanning goes on after an error
+    assert_eq!(scanner.next().unwrap().unwrap().lexeme, "b");
+    assert_eq!(scanner.next().unwrap().unwrap().ttype, TokenType::EOF);
+}
+
+#[test]
+fn the_parser_reports_scanner_errors_in_place() {
+    let mut parser = Parser::new(Scanner::new("let x = 1;\nlet y = @;", "test".to_string()));
+    let statements = parser.parse();
+    assert_eq!(statements.len(), 2);
+    let lines: Vec<usize> = parser.errors().iter().map(|error| error.line()).collect();
+    assert!(!lines.is_empty() && lines.iter().all(|line| *line == 1));
+}
+
+#[test]
+fn lexemes_borrow_from_the_source_and_identifiers_are_interned() {
+    let source = String::from("count + count");
+    let tokens: Vec<_> = Scanner::new(&source, "test".to_string())
+        .map(Result::unwrap)
+        .collect();
+    let (first, last) = (&tokens[0], &tokens[2]);
+    assert_eq!(first.lexeme, "count");
+    // The lexeme is a slice of the source, not a copy
+    assert_eq!(first.lexeme.as_ptr(), source.as_ptr());
+    assert_eq!(first.symbol, Some(Symbol::intern("count")));
+    assert_eq!(first.symbol, last.symbol);
+    assert_eq!(Symbol::intern("count").as_str(), Some("count"));
+    assert_ne!(Symbol::intern("count"), Symbol::intern("total"));
+}
+
+#[test]
+fn names_interned_in_a_scope_stay_in_it() {
+    let shared = Symbol::intern("shared_name");
+    let scope = SymbolScope::new();
+    let (inside, again) = scope.enter(|| {
+        let inside = Symbol::intern("scoped_name");
+        assert_eq!(inside.to_string(), "scoped_name");
+        assert!(inside == "scoped_name");
+        // Names already interned are shared with the scope
+        assert_eq!(Symbol::intern("shared_name"), shared);
+        (inside, Symbol::intern("scoped_name"))
+    });
+    assert_eq!(inside, again);
+    assert_eq!(scope.enter(|| inside.to_string()), "scoped_name");
+    // Scoped names have no 'static string, so the global table never
+    // sees them
+    assert_eq!(scope.enter(|| inside.as_str()), None);
+    assert_ne!(Symbol::intern("scoped_name"), inside);
+
+    // The symbols of two scopes never collide, and a symbol used outside
+    // of its scope matches nothing
+    let other = SymbolScope::new();
+    let elsewhere = other.enter(|| Symbol::intern("other_name"));
+    assert_ne!(elsewhere, inside);
+    assert!(!other.enter(|| inside == "scoped_name"));
+    assert_eq!(other.enter(|| inside.to_string()), "<unknown symbol>");
+    drop(scope);
+    assert!(inside != "scoped_name");
+    assert_eq!(inside.to_string(), "<unknown symbol>");
+}
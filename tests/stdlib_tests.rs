@@ -0,0 +1,80 @@
+mod common;
+
+use common::{run, run_with, Project};
+use nd_lang::{object::object::Object, stdlib::fs::Capabilities};
+
+#[test]
+fn math_functions() {
+    let source = "
+        import \"math\" as math;
+        import { gcd, clamp } from \"math\";
+        math.sqrt(16) + math.max(1, 9, 3) + gcd(12, 18) + clamp(15, 0, 10) + math.floor(math.pi);
+    ";
+    assert_eq!(run(source), Ok(Object::Num(32.0)));
+}
+
+#[test]
+fn math_functions_check_their_arguments() {
+    assert_eq!(
+        run("import \"math\" as math; math.sqrt(\"16\");"),
+        Err("math.sqrt expects a number as argument 1, not string".to_string())
+    );
+    assert_eq!(
+        run("import \"math\" as math; math.gcd(4.5, 3);"),
+        Err("math.gcd expects a whole number as argument 1, not 4.5".to_string())
+    );
+    assert_eq!(
+        run("import \"math\" as math; math.abs(1, 2);"),
+        Err("math.abs expects 1 arguments but got 2".to_string())
+    );
+}
+
+#[test]
+fn string_methods_count_characters() {
+    let source = "
+        let word = \" Grüße \".trim();
+        word.upper() + \"|\" + word.len() + \"|\" + word.substring(2, 4) + \"|\" + word.find(\"e\");
+    ";
+    assert_eq!(run(source), Ok(Object::Str("GRÜSSE|5|üß|4".to_string())));
+}
+
+#[test]
+fn strings_split_and_join() {
+    let source = "
+        import \"string\" as string;
+        let parts = string.split(\"a-b-c\", \"-\");
+        \"+\".join(parts) + parts[2] + \"xy\".chars()[1];
+    ";
+    assert_eq!(run(source), Ok(Object::Str("a+b+ccy".to_string())));
+}
+
+#[test]
+fn files_are_only_reachable_where_allowed() {
+    let root = Project::new(&[("secret.txt", "secret")]);
+    std::fs::create_dir(root.join("data")).unwrap();
+    let mut capabilities = Capabilities::default();
+    capabilities.allow_read(root.join("data"));
+    capabilities.allow_write(root.join("data"));
+
+    let data = root.join("data").display().to_string();
+    let source = format!(
+        "import \"fs\" as fs;
+        fs.write_file(\"{data}/notes.txt\", \"a\");
+        fs.append_file(\"{data}/notes.txt\", \"b\");
+        fs.read_file(\"{data}/notes.txt\") + fs.list_dir(\"{data}\")[0];"
+    );
+    assert_eq!(
+        run_with(&source, capabilities.clone()),
+        Ok(Object::Str("abnotes.txt".to_string()))
+    );
+
+    let escape = format!("import \"fs\" as fs; fs.read_file(\"{data}/../secret.txt\");");
+    assert!(run_with(&escape, capabilities.clone())
+        .unwrap_err()
+        .starts_with("fs.read_file is not allowed to read"));
+    let missing = format!("import \"fs\" as fs; fs.read_file(\"{data}/missing.txt\");");
+    assert!(run_with(&missing, capabilities)
+        .unwrap_err()
+        .starts_with("fs.read_file failed on"));
+    assert!(run(&format!("import \"fs\" as fs; fs.exists(\"{data}\");")).is_err());
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use std::path::Path;
+
+use common::Project;
+use nd_lang::{
+    object::object::Object,
+    testing::runner::{discover, TestResult, TestRunner},
+    Engine,
+};
+
+fn run_tests(runner: &TestRunner, root: &Path) -> Vec<TestResult> {
+    discover(&[root.to_path_buf()])
+        .unwrap()
+        .iter()
+        .flat_map(|file| runner.run_file(file).unwrap())
+        .collect()
+}
+
+fn failure(source: &str) -> String {
+    match Engine::new().eval(source) {
+        Ok(value) => panic!("expected an error, got {:?}", value),
+        Err(error) => error.diagnostics()[0].message().to_string(),
+    }
+}
+
+#[test]
+fn assertions_fail_with_the_values_compared() {
+    let mut engine = Engine::new();
+    assert_eq!(
+        engine
+            .eval("assert(true); assert_eq([1, 2], [1, 2]);")
+            .unwrap(),
+        Object::Nil
+    );
+    assert_eq!(
+        failure("assert(1 > 2, \"too small\");"),
+        "Assertion failed: too small"
+    );
+    assert_eq!(
+        failure("assert_eq([1, 2, 3], [1, 5]);"),
+        "assert_eq failed\n  expected: [1, 5]\n    actual: [1, 2, 3]\n  at [1]: expected 5, got 2\n  expected 2 items, got 3"
+    );
+    assert_eq!(
+        failure("assert_eq(\"1\", 1);"),
+        "assert_eq failed\n  expected: 1\n    actual: \"1\"\n  expected a number, got a string"
+    );
+}
+
+#[test]
+fn assert_throws_returns_the_error() {
+    let mut engine = Engine::new();
+    let source = "
+        let error = assert_throws(func() { throw Error(\"bad\", \"ValueError\"); }, \"ValueError\");
+        error.message;
+    ";
+    assert_eq!(engine.eval(source).unwrap(), Object::Str("bad".to_string()));
+    assert_eq!(
+        failure("assert_throws(func() { ret 1; });"),
+        "assert_throws expected an error but the function returned 1"
+    );
+    assert_eq!(
+        failure("assert_throws(func() { ret 1 / 0; }, \"TypeError\");"),
+        "assert_throws expected a TypeError but got ZeroDivisionError: Division by zero"
+    );
+    // Failures can be caught like any other error
+    let source = "
+        let kind = \"none\";
+        try { assert(false); } catch (error) { kind = error.kind; }
+        kind;
+    ";
+    assert_eq!(
+        engine.eval(source).unwrap(),
+        Object::Str("AssertionError".to_string())
+    );
+}
+
+#[test]
+fn tests_run_in_isolation() {
+    let root = Project::new(&[(
+        "count_test.ndl",
+        "let count = 0;
+            test \"changes the count\" { count = count + 1; assert_eq(count, 1); }
+            test \"sees a fresh count\" { count = count + 1; assert_eq(count, 1); }
+            test \"fails\" { assert_eq(count, 2); }",
+    )]);
+    let results = run_tests(&TestRunner::new(), &root);
+    let outcomes: Vec<(&str, bool)> = results
+        .iter()
+        .map(|test| (test.name.as_str(), test.passed()))
+        .collect();
+    assert_eq!(
+        outcomes,
+        [
+            ("changes the count", true),
+            ("sees a fresh count", true),
+            ("fails", false)
+        ]
+    );
+    let failure = results[2].failure.as_ref().unwrap();
+    assert_eq!(failure.kind(), Some("AssertionError"));
+    assert_eq!(results[2].line, 4);
+}
+
+#[test]
+fn only_test_files_are_discovered() {
+    let root = Project::new(&[
+        ("a_test.ndl", "test \"a\" {}"),
+        ("helpers.ndl", "test \"not run\" { assert(false); }"),
+        ("nested/b_test.ndl", "test \"b\" {}"),
+    ]);
+    let names: Vec<String> = discover(&[root.to_path_buf()])
+        .unwrap()
+        .iter()
+        .map(|file| file.strip_prefix(&*root).unwrap().display().to_string())
+        .collect();
+    assert_eq!(names, ["a_test.ndl", "nested/b_test.ndl"]);
+}
+
+#[test]
+fn tests_are_filtered_by_name() {
+    let root = Project::new(&[(
+        "math_test.ndl",
+        "test \"adds numbers\" { assert_eq(1 + 1, 2); }
+            test \"subtracts numbers\" { assert_eq(2 - 1, 1); }
+            test \"adds lists\" { assert_eq([1] + [2], [1, 2]); }",
+    )]);
+    let mut runner = TestRunner::new();
+    runner.set_filter("adds");
+    let names: Vec<String> = run_tests(&runner, &root)
+        .into_iter()
+        .map(|test| test.name)
+        .collect();
+    assert_eq!(names, ["adds numbers", "adds lists"]);
+}